@@ -0,0 +1,161 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! End-to-end example of a contract whose read-once memory cells are owned by single-use seals.
+//!
+//! The seals here are modelled after bitcoin transaction outputs: a seal is closed by a
+//! transaction spending the output and committing to the operation id.
+
+#[macro_use]
+extern crate amplify;
+extern crate zkaluvm as aluvm;
+
+use aluvm::isa::CtrlInstr;
+use aluvm::{Lib, LibId, LibSite};
+use amplify::confinement::{SmallVec, TinyString};
+use amplify::num::u256;
+use strict_encoding::TypeName;
+use ultrasonic::seals::{check_seals, seal_auth_token, SealWitness, SingleUseSeal};
+use ultrasonic::{
+    fe256, ApplyState, AuthToken, CallId, CellAddr, CellLock, Codex, ContractDraft, ContractName,
+    Input, Instr, LibRepo, Operation, Opid, ReplayMemory, StateCell, StateValue, Timestamp,
+    FIELD_ORDER_SECP,
+};
+
+const SEAL_TAG: &str = "urn:example:seal:outpoint#2025-01-01";
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Outpoint {
+    txid: [u8; 32],
+    vout: u32,
+}
+
+impl SingleUseSeal for Outpoint {
+    fn auth_token(&self) -> AuthToken {
+        let mut data = self.txid.to_vec();
+        data.extend(self.vout.to_le_bytes());
+        seal_auth_token(SEAL_TAG, &data)
+    }
+}
+
+/// Simplified witness transaction: the outputs it spends and the message it commits to.
+struct WitnessTx {
+    spends: Vec<Outpoint>,
+    commitment: Opid,
+}
+
+impl SealWitness<Outpoint> for WitnessTx {
+    type Error = &'static str;
+
+    fn verify_seals_closed(&self, seals: &[Outpoint], message: Opid) -> Result<(), Self::Error> {
+        if self.commitment != message {
+            return Err("transaction commits to a different operation");
+        }
+        if !seals.iter().all(|seal| self.spends.contains(seal)) {
+            return Err("transaction doesn't spend all the seals");
+        }
+        Ok(())
+    }
+}
+
+struct Libs(Lib);
+
+impl LibRepo for Libs {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        (self.0.lib_id() == lib_id).then_some(&self.0)
+    }
+}
+
+fn main() {
+    let call_id = CallId::new(0);
+    let lib = Lib::assemble::<Instr<LibId>>(&[CtrlInstr::Stop.into()]).unwrap();
+    let codex = Codex {
+        version: default!(),
+        name: TinyString::from_checked(s!("Sealed")),
+        developer: "ssi:example".into(),
+        timestamp: Timestamp::from_unchecked(Timestamp::MIN),
+        field_order: FIELD_ORDER_SECP,
+        input_config: default!(),
+        verification_config: default!(),
+        verifiers: tiny_bmap! { call_id => LibSite::new(lib.lib_id(), 0) },
+        flags: default!(),
+        reserved: default!(),
+    };
+    let libs = Libs(lib);
+
+    // Genesis assigns the state to the first seal
+    let seal = Outpoint { txid: [1; 32], vout: 0 };
+    let draft = ContractDraft {
+        testnet: true,
+        name: ContractName::Named(TypeName::from("Sealed")),
+        issuer: "ssi:example".into(),
+        codex,
+        call_id,
+        destructible: SmallVec::from_checked(vec![cell(100, &seal)]),
+        immutable: none!(),
+    };
+    let contract =
+        draft.seal::<0>(Timestamp::from_unchecked(Timestamp::MIN), fe256::from(u256::ZERO));
+    let contract_id = contract.contract_id();
+    let mut memory = ReplayMemory::from_history(contract_id, &contract.genesis, []).unwrap();
+
+    // The owner of the seal transfers the state to a new seal
+    let next_seal = Outpoint { txid: [2; 32], vout: 1 };
+    let operation = Operation {
+        contract_id,
+        call_id,
+        nonce: fe256::from(u256::ONE),
+        destroying: small_vec![Input {
+            addr: CellAddr::new(contract.genesis_opid(), 0),
+            witness: StateValue::None,
+        }],
+        reading: none!(),
+        destructible: small_vec![cell(100, &next_seal)],
+        immutable: none!(),
+        witness_commitment: None,
+        read_intents: none!(),
+        reserved: default!(),
+    };
+
+    // The seal is closed by a transaction committing to the operation
+    let witness = WitnessTx { spends: vec![seal], commitment: operation.opid() };
+    check_seals(&operation, &memory, &[seal], &witness).expect("seal is not closed");
+
+    let verified = contract
+        .codex
+        .verify(contract_id, operation, &memory, &libs)
+        .expect("invalid operation");
+    memory.apply(verified).expect("operation can't be applied");
+
+    let (addr, cell) = memory.read_once_cells().next().unwrap();
+    assert_eq!(cell.auth, next_seal.auth_token());
+    println!("state is now owned by the seal {next_seal:?} at {addr:?}");
+}
+
+fn cell(amount: u64, seal: &Outpoint) -> StateCell {
+    StateCell {
+        data: StateValue::from([u256::from(amount)]),
+        auth: seal.auth_token(),
+        lock: CellLock::None,
+    }
+}
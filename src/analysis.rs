@@ -0,0 +1,203 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Static analysis of the verifier code.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use aluvm::isa::{CtrlInstr, Instruction};
+use aluvm::{Lib, LibId, LibSite};
+
+use crate::{Codex, Instr, LibRepo, UsonicInstr};
+
+/// Call graph of the code reachable from a verifier entry point.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CallGraph {
+    /// Offsets of the reachable instructions, by the library.
+    pub reachable: BTreeMap<LibId, BTreeSet<u16>>,
+    /// Libraries other than the one of the entry point, which are called or jumped into.
+    pub external_libs: BTreeSet<LibId>,
+    /// Libraries absent in the library repository or which code can't be decoded.
+    pub missing_libs: BTreeSet<LibId>,
+    /// Control flow targets not pointing to the start of an instruction.
+    pub invalid_targets: BTreeSet<(LibId, u16)>,
+    /// Whether the code contains backward jumps or a routine called more than once, which makes
+    /// the worst-case complexity dependent on the data.
+    pub has_loops: bool,
+    /// Sum of the complexity of all the reachable instructions.
+    pub total_complexity: u64,
+}
+
+impl CallGraph {
+    /// Worst-case complexity of the verifier execution.
+    ///
+    /// The estimation is available only for the code without loops and repeated routine calls,
+    /// where each of the instructions gets executed at most once, and when all the libraries are
+    /// known; otherwise, the execution is bounded only by the VM complexity limit.
+    pub fn worst_case_complexity(&self) -> Option<u64> {
+        if self.has_loops || !self.missing_libs.is_empty() || !self.invalid_targets.is_empty() {
+            return None;
+        }
+        Some(self.total_complexity)
+    }
+
+    /// Checks whether the verifier provably terminates within the complexity `limit`.
+    pub fn fits(&self, limit: u64) -> bool {
+        self.worst_case_complexity()
+            .map(|complexity| complexity <= limit)
+            .unwrap_or_default()
+    }
+
+    /// Returns ids of all the libraries referenced by the code, including the missing ones.
+    pub fn lib_ids(&self) -> BTreeSet<LibId> {
+        self.reachable
+            .keys()
+            .chain(&self.external_libs)
+            .chain(&self.missing_libs)
+            .copied()
+            .collect()
+    }
+}
+
+impl Codex {
+    /// Returns ids of all the libraries which may be used by the codex verifiers, as found by the
+    /// call graph analysis of their entry points.
+    ///
+    /// The libraries of the lock scripts are not included, since they are defined by the memory
+    /// cells and not by the codex.
+    pub fn required_libs(&self, repo: &impl LibRepo) -> BTreeSet<LibId> {
+        self.verifiers
+            .values()
+            .flat_map(|entry| call_graph(*entry, repo).lib_ids())
+            .collect()
+    }
+}
+
+/// Extracts the call graph of the code reachable from the `entry` point.
+pub fn call_graph(entry: LibSite, repo: &impl LibRepo) -> CallGraph {
+    let mut graph = CallGraph::default();
+    let mut libs = BTreeMap::<LibId, Option<Disassembly>>::new();
+    let mut calls = BTreeSet::new();
+    let mut queue = VecDeque::from([(entry.prog_id, entry.offset, true)]);
+
+    while let Some((lib_id, offset, call)) = queue.pop_front() {
+        if lib_id != entry.prog_id {
+            graph.external_libs.insert(lib_id);
+        }
+        if call && !calls.insert((lib_id, offset)) {
+            // A routine called more than once
+            graph.has_loops = true;
+            continue;
+        }
+        let disasm = libs
+            .entry(lib_id)
+            .or_insert_with(|| repo.get_lib(lib_id).and_then(Disassembly::with));
+        let Some(disasm) = disasm else {
+            graph.missing_libs.insert(lib_id);
+            continue;
+        };
+        let Some(mut no) = disasm.offsets.iter().position(|pos| *pos == offset) else {
+            graph.invalid_targets.insert((lib_id, offset));
+            continue;
+        };
+
+        let reachable = graph.reachable.entry(lib_id).or_default();
+        while let Some(instr) = disasm.code.get(no) {
+            let pos = disasm.offsets[no];
+            if !reachable.insert(pos) {
+                // Merge with the already analyzed code
+                break;
+            }
+            graph.total_complexity = graph
+                .total_complexity
+                .saturating_add(Instruction::<LibId>::complexity(instr));
+
+            let next = disasm.offsets.get(no + 1).copied();
+            let local = |target: Option<u16>| target.map(|target| (lib_id, target, false));
+            let remote = |site: &LibSite| Some((site.prog_id, site.offset, false));
+            let call = |site: &LibSite| Some((site.prog_id, site.offset, true));
+            let (target, proceed) = match instr {
+                Instr::Ctrl(CtrlInstr::Jmp { pos }) => (local(Some(*pos)), false),
+                Instr::Ctrl(CtrlInstr::JiNe { pos } | CtrlInstr::JiFail { pos }) => {
+                    (local(Some(*pos)), true)
+                }
+                Instr::Ctrl(CtrlInstr::Sh { shift }) => (local(relative(next, *shift)), false),
+                Instr::Ctrl(CtrlInstr::ShNe { shift } | CtrlInstr::ShFail { shift }) => {
+                    (local(relative(next, *shift)), true)
+                }
+                Instr::Ctrl(CtrlInstr::Fn { pos }) => (call(&LibSite::new(lib_id, *pos)), true),
+                Instr::Ctrl(CtrlInstr::Call { site }) => (call(site), true),
+                Instr::Ctrl(CtrlInstr::Exec { site }) => (remote(site), false),
+                Instr::Ctrl(CtrlInstr::Ret | CtrlInstr::Stop) => (None, false),
+                Instr::Usonic(
+                    UsonicInstr::NxIRo(site)
+                    | UsonicInstr::NxIIm(site)
+                    | UsonicInstr::NxORo(site)
+                    | UsonicInstr::NxOIm(site),
+                ) => {
+                    // The routine is called once per memory cell
+                    graph.has_loops = true;
+                    (call(site), true)
+                }
+                _ => (None, true),
+            };
+            if let Some((target_lib, target_pos, is_call)) = target {
+                if !is_call && target_lib == lib_id && target_pos <= pos {
+                    graph.has_loops = true;
+                }
+                queue.push_back((target_lib, target_pos, is_call));
+            }
+            if !proceed {
+                break;
+            }
+            no += 1;
+        }
+    }
+
+    graph
+}
+
+/// Resolves target of a relative jump, which is counted from the next instruction.
+fn relative(next: Option<u16>, shift: i8) -> Option<u16> {
+    let next = next?;
+    u16::try_from(next as i32 + shift as i32).ok()
+}
+
+/// Library code decoded into instructions, with their offsets.
+struct Disassembly {
+    code: Vec<Instr<LibId>>,
+    offsets: Vec<u16>,
+}
+
+impl Disassembly {
+    fn with(lib: &Lib) -> Option<Self> {
+        let code = lib.disassemble::<Instr<LibId>>().ok()?;
+        // The offset of an instruction is the length of the code preceding it
+        let mut offsets = Vec::with_capacity(code.len());
+        for no in 0..code.len() {
+            let prefix = Lib::assemble(&code[..no]).ok()?;
+            offsets.push(u16::try_from(prefix.code.len()).ok()?);
+        }
+        Some(Self { code, offsets })
+    }
+}
@@ -0,0 +1,181 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Interface for the evidence of operations being anchored to a consensus layer.
+//!
+//! The crate doesn't verify anchors itself: concrete proofs (like transaction inclusion proofs)
+//! are implemented downstream with the [`AnchorProof`] trait, while [`AnchorPolicy`] allows
+//! requiring the evidence during the operation verification.
+
+use std::collections::BTreeMap;
+
+use crate::{CallError, Consensus, Operation, Opid, VerifyPolicy};
+
+/// Proof that an operation is anchored to a specific consensus layer.
+pub trait AnchorProof {
+    /// Consensus layer the proof is produced for.
+    fn consensus(&self) -> Consensus;
+
+    /// Verifies that the proof anchors the operation with the given id.
+    fn verify(&self, opid: Opid) -> bool;
+}
+
+/// Verification policy requiring each of the verified operations to have a valid anchor proof
+/// for the specific consensus layer.
+///
+/// Operations lacking the evidence are rejected with a [`CallError::Policy`] error carrying
+/// [`AnchorError::code`].
+#[derive(Clone, Debug)]
+pub struct AnchorPolicy<'p, P: AnchorProof> {
+    consensus: Consensus,
+    proofs: &'p BTreeMap<Opid, P>,
+    error: Option<AnchorError>,
+}
+
+impl<'p, P: AnchorProof> AnchorPolicy<'p, P> {
+    pub fn new(consensus: Consensus, proofs: &'p BTreeMap<Opid, P>) -> Self {
+        Self { consensus, proofs, error: None }
+    }
+
+    pub fn consensus(&self) -> Consensus { self.consensus }
+
+    /// Checks that the operation with `opid` has a valid anchor proof.
+    pub fn check(&self, opid: Opid) -> Result<(), AnchorError> {
+        let proof = self.proofs.get(&opid).ok_or(AnchorError::Missing(opid))?;
+        if proof.consensus() != self.consensus {
+            return Err(AnchorError::Consensus {
+                opid,
+                expected: self.consensus,
+                found: proof.consensus(),
+            });
+        }
+        if !proof.verify(opid) {
+            return Err(AnchorError::Invalid(opid));
+        }
+        Ok(())
+    }
+
+    /// Returns the detailed error of the last rejected operation.
+    pub fn last_error(&self) -> Option<AnchorError> { self.error }
+}
+
+impl<P: AnchorProof> VerifyPolicy for AnchorPolicy<'_, P> {
+    fn check_operation(&mut self, operation: &Operation) -> Result<(), CallError> {
+        self.check(operation.opid()).map_err(|err| {
+            self.error = Some(err);
+            CallError::Policy(err.code())
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AnchorError {
+    #[cfg_attr(feature = "baid64", display = "operation {0} has no anchor proof.")]
+    #[cfg_attr(not(feature = "baid64"), display = "operation {0:?} has no anchor proof.")]
+    Missing(Opid),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "anchor proof of operation {opid} is for {found} while {expected} is required."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "anchor proof of operation {opid:?} is for {found} while {expected} is required."
+    )]
+    Consensus {
+        opid: Opid,
+        expected: Consensus,
+        found: Consensus,
+    },
+
+    #[cfg_attr(feature = "baid64", display = "anchor proof of operation {0} is invalid.")]
+    #[cfg_attr(not(feature = "baid64"), display = "anchor proof of operation {0:?} is invalid.")]
+    Invalid(Opid),
+}
+
+impl AnchorError {
+    /// Error code reported inside [`CallError::Policy`].
+    pub const fn code(&self) -> u32 {
+        match self {
+            AnchorError::Missing(_) => 0x0101,
+            AnchorError::Consensus { .. } => 0x0102,
+            AnchorError::Invalid(_) => 0x0103,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct MockProof(Consensus, Opid);
+
+    impl AnchorProof for MockProof {
+        fn consensus(&self) -> Consensus { self.0 }
+        fn verify(&self, opid: Opid) -> bool { self.1 == opid }
+    }
+
+    #[test]
+    fn requires_proof() {
+        let anchored = Opid::from([1u8; 32]);
+        let liquid = Opid::from([2u8; 32]);
+        let forged = Opid::from([3u8; 32]);
+        let missing = Opid::from([4u8; 32]);
+        let mut proofs = BTreeMap::new();
+        proofs.insert(anchored, MockProof(Consensus::Bitcoin, anchored));
+        proofs.insert(liquid, MockProof(Consensus::Liquid, liquid));
+        proofs.insert(forged, MockProof(Consensus::Bitcoin, anchored));
+
+        let policy = AnchorPolicy::new(Consensus::Bitcoin, &proofs);
+        assert_eq!(policy.check(anchored), Ok(()));
+        assert_eq!(
+            policy.check(liquid),
+            Err(AnchorError::Consensus {
+                opid: liquid,
+                expected: Consensus::Bitcoin,
+                found: Consensus::Liquid
+            })
+        );
+        assert_eq!(policy.check(forged), Err(AnchorError::Invalid(forged)));
+        assert_eq!(policy.check(missing), Err(AnchorError::Missing(missing)));
+    }
+
+    #[test]
+    fn policy_hook() {
+        use strict_encoding::StrictDumb;
+
+        let op = Operation::strict_dumb();
+        let mut proofs = BTreeMap::new();
+        let mut policy = AnchorPolicy::new(Consensus::Prime, &proofs);
+        assert_eq!(
+            policy.check_operation(&op),
+            Err(CallError::Policy(AnchorError::Missing(op.opid()).code()))
+        );
+        assert_eq!(policy.last_error(), Some(AnchorError::Missing(op.opid())));
+
+        proofs.insert(op.opid(), MockProof(Consensus::Prime, op.opid()));
+        let mut policy = AnchorPolicy::new(Consensus::Prime, &proofs);
+        assert_eq!(policy.check_operation(&op), Ok(()));
+    }
+}
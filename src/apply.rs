@@ -0,0 +1,131 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeSet;
+
+use crate::{CellAddr, LiveState, Memory, VerifiedOperation, VerifyJournalEntry};
+
+/// Contract state which can be updated by applying verified operations to it.
+pub trait ApplyState: Memory {
+    /// Applies the operation to the state without checking any preconditions.
+    ///
+    /// Implementations must remove all memory cells destroyed by the operation and add all the
+    /// memory cells it creates, and must remember the operation [`crate::Height`], which is
+    /// later reported by [`Memory::height`].
+    fn apply_unchecked(&mut self, op: VerifiedOperation);
+
+    /// Checks that the operation can be applied to the current state: all memory cells it destroys
+    /// are live and are not destroyed twice, and none of the memory cells it creates already
+    /// exist.
+    fn check_apply(&self, op: &VerifiedOperation) -> Result<(), ApplyError> {
+        let operation = op.as_operation();
+
+        let mut destroyed = BTreeSet::new();
+        for input in &operation.destroying {
+            if !destroyed.insert(input.addr) {
+                return Err(ApplyError::DoubleSpend(input.addr));
+            }
+            if self.read_once(input.addr).is_none() {
+                return Err(ApplyError::NotLive(input.addr));
+            }
+        }
+
+        for (addr, _) in op.destructible_cells() {
+            if self.read_once(addr).is_some() {
+                return Err(ApplyError::ReadOnceExists(addr));
+            }
+        }
+        for (addr, _) in op.immutable_cells() {
+            if self.immutable(addr).is_some() {
+                return Err(ApplyError::ImmutableExists(addr));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the operation to the state after checking preconditions with
+    /// [`Self::check_apply`].
+    ///
+    /// If the check fails, the state is left unmodified.
+    fn apply(&mut self, op: VerifiedOperation) -> Result<(), ApplyError> {
+        self.check_apply(&op)?;
+        self.apply_unchecked(op);
+        Ok(())
+    }
+
+    /// Applies the operation to the state like [`Self::apply`], returning the journal entry
+    /// describing the application.
+    ///
+    /// Nodes using a write-ahead journal must persist the entry before modifying the state; they
+    /// should construct it with [`VerifyJournalEntry::new`] and then call [`Self::apply`].
+    fn apply_journaled(&mut self, op: VerifiedOperation) -> Result<VerifyJournalEntry, ApplyError>
+    where Self: LiveState {
+        let entry = VerifyJournalEntry::new(&*self, &op)?;
+        self.apply_unchecked(op);
+        Ok(entry)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ApplyError {
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation destroys memory cell {0} which is not live (was never created or is \
+                   already destroyed)."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation destroys memory cell {0:?} which is not live (was never created or \
+                   is already destroyed)."
+    )]
+    NotLive(CellAddr),
+
+    #[cfg_attr(feature = "baid64", display = "operation destroys memory cell {0} more than once.")]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation destroys memory cell {0:?} more than once."
+    )]
+    DoubleSpend(CellAddr),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation creates read-once memory cell {0} which already exists."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation creates read-once memory cell {0:?} which already exists."
+    )]
+    ReadOnceExists(CellAddr),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation creates immutable memory cell {0} which already exists."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation creates immutable memory cell {0:?} which already exists."
+    )]
+    ImmutableExists(CellAddr),
+}
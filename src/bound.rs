@@ -0,0 +1,181 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use crate::{AuthToken, CellAddr, CellPosError, ContractId, Operation, Opid};
+
+/// Value bound to a specific contract.
+///
+/// Identifiers like [`Opid`], [`CellAddr`] and [`AuthToken`] don't carry the contract they belong
+/// to, so they can be mixed up between contracts without a notice. Bound values can be combined
+/// only with the values bound to the same contract, and are unwrapped with a check of the
+/// contract id at the trust boundaries.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct Bound<T> {
+    contract_id: ContractId,
+    value: T,
+}
+
+/// Operation id bound to a contract.
+pub type BoundOpid = Bound<Opid>;
+/// Memory cell address bound to a contract.
+pub type BoundAddr = Bound<CellAddr>;
+/// Token of authority bound to a contract.
+pub type BoundAuth = Bound<AuthToken>;
+
+impl<T> Bound<T> {
+    /// Binds the value to the contract.
+    ///
+    /// The caller is responsible for the value really belonging to the contract; this should be
+    /// done only at the trust boundaries, like when the value is received together with the
+    /// contract id from a user or a peer.
+    pub fn new(contract_id: ContractId, value: T) -> Self { Self { contract_id, value } }
+
+    /// Returns the id of the contract the value is bound to.
+    pub fn contract_id(&self) -> ContractId { self.contract_id }
+
+    /// Returns the value without checking the contract it is bound to.
+    pub fn as_unchecked(&self) -> &T { &self.value }
+
+    /// Releases the value without checking the contract it is bound to.
+    pub fn into_unchecked(self) -> T { self.value }
+
+    /// Returns the value, checking that it is bound to the `contract_id`.
+    pub fn get(&self, contract_id: ContractId) -> Result<&T, BoundError> {
+        self.check(contract_id).map(|_| &self.value)
+    }
+
+    /// Releases the value, checking that it is bound to the `contract_id`.
+    pub fn into_inner(self, contract_id: ContractId) -> Result<T, BoundError> {
+        self.check(contract_id).map(|_| self.value)
+    }
+
+    /// Checks that the value is bound to the `contract_id`.
+    pub fn check(&self, contract_id: ContractId) -> Result<(), BoundError> {
+        if self.contract_id != contract_id {
+            return Err(BoundError { expected: contract_id, found: self.contract_id });
+        }
+        Ok(())
+    }
+
+    /// Converts the value, keeping it bound to the same contract.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Bound<U> {
+        Bound { contract_id: self.contract_id, value: f(self.value) }
+    }
+
+    /// Combines the value with another value, failing if they are bound to different contracts.
+    pub fn zip<U>(self, other: Bound<U>) -> Result<Bound<(T, U)>, BoundError> {
+        other.check(self.contract_id)?;
+        Ok(Bound {
+            contract_id: self.contract_id,
+            value: (self.value, other.value),
+        })
+    }
+}
+
+impl Bound<Opid> {
+    /// Returns the address of the memory cell at position `pos` created by the operation.
+    pub fn cell(self, pos: u16) -> Bound<CellAddr> { self.map(|opid| CellAddr::new(opid, pos)) }
+}
+
+impl Bound<CellAddr> {
+    /// Constructs the address of a read-once memory cell created by the operation, binding it to
+    /// the operation contract (see [`CellAddr::destructible_of`]).
+    pub fn destructible_of(operation: &Operation, pos: u16) -> Result<Self, CellPosError> {
+        CellAddr::destructible_of(operation, pos).map(|addr| Self::new(operation.contract_id, addr))
+    }
+
+    /// Constructs the address of an immutable memory cell created by the operation, binding it to
+    /// the operation contract (see [`CellAddr::immutable_of`]).
+    pub fn immutable_of(operation: &Operation, pos: u16) -> Result<Self, CellPosError> {
+        CellAddr::immutable_of(operation, pos).map(|addr| Self::new(operation.contract_id, addr))
+    }
+
+    /// Returns the id of the operation which has created the memory cell.
+    pub fn opid(&self) -> Bound<Opid> { Bound::new(self.contract_id, self.value.opid) }
+}
+
+impl Operation {
+    /// Returns the operation id bound to the operation contract.
+    pub fn bound_opid(&self) -> Bound<Opid> { Bound::new(self.contract_id, self.opid()) }
+
+    /// Returns the value bound to a contract, checking that it is the contract of the operation.
+    pub fn check_bound<'v, T>(&self, value: &'v Bound<T>) -> Result<&'v T, BoundError> {
+        value.get(self.contract_id)
+    }
+}
+
+/// Error combining values bound to different contracts.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[cfg_attr(
+    feature = "baid64",
+    display = "value bound to contract {found} is used with contract {expected}."
+)]
+#[cfg_attr(not(feature = "baid64"), display = "value bound to a different contract is used.")]
+pub struct BoundError {
+    /// Contract with which the value is used.
+    pub expected: ContractId,
+    /// Contract to which the value is bound.
+    pub found: ContractId,
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn refuses_mixing() {
+        let first = ContractId::from([1; 32]);
+        let second = ContractId::from([2; 32]);
+        let opid = Bound::new(first, Opid::from([0xAB; 32]));
+        let auth = Bound::new(second, AuthToken::from([0xCD; 30]));
+
+        let addr = opid.cell(1);
+        assert_eq!(addr.opid(), opid);
+        assert_eq!(addr.get(first), Ok(&CellAddr::new(Opid::from([0xAB; 32]), 1)));
+        assert_eq!(addr.get(second), Err(BoundError { expected: second, found: first }));
+        assert_eq!(addr.zip(auth), Err(BoundError { expected: first, found: second }));
+        let pair = addr.zip(Bound::new(first, auth.into_unchecked())).unwrap();
+        assert_eq!(pair.contract_id(), first);
+    }
+
+    #[test]
+    fn operation_cells() {
+        let mut op = Operation {
+            contract_id: ContractId::from([1; 32]),
+            ..Operation::strict_dumb()
+        };
+        op.destructible
+            .push(crate::StateCell::strict_dumb())
+            .unwrap();
+        let addr = BoundAddr::destructible_of(&op, 0).unwrap();
+        assert_eq!(addr.opid(), op.bound_opid());
+        assert_eq!(op.check_bound(&addr), Ok(&CellAddr::new(op.opid(), 0)));
+        assert!(BoundAddr::immutable_of(&op, 0).is_err());
+
+        let foreign = Bound::new(ContractId::from([2; 32]), *addr.as_unchecked());
+        assert!(op.check_bound(&foreign).is_err());
+    }
+}
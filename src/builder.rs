@@ -0,0 +1,363 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Incremental construction of contract operations.
+
+use aluvm::fe256;
+use amplify::confinement::{SmallVec, U16};
+use amplify::num::u256;
+
+use crate::{
+    CallId, CellAddr, Codex, ContractId, Input, Operation, ReadIntent, StateCell, StateData,
+    StateValue,
+};
+
+/// Builder constructing an [`Operation`] with a fluent interface.
+///
+/// The builder keeps the operation fields in unconfined collections and checks them only when
+/// the operation is [finished](Self::finish), such that the calls can be chained. All reserved
+/// fields are set to zero.
+///
+/// # Example
+///
+/// ```
+/// use amplify::num::u256;
+/// use ultrasonic::{
+///     AuthToken, CallId, CellAddr, CellLock, ContractId, OperationBuilder, StateCell, StateData,
+///     StateValue,
+/// };
+///
+/// let contract_id = ContractId::from([0xAB; 32]);
+/// let spent = CellAddr::new([0xCD; 32].into(), 0);
+/// let op = OperationBuilder::new(contract_id, CallId::from(1u16))
+///     .destroy(spent, StateValue::None)
+///     .create(StateCell {
+///         data: StateValue::from([u256::from(10u8)]),
+///         auth: AuthToken::from([0xEF; 30]),
+///         lock: CellLock::None,
+///     })
+///     .write(StateData::new(StateValue::from([u256::ONE])))
+///     .witness(spent, StateValue::from([u256::from(7u8)]))
+///     .finish()
+///     .unwrap();
+/// assert_eq!(op.destroying[0].witness, StateValue::from([u256::from(7u8)]));
+/// let _opid = op.opid();
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct OperationBuilder {
+    contract_id: ContractId,
+    call_id: CallId,
+    nonce: fe256,
+    destroying: Vec<Input>,
+    reading: Vec<CellAddr>,
+    destructible: Vec<StateCell>,
+    immutable: Vec<StateData>,
+    witness_commitment: Option<fe256>,
+    read_intents: Vec<ReadIntent>,
+    error: Option<BuildError>,
+}
+
+impl OperationBuilder {
+    /// Starts building an operation of the contract calling the codex verifier with `call_id`.
+    ///
+    /// The nonce defaults to zero.
+    pub fn new(contract_id: ContractId, call_id: CallId) -> Self {
+        Self {
+            contract_id,
+            call_id,
+            nonce: fe256::from(u256::ZERO),
+            destroying: vec![],
+            reading: vec![],
+            destructible: vec![],
+            immutable: vec![],
+            witness_commitment: None,
+            read_intents: vec![],
+            error: None,
+        }
+    }
+
+    pub fn nonce(mut self, nonce: fe256) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Adds a read-once memory cell destroyed by the operation together with its witness.
+    pub fn destroy(mut self, addr: CellAddr, witness: StateValue) -> Self {
+        if self.destroying.iter().any(|input| input.addr == addr) {
+            self.fail(BuildError::DuplicateInput(addr));
+        }
+        self.destroying.push(Input { addr, witness });
+        self
+    }
+
+    /// Adds a read-once memory cell created at position `pos` by another, possibly not yet
+    /// applied, operation, together with its witness.
+    ///
+    /// The address of the cell is computed from the id of the `operation`; an error is reported
+    /// if the operation has no read-once output at `pos`. The witness must satisfy the lock of the
+    /// spent cell, which can be checked with [`Codex::estimate_lock_cost`].
+    pub fn spend_from(mut self, operation: &Operation, pos: u16, witness: StateValue) -> Self {
+        let addr = CellAddr::new(operation.opid(), pos);
+        if operation.destructible_at(pos).is_none() {
+            self.fail(BuildError::UnknownOutput(addr));
+        }
+        self.destroy(addr, witness)
+    }
+
+    /// Replaces the witness of a previously added destroyed memory cell.
+    pub fn witness(mut self, addr: CellAddr, witness: StateValue) -> Self {
+        match self.destroying.iter_mut().find(|input| input.addr == addr) {
+            Some(input) => input.witness = witness,
+            None => self.fail(BuildError::UnknownInput(addr)),
+        }
+        self
+    }
+
+    /// Adds an immutable memory cell read by the operation.
+    pub fn read(mut self, addr: CellAddr) -> Self {
+        self.reading.push(addr);
+        self
+    }
+
+    /// Adds an immutable memory cell read by the operation, together with the [`ReadIntent`]
+    /// requiring the cell to contain the `expected` value.
+    pub fn read_expecting(mut self, addr: CellAddr, expected: &StateValue) -> Self {
+        let pos = self.reading.len().min(u16::MAX as usize) as u16;
+        self.read_intents.push(ReadIntent::new(pos, expected));
+        self.read(addr)
+    }
+
+    /// Adds a read-once memory cell created by the operation.
+    pub fn create(mut self, cell: StateCell) -> Self {
+        self.destructible.push(cell);
+        self
+    }
+
+    /// Adds an immutable memory cell created by the operation.
+    pub fn write(mut self, data: StateData) -> Self {
+        self.immutable.push(data);
+        self
+    }
+
+    /// Sets the commitment to the operation witness data (see
+    /// [`Operation::witness_commitment`]).
+    pub fn witness_commitment(mut self, commitment: fe256) -> Self {
+        self.witness_commitment = Some(commitment);
+        self
+    }
+
+    /// Produces the operation, checking that all the collections fit the operation limits.
+    ///
+    /// Returns the first error detected while building the operation.
+    pub fn finish(self) -> Result<Operation, BuildError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let confine = |len: usize, field: &'static str| {
+            if len > U16 {
+                Err(BuildError::TooMany { field, len })
+            } else {
+                Ok(())
+            }
+        };
+        confine(self.destroying.len(), "destroying")?;
+        confine(self.reading.len(), "reading")?;
+        confine(self.destructible.len(), "destructible")?;
+        confine(self.immutable.len(), "immutable")?;
+        confine(self.read_intents.len(), "read_intents")?;
+        Ok(Operation {
+            contract_id: self.contract_id,
+            call_id: self.call_id,
+            nonce: self.nonce,
+            destroying: SmallVec::from_checked(self.destroying),
+            reading: SmallVec::from_checked(self.reading),
+            destructible: SmallVec::from_checked(self.destructible),
+            immutable: SmallVec::from_checked(self.immutable),
+            witness_commitment: self.witness_commitment,
+            read_intents: SmallVec::from_checked(self.read_intents),
+            reserved: default!(),
+        })
+    }
+
+    /// Produces the operation like [`Self::finish`], additionally checking that the `codex` has a
+    /// verifier for the operation call id.
+    ///
+    /// This detects operations which would be rejected with [`crate::CallError::NotFound`] during
+    /// the verification at the construction time.
+    pub fn finish_for(self, codex: &Codex) -> Result<Operation, BuildError> {
+        if self.error.is_none() && !codex.has_call(self.call_id) {
+            return Err(BuildError::UnknownCall(self.call_id));
+        }
+        self.finish()
+    }
+
+    fn fail(&mut self, err: BuildError) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum BuildError {
+    #[cfg_attr(feature = "baid64", display = "memory cell {0} is destroyed more than once.")]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "memory cell {0:?} is destroyed more than once."
+    )]
+    DuplicateInput(CellAddr),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "witness is provided for memory cell {0} which is not destroyed by the \
+                   operation."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "witness is provided for memory cell {0:?} which is not destroyed by the \
+                   operation."
+    )]
+    UnknownInput(CellAddr),
+
+    #[cfg_attr(feature = "baid64", display = "spent operation has no read-once output {0}.")]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "spent operation has no read-once output {0:?}."
+    )]
+    UnknownOutput(CellAddr),
+
+    /// operation can't have {len} items in `{field}`.
+    TooMany { field: &'static str, len: usize },
+
+    /// codex has no verifier for the operation call id {0}.
+    UnknownCall(CallId),
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::Opid;
+
+    #[test]
+    fn matches_manual() {
+        let contract_id = ContractId::from([0xAB; 32]);
+        let spent = CellAddr::new(Opid::from([1u8; 32]), 0);
+        let read = CellAddr::new(Opid::from([2u8; 32]), 1);
+        let value = StateValue::from([u256::from(5u8)]);
+
+        let built = OperationBuilder::new(contract_id, CallId::from(3u16))
+            .nonce(fe256::from(u256::from(9u8)))
+            .destroy(spent, StateValue::None)
+            .read_expecting(read, &value)
+            .create(StateCell::strict_dumb())
+            .write(StateData::new(value))
+            .witness(spent, value)
+            .finish()
+            .unwrap();
+
+        let mut manual = Operation {
+            contract_id,
+            call_id: CallId::from(3u16),
+            nonce: fe256::from(u256::from(9u8)),
+            ..Operation::strict_dumb()
+        };
+        manual
+            .destroying
+            .push(Input { addr: spent, witness: value })
+            .unwrap();
+        manual.reading.push(read).unwrap();
+        manual.destructible.push(StateCell::strict_dumb()).unwrap();
+        manual.immutable.push(StateData::new(value)).unwrap();
+        manual
+            .read_intents
+            .push(ReadIntent::new(0, &value))
+            .unwrap();
+        assert_eq!(built, manual);
+        assert_eq!(built.opid(), manual.opid());
+    }
+
+    #[test]
+    fn deferred_errors() {
+        let contract_id = ContractId::from([0xAB; 32]);
+        let spent = CellAddr::new(Opid::from([1u8; 32]), 0);
+        let other = CellAddr::new(Opid::from([1u8; 32]), 1);
+
+        let err = OperationBuilder::new(contract_id, CallId::from(0u16))
+            .witness(other, StateValue::None)
+            .destroy(spent, StateValue::None)
+            .destroy(spent, StateValue::None)
+            .finish()
+            .unwrap_err();
+        assert_eq!(err, BuildError::UnknownInput(other));
+
+        let err = OperationBuilder::new(contract_id, CallId::from(0u16))
+            .destroy(spent, StateValue::None)
+            .destroy(spent, StateValue::None)
+            .finish()
+            .unwrap_err();
+        assert_eq!(err, BuildError::DuplicateInput(spent));
+    }
+
+    #[test]
+    fn spend_from() {
+        let contract_id = ContractId::from([0xAB; 32]);
+        let parent = OperationBuilder::new(contract_id, CallId::from(0u16))
+            .create(StateCell::strict_dumb())
+            .finish()
+            .unwrap();
+        let addr = CellAddr::new(parent.opid(), 0);
+
+        let op = OperationBuilder::new(contract_id, CallId::from(0u16))
+            .spend_from(&parent, 0, StateValue::None)
+            .finish()
+            .unwrap();
+        assert_eq!(op.destroying[0], Input { addr, witness: StateValue::None });
+
+        let err = OperationBuilder::new(contract_id, CallId::from(0u16))
+            .spend_from(&parent, 1, StateValue::None)
+            .finish()
+            .unwrap_err();
+        assert_eq!(err, BuildError::UnknownOutput(CellAddr::new(parent.opid(), 1)));
+    }
+
+    #[test]
+    fn finish_for() {
+        let contract_id = ContractId::from([0xAB; 32]);
+        let mut codex = Codex::strict_dumb();
+        let site = aluvm::LibSite::new(aluvm::LibId::from([0xCD; 32]), 0);
+        codex.verifiers.insert(CallId::from(1u16), site).unwrap();
+
+        let op = OperationBuilder::new(contract_id, CallId::from(1u16))
+            .finish_for(&codex)
+            .unwrap();
+        assert_eq!(op.call_id, CallId::from(1u16));
+
+        let err = OperationBuilder::new(contract_id, CallId::from(2u16))
+            .finish_for(&codex)
+            .unwrap_err();
+        assert_eq!(err, BuildError::UnknownCall(CallId::from(2u16)));
+    }
+}
@@ -0,0 +1,223 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use core::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+use crate::{CellAddr, Height, Memory, Opid, StateCell, StateValue};
+
+/// Statistics of the [`CachedMemory`] use.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct CacheStats {
+    /// Number of reads served from the cache.
+    pub hits: u64,
+    /// Number of reads forwarded to the underlying memory.
+    pub misses: u64,
+}
+
+/// Read-through cache over a contract memory.
+///
+/// The cache memoizes immutable memory cells and operation heights; reads of the read-once memory
+/// cells, which may get destroyed, are always forwarded to the underlying memory. Absent cells are
+/// not cached, since they may be created later.
+///
+/// Immutable cells and heights change only when the memory is rolled back (see
+/// [`crate::MemoryStore::rollback`]) or pruned (see [`crate::Prunable`]). Such modifications
+/// change the memory generation token (see [`Memory::tip`]), and the cache drops all its entries
+/// once the token differs from the one under which they were cached. Memories which don't track
+/// their generations (returning `None` from [`Memory::tip`]) must not be rolled back or pruned
+/// while being wrapped, unless the cache is [cleared](Self::clear) after each modification.
+///
+/// Each of the cached maps holds at most [`Self::capacity`] entries; once it is full, the entry
+/// with the smallest key is evicted.
+///
+/// The cache is useful when verifying a batch of operations reading the same immutable cells
+/// from a slow storage backend.
+#[derive(Debug)]
+pub struct CachedMemory<M: Memory> {
+    memory: M,
+    capacity: usize,
+    /// Generation token of the memory under which the cached entries were read.
+    tip: Cell<Option<u64>>,
+    immutable: RefCell<BTreeMap<CellAddr, StateValue>>,
+    heights: RefCell<BTreeMap<Opid, Height>>,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+impl<M: Memory> CachedMemory<M> {
+    /// Default maximal number of entries in each of the cached maps.
+    pub const DEFAULT_CAPACITY: usize = 1 << 16;
+
+    pub fn new(memory: M) -> Self { Self::with_capacity(memory, Self::DEFAULT_CAPACITY) }
+
+    /// Constructs the cache holding at most `capacity` entries in each of the cached maps.
+    ///
+    /// # Panics
+    ///
+    /// If the `capacity` is zero.
+    pub fn with_capacity(memory: M, capacity: usize) -> Self {
+        assert!(capacity > 0, "cache capacity must be non-zero");
+        Self {
+            tip: Cell::new(memory.tip()),
+            memory,
+            capacity,
+            immutable: none!(),
+            heights: none!(),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize { self.capacity }
+
+    pub fn inner(&self) -> &M { &self.memory }
+
+    /// Returns the mutable underlying memory.
+    ///
+    /// Modifications of the memory tracking its generations invalidate the cache automatically;
+    /// otherwise, the cache must be [cleared](Self::clear) after the modification.
+    pub fn inner_mut(&mut self) -> &mut M { &mut self.memory }
+
+    pub fn into_inner(self) -> M { self.memory }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { hits: self.hits.get(), misses: self.misses.get() }
+    }
+
+    /// Removes all the cached data and resets the statistics.
+    pub fn clear(&mut self) {
+        self.tip.set(self.memory.tip());
+        self.immutable.get_mut().clear();
+        self.heights.get_mut().clear();
+        self.hits.set(0);
+        self.misses.set(0);
+    }
+
+    fn read_through<K: Ord + Copy, V: Copy>(
+        &self,
+        cache: &RefCell<BTreeMap<K, V>>,
+        key: K,
+        read: impl FnOnce() -> Option<V>,
+    ) -> Option<V> {
+        let tip = self.memory.tip();
+        if tip != self.tip.get() {
+            self.tip.set(tip);
+            self.immutable.borrow_mut().clear();
+            self.heights.borrow_mut().clear();
+        }
+        if let Some(val) = cache.borrow().get(&key) {
+            self.hits.set(self.hits.get() + 1);
+            return Some(*val);
+        }
+        self.misses.set(self.misses.get() + 1);
+        let val = read()?;
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= self.capacity {
+            cache.pop_first();
+        }
+        cache.insert(key, val);
+        Some(val)
+    }
+}
+
+impl<M: Memory> Memory for CachedMemory<M> {
+    fn read_once(&self, addr: CellAddr) -> Option<StateCell> { self.memory.read_once(addr) }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.read_through(&self.immutable, addr, || self.memory.immutable(addr))
+    }
+
+    fn height(&self, opid: Opid) -> Option<Height> {
+        self.read_through(&self.heights, opid, || self.memory.height(opid))
+    }
+
+    fn tip(&self) -> Option<u64> { self.memory.tip() }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::num::u256;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{ApplyState, MemoryStore, Operation, StateData, VerifiedOperation};
+
+    fn value(val: u8) -> StateValue { StateValue::from([u256::from(val)]) }
+
+    fn addr(no: u8) -> CellAddr { CellAddr::new(Opid::from([no; 32]), 0) }
+
+    #[test]
+    fn read_through() {
+        let mut store = MemoryStore::new();
+        store.insert_immutable(addr(1), value(1));
+        store.insert_read_once(addr(2), StateCell::strict_dumb());
+        let cache = CachedMemory::new(store);
+
+        assert_eq!(cache.immutable(addr(1)), Some(value(1)));
+        assert_eq!(cache.immutable(addr(1)), Some(value(1)));
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+        assert_eq!(cache.read_once(addr(2)), Some(StateCell::strict_dumb()));
+        assert_eq!(cache.immutable(addr(3)), None);
+        assert_eq!(cache.immutable(addr(3)), None);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 3 });
+    }
+
+    #[test]
+    fn rollback_invalidates() {
+        let mut op = Operation::strict_dumb();
+        op.immutable.push(StateData::new(value(7))).unwrap();
+        let opid = op.opid();
+        let output = CellAddr::new(opid, 0);
+
+        let mut cache = CachedMemory::new(MemoryStore::new());
+        cache
+            .inner_mut()
+            .apply_unchecked(VerifiedOperation::new_unchecked(opid, op, Height::new(1)));
+        assert_eq!(cache.immutable(output), Some(value(7)));
+        assert_eq!(cache.height(opid), Some(Height::new(1)));
+
+        assert_eq!(cache.inner_mut().rollback(), Some(opid));
+        assert_eq!(cache.immutable(output), None);
+        assert_eq!(cache.height(opid), None);
+        assert_eq!(cache.stats().hits, 0);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut store = MemoryStore::new();
+        for no in 1..=3 {
+            store.insert_immutable(addr(no), value(no));
+        }
+        let cache = CachedMemory::with_capacity(store, 2);
+        for no in 1..=3 {
+            cache.immutable(addr(no));
+        }
+        assert_eq!(cache.immutable.borrow().len(), 2);
+        // The entry with the smallest address was evicted
+        assert_eq!(cache.immutable(addr(1)), Some(value(1)));
+        assert_eq!(cache.immutable(addr(3)), Some(value(3)));
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 4 });
+    }
+}
@@ -21,26 +21,67 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use core::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+
 use aluvm::regs::Status;
-use aluvm::{fe256, CoreConfig, CoreExt, Lib, LibId, LibSite, RegE, Vm};
-use amplify::confinement::{SmallVec, TinyOrdMap, TinyString};
+use aluvm::{fe256, CoreConfig, CoreExt, Lib, LibId, LibSite, Vm};
+use amplify::confinement::{self, SmallVec, TinyOrdMap, TinyString};
 use amplify::num::u256;
 use amplify::Bytes32;
-use commit_verify::{CommitId, CommitmentId, DigestExt, ReservedBytes, Sha256};
+use commit_verify::{CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, ReservedBytes};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
 
 use crate::{
-    CellAddr, ContractId, Identity, Instr, Operation, StateCell, StateData, StateValue,
-    LIB_NAME_ULTRASONIC,
+    check_reserved, reg, AuthToken, CellAddr, CellLock, ContractId, Height, IdHash, Identity,
+    Instr, Operation, Opid, ReservedError, StateCell, StateData, StateSchema, StateValue,
+    Timestamp, VerifiedOperation, VerifiedRef, VerifyPolicy, VerifyStats, LIB_NAME_ULTRASONIC,
 };
 
-pub type CallId = u16;
 pub type AccessId = u16;
 
+/// Identifier of a contract method, used to select an operation verifier from the codex.
+///
+/// Call ids starting from [`CallId::RESERVED_MIN`] are reserved for standard calls defined by
+/// UltraSONIC and protocols built on top of it; codices must not use them for their own methods.
+///
+/// The type is a newtype around `u16` and has the same strict encoding.
+#[derive(Wrapper, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, From, Display)]
+#[wrapper(Deref, FromStr)]
+#[display(inner)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct CallId(#[from] u16);
+
+impl CallId {
+    /// The first call id from the range reserved for the standard calls.
+    pub const RESERVED_MIN: u16 = 0xFF00;
+
+    /// Standard call destroying its inputs without producing any new state.
+    pub const BURN: CallId = CallId(Self::RESERVED_MIN);
+
+    /// Standard call designating the state audit script, checking contract invariants over the
+    /// complete live state (see [`Codex::check_invariants`]).
+    ///
+    /// Operations must not use this call id.
+    pub const INVARIANTS: CallId = CallId(Self::RESERVED_MIN + 1);
+
+    pub const fn new(id: u16) -> Self { Self(id) }
+
+    pub const fn to_u16(&self) -> u16 { self.0 }
+
+    /// Detects whether the call id belongs to the range reserved for the standard calls.
+    pub const fn is_reserved(&self) -> bool { self.0 >= Self::RESERVED_MIN }
+}
+
+impl From<CallId> for u16 {
+    fn from(call_id: CallId) -> Self { call_id.0 }
+}
+
 /// Codex is a crucial part of a contract; it provides a set of commitments to the contract terms
 /// and conditions expressed as a deterministic program able to run in SONIC computer model.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(CommitEncode)]
-#[commit_encode(strategy = strict, id = CodexId)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
@@ -48,26 +89,266 @@ pub struct Codex {
     pub version: ReservedBytes<2>,
     pub name: TinyString,
     pub developer: Identity,
-    pub timestamp: i64,
+    pub timestamp: Timestamp,
     pub field_order: u256,
     pub input_config: CoreConfig,
     pub verification_config: CoreConfig,
+    /// Verifiers of the operations by their call id.
+    ///
+    /// The map is serialized, and thus committed to by the [`CodexId`], as a sequence of entries
+    /// in strictly ascending order of the call ids; decoding rejects unsorted and repeated call
+    /// ids, such that each codex has a single valid encoding.
     pub verifiers: TinyOrdMap<CallId, LibSite>,
+    /// Flags opting the codex into stricter verification rules.
+    pub flags: CodexFlags,
+    /// Descriptors of the state layout per state type tag, allowing generic software to render
+    /// the contract state (see [`Codex::describe`]).
+    ///
+    /// The descriptors are informational and are not used in the verification. An empty schema is
+    /// not committed to, such that codexes without a schema keep their ids.
+    pub schema: StateSchema,
     /// Reserved for the future codex extensions
-    pub reserved: ReservedBytes<8>,
+    pub reserved: ReservedBytes<7>,
+}
+
+impl StrictSerialize for Codex {}
+impl StrictDeserialize for Codex {}
+
+impl CommitEncode for Codex {
+    type CommitmentId = CodexId;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        e.commit_to_serialized(&self.version);
+        e.commit_to_serialized(&self.name);
+        e.commit_to_serialized(&self.developer);
+        e.commit_to_serialized(&self.timestamp);
+        e.commit_to_serialized(&self.field_order);
+        e.commit_to_serialized(&self.input_config);
+        e.commit_to_serialized(&self.verification_config);
+        e.commit_to_serialized(&self.verifiers);
+        e.commit_to_serialized(&self.flags);
+        if !self.schema.is_empty() {
+            e.commit_to_serialized(&self.schema);
+        }
+        e.commit_to_serialized(&self.reserved);
+    }
+}
+
+/// Flags of a [`Codex`] opting it into stricter verification rules.
+///
+/// The flags take a byte which was previously reserved, such that codices without any flags set
+/// keep their serialization and ids. Flags unknown to the current version of the library make
+/// [`Codex::verify`] fail, so that no operation can be accepted by a verifier which doesn't
+/// implement all the rules required by the codex.
+#[derive(Wrapper, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, From)]
+#[wrapper(Deref)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct CodexFlags(#[from] u8);
+
+impl CodexFlags {
+    /// Requires all tokens of authority of the read-once memory cells created by an operation to
+    /// be distinct.
+    pub const UNIQUE_AUTH: Self = Self(0x01);
+
+    /// Executes reserved (unknown) instructions in the verifier code as no-ops, instead of failing
+    /// the verification with [`CallError::UnknownInstruction`].
+    ///
+    /// The flag allows codices to use instructions activated by the future ISA upgrades, while
+    /// remaining verifiable by the older versions of the library.
+    pub const RESERVED_NOP: Self = Self(0x02);
+
+    /// Allows operations to read immutable memory cells only of the operations whose read-once
+    /// memory cells they destroy.
+    ///
+    /// This is the retention policy of the codex: once all read-once memory cells of an operation
+    /// are destroyed, its immutable memory cells can't be read anymore and can be pruned from the
+    /// contract state (see [`crate::Prunable`]).
+    pub const BOUND_READS: Self = Self(0x04);
+
+    /// All flags known to this version of the library.
+    pub const KNOWN: Self = Self(0x07);
+
+    pub const fn empty() -> Self { Self(0) }
+
+    pub const fn bits(&self) -> u8 { self.0 }
+
+    pub const fn with(self, flags: Self) -> Self { Self(self.0 | flags.0) }
+
+    pub const fn contains(&self, flags: Self) -> bool { self.0 & flags.0 == flags.0 }
+
+    /// Returns bits of the flags unknown to this version of the library.
+    pub const fn unknown(&self) -> u8 { self.0 & !Self::KNOWN.0 }
 }
 
 impl Codex {
     pub fn codex_id(&self) -> CodexId { self.commit_id() }
 
+    /// Checks that all reserved fields of the codex are zero.
+    pub fn check_reserved(&self) -> Result<(), ReservedError> {
+        check_reserved!(self, "Codex", version, reserved);
+        Ok(())
+    }
+
+    /// Iterates over the call ids which have verifiers in the codex, in ascending order.
+    pub fn call_ids(&self) -> impl Iterator<Item = CallId> + '_ { self.verifiers.keys().copied() }
+
+    /// Checks whether the codex has a verifier for the given call id.
+    pub fn has_call(&self, call_id: CallId) -> bool { self.verifiers.contains_key(&call_id) }
+
+    /// Iterates over the verifiers in ascending order of their call ids, which is the order they
+    /// are serialized and committed in.
+    pub fn verifiers_ordered(&self) -> impl Iterator<Item = (CallId, LibSite)> + '_ {
+        self.verifiers
+            .iter()
+            .map(|(call_id, site)| (*call_id, *site))
+    }
+
+    /// Returns the largest call id which has a verifier, or `None` if the codex has no verifiers.
+    pub fn max_call_id(&self) -> Option<CallId> { self.verifiers.keys().next_back().copied() }
+
+    /// Checks that the codex has a verifier for the given call id.
+    ///
+    /// Operation constructors should use this before building an operation, since operations with
+    /// unknown call ids will be rejected with the same error during verification.
+    pub fn check_call(&self, call_id: CallId) -> Result<(), CallError> {
+        if self.has_call(call_id) {
+            Ok(())
+        } else {
+            Err(CallError::NotFound(call_id))
+        }
+    }
+
+    /// Registers verifier for the call id of the site, returning the previously used verifier, if
+    /// any.
+    ///
+    /// Errors if the codex already has the maximal number of verifiers.
+    pub fn set_verifier(
+        &mut self,
+        site: VerifierSite,
+    ) -> Result<Option<LibSite>, confinement::Error> {
+        self.verifiers.insert(site.call_id, site.site)
+    }
+
+    /// Verifies the operation against the contract state provided by `memory`, running the lock
+    /// scripts of the destroyed memory cells and the codex verifier for the operation call id.
+    ///
+    /// Operations which belong to a different contract are rejected, such that an operation can't
+    /// be replayed in another contract using the same codex.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "fixtures")] {
+    /// use ultrasonic::fixtures::Fixture;
+    /// use ultrasonic::CallError;
+    ///
+    /// let fixture = Fixture::counter();
+    /// let contract_id = fixture.contract.contract_id();
+    /// let op = fixture.operations[0].clone();
+    ///
+    /// let verified = fixture
+    ///     .codex()
+    ///     .verify(contract_id, op.clone(), &fixture, &fixture)
+    ///     .unwrap();
+    /// assert_eq!(verified.opid(), op.opid());
+    ///
+    /// let other = Fixture::fungible_token().contract.contract_id();
+    /// let err = fixture
+    ///     .codex()
+    ///     .verify(other, op, &fixture, &fixture)
+    ///     .unwrap_err();
+    /// assert!(matches!(err, CallError::WrongContract { .. }));
+    /// # }
+    /// ```
     pub fn verify(
         &self,
         contract_id: ContractId,
-        operation: &Operation,
+        operation: Operation,
         memory: &impl Memory,
         repo: &impl LibRepo,
-    ) -> Result<(), CallError> {
-        let resolver = |lib_id: LibId| repo.get_lib(lib_id);
+    ) -> Result<VerifiedOperation, CallError> {
+        self.verify_with_policy(contract_id, operation, memory, repo, &mut ())
+    }
+
+    /// Verifies the operation like [`Self::verify`], calling hooks of the provided `policy` before
+    /// and after each of the verification phases.
+    ///
+    /// Any error returned by the policy hooks aborts the verification.
+    pub fn verify_with_policy(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        policy: &mut impl VerifyPolicy,
+    ) -> Result<VerifiedOperation, CallError> {
+        let VerifiedRef { opid, height, stats, .. } = self.verify_snapshot(
+            contract_id,
+            &operation,
+            memory,
+            repo,
+            policy,
+            None,
+            &mut none!(),
+        )?;
+        Ok(VerifiedOperation::new_unchecked(opid, operation, height).with_stats(stats))
+    }
+
+    /// Verifies the operation like [`Self::verify`], but borrows the operation instead of taking
+    /// it by value, avoiding clones in pipelines which need to keep the original operation.
+    pub fn verify_ref<'op>(
+        &self,
+        contract_id: ContractId,
+        operation: &'op Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedRef<'op>, CallError> {
+        self.verify_snapshot(contract_id, operation, memory, repo, &mut (), None, &mut none!())
+    }
+
+    /// Verifies the operation like [`Self::verify`], additionally checking that the memory
+    /// reports the `expected` generation token (see [`Memory::tip`]) both before and after
+    /// reading the operation inputs.
+    ///
+    /// This detects verification against a stale or concurrently modified state snapshot, in
+    /// which case [`CallError::StaleMemory`] is returned.
+    pub fn verify_at_tip(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        expected: u64,
+    ) -> Result<VerifiedOperation, CallError> {
+        let VerifiedRef { opid, height, stats, .. } = self.verify_snapshot(
+            contract_id,
+            &operation,
+            memory,
+            repo,
+            &mut (),
+            Some(expected),
+            &mut none!(),
+        )?;
+        Ok(VerifiedOperation::new_unchecked(opid, operation, height).with_stats(stats))
+    }
+
+    fn verify_snapshot<'op>(
+        &self,
+        contract_id: ContractId,
+        operation: &'op Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        policy: &mut impl VerifyPolicy,
+        tip: Option<u64>,
+        vms: &mut VmPool,
+    ) -> Result<VerifiedRef<'op>, CallError> {
+        let check_tip = || match (tip, memory.tip()) {
+            (None, _) => Ok(()),
+            (Some(expected), Some(found)) if expected == found => Ok(()),
+            (Some(expected), found) => Err(CallError::StaleMemory { expected, found }),
+        };
 
         if operation.contract_id != contract_id {
             return Err(CallError::WrongContract {
@@ -75,47 +356,196 @@ impl Codex {
                 found: operation.contract_id,
             });
         }
+        self.check_operation(operation)?;
+        policy.check_operation(operation)?;
 
-        // Phase one: get inputs, verify access conditions
-        let mut vm_inputs =
-            Vm::<aluvm::gfa::Instr<LibId>>::with(self.input_config, self.field_order);
-        let mut read_once_input = SmallVec::new();
-        for input in &operation.destroying {
-            let cell = memory
-                .read_once(input.addr)
-                .ok_or(CallError::NoReadOnceInput(input.addr))?;
+        check_tip()?;
+        let inputs = OperationInputs::from_memory(operation, memory);
+        // Inputs read from a modified memory may be inconsistent, so the staleness takes
+        // precedence over the errors of reading them.
+        check_tip()?;
+        self.verify_materialized(operation, &inputs?, repo, policy, vms)
+    }
 
-            // Verify that the lock script conditions are satisfied
-            if let Some(lock) = cell.lock {
-                // Put also token of authority into a register
-                vm_inputs.core.cx.set(RegE::E1, cell.auth.to_fe256());
-
-                // Put witness into input registers
-                for (no, reg) in [RegE::E2, RegE::E3, RegE::E4, RegE::E5]
-                    .into_iter()
-                    .enumerate()
-                {
-                    let Some(el) = input.witness.get(no as u8) else {
-                        break;
-                    };
-                    vm_inputs.core.cx.set(reg, el);
+    /// Verifies a set of operations against the same contract state, returning the verification
+    /// result for each of the operations in the order they are provided.
+    ///
+    /// Unlike calling [`Self::verify`] for each of the operations, the VM instances are allocated
+    /// once and reused across all the operations. The operations are verified independently from
+    /// each other: an operation in the batch can't read or destroy memory cells created by
+    /// another operation of the batch unless they are already present in the `memory`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "fixtures")] {
+    /// use ultrasonic::fixtures::Fixture;
+    ///
+    /// let fixture = Fixture::counter();
+    /// let contract_id = fixture.contract.contract_id();
+    /// let codex = fixture.codex();
+    ///
+    /// let results = codex.verify_batch(contract_id, fixture.operations.clone(), &fixture, &fixture);
+    /// assert_eq!(results.len(), fixture.operations.len());
+    /// for (res, op) in results.into_iter().zip(&fixture.operations) {
+    ///     let single = codex.verify(contract_id, op.clone(), &fixture, &fixture);
+    ///     assert_eq!(res.map(|v| v.stats()), single.map(|v| v.stats()));
+    /// }
+    /// # }
+    /// ```
+    pub fn verify_batch(
+        &self,
+        contract_id: ContractId,
+        operations: impl IntoIterator<Item = Operation>,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Vec<Result<VerifiedOperation, CallError>> {
+        let mut vms = VmPool::default();
+        operations
+            .into_iter()
+            .map(|operation| {
+                let VerifiedRef { opid, height, stats, .. } = self.verify_snapshot(
+                    contract_id,
+                    &operation,
+                    memory,
+                    repo,
+                    &mut (),
+                    None,
+                    &mut vms,
+                )?;
+                Ok(VerifiedOperation::new_unchecked(opid, operation, height).with_stats(stats))
+            })
+            .collect()
+    }
+
+    /// Performs checks of the operation which don't require its inputs.
+    fn check_operation(&self, operation: &Operation) -> Result<(), CallError> {
+        self.check_operation_with(operation, &mut Err)
+    }
+
+    /// Performs checks of the operation which don't require its inputs, passing each of the
+    /// detected errors to `report`.
+    ///
+    /// The checks stop once `report` returns an error, which is then returned.
+    pub(crate) fn check_operation_with(
+        &self,
+        operation: &Operation,
+        report: &mut impl FnMut(CallError) -> Result<(), CallError>,
+    ) -> Result<(), CallError> {
+        if operation.call_id == CallId::INVARIANTS {
+            report(CallError::NotFound(operation.call_id))?;
+        }
+        if !operation.is_canonical(self.field_order) {
+            report(CallError::NonCanonical)?;
+        }
+        if self.flags.unknown() != 0 {
+            report(CallError::UnknownFlags(self.flags.unknown()))?;
+        }
+        for (pos, cell) in operation.destructible.iter().enumerate() {
+            if !cell.lock.is_valid() {
+                report(CallError::InvalidLock(pos as u16))?;
+            }
+        }
+        if self.flags.contains(CodexFlags::UNIQUE_AUTH) {
+            let mut tokens = BTreeSet::new();
+            for cell in &operation.destructible {
+                if !tokens.insert(cell.auth.to_byte_array()) {
+                    report(CallError::DuplicateAuth(cell.auth))?;
                 }
-                if vm_inputs.exec(lock, &(), resolver) == Status::Fail {
-                    // Read error code from output register
-                    return Err(CallError::Lock(vm_inputs.core.cx.get(RegE::E8)));
+            }
+        }
+        if self.flags.contains(CodexFlags::BOUND_READS) {
+            for addr in &operation.reading {
+                if !operation
+                    .destroying
+                    .iter()
+                    .any(|input| input.addr.opid == addr.opid)
+                {
+                    report(CallError::UnboundRead(*addr))?;
                 }
-                vm_inputs.reset();
             }
+        }
+        let mut prev = None;
+        for intent in &operation.read_intents {
+            if intent.pos as usize >= operation.reading.len() || prev >= Some(intent.pos) {
+                report(CallError::InvalidReadIntent(intent.pos))?;
+            }
+            prev = Some(intent.pos);
+        }
+        Ok(())
+    }
 
-            let _ = read_once_input.push(cell.data);
+    /// Verifies access conditions and the integrity of the operation with materialized inputs.
+    fn verify_materialized<'op>(
+        &self,
+        operation: &'op Operation,
+        inputs: &OperationInputs,
+        repo: &impl LibRepo,
+        policy: &mut impl VerifyPolicy,
+        vms: &mut VmPool,
+    ) -> Result<VerifiedRef<'op>, CallError> {
+        let mut stats = VerifyStats::default();
+
+        if let Some(input) = operation.destroying.get(inputs.read_once.len()) {
+            return Err(CallError::NoReadOnceInput(input.addr));
+        }
+        if let Some(addr) = operation.reading.get(inputs.immutable.len()) {
+            return Err(CallError::NoImmutableInput(*addr));
+        }
+        for intent in &operation.read_intents {
+            let pos = intent.pos as usize;
+            let Some(addr) = operation.reading.get(pos) else {
+                return Err(CallError::InvalidReadIntent(intent.pos));
+            };
+            if !intent.matches(&inputs.immutable[pos]) {
+                return Err(CallError::ReadIntentMismatch(*addr));
+            }
         }
+        #[cfg(feature = "prover-hooks")]
+        policy.after_inputs(operation, inputs)?;
+        let read_once_input = inputs.read_once[..operation.destroying.len()]
+            .iter()
+            .map(|cell| cell.data)
+            .collect::<Vec<_>>();
 
-        let mut immutable_input = SmallVec::new();
-        for input in &operation.destroying {
-            let data = memory
-                .immutable(input.addr)
-                .ok_or(CallError::NoImmutableInput(input.addr))?;
-            let _ = immutable_input.push(data);
+        // The context is constructed before running lock scripts, such that they can constrain
+        // the outputs of the spending operation (covenants).
+        let context = VmContext {
+            read_once_input: read_once_input.as_slice(),
+            immutable_input: &inputs.immutable.as_slice()[..operation.reading.len()],
+            read_once_output: operation.destructible.as_slice(),
+            immutable_output: operation.immutable.as_slice(),
+            witness_commitment: operation.witness_commitment,
+        };
+
+        // Phase one: verify access conditions. The VM for the lock scripts is constructed only if
+        // some of the inputs are locked, which is rarely the case.
+        for (input, cell) in operation.destroying.iter().zip(&inputs.read_once) {
+            policy.before_lock(input, cell)?;
+
+            // Verify that the lock script conditions are satisfied
+            if cell.lock.is_locked() {
+                let vm = vms.lock.get_or_insert_with(|| self.lock_vm());
+                let complexity = stats.lock_complexity;
+                if let Err(code) = self.check_lock(
+                    vm,
+                    cell.lock,
+                    cell.auth,
+                    &input.witness,
+                    None,
+                    &context,
+                    repo,
+                    &mut stats.lock_complexity,
+                ) {
+                    let err = CallError::Lock(code);
+                    policy.after_script_failure(&err, stats.lock_complexity - complexity);
+                    return Err(err);
+                }
+                policy.after_script(stats.lock_complexity - complexity)?;
+                #[cfg(feature = "prover-hooks")]
+                policy.after_lock_script(input, cell, stats.lock_complexity - complexity)?;
+            }
+            policy.after_lock(input, cell)?;
         }
 
         // Phase 2: Verify operation integrity
@@ -123,29 +553,478 @@ impl Codex {
             .verifiers
             .get(&operation.call_id)
             .ok_or(CallError::NotFound(operation.call_id))?;
-        let context = VmContext {
-            read_once_input: read_once_input.as_slice(),
-            immutable_input: immutable_input.as_slice(),
-            read_once_output: operation.destructible.as_slice(),
-            immutable_output: operation.immutable.as_slice(),
-        };
-        let mut vm_main = Vm::<Instr<LibId>>::with(self.verification_config, self.field_order);
-        match vm_main.exec(*entry_point, &context, resolver) {
+        policy.before_exec(operation, &context)?;
+        let vm_main = vms.main.get_or_insert_with(|| self.verifier_vm());
+        vm_main.reset();
+        if let Err(err) = self.run_verifier(vm_main, *entry_point, &context, repo) {
+            policy.after_script_failure(&err, vm_main.core.ca());
+            return Err(err);
+        }
+        policy.after_script(vm_main.core.ca())?;
+        #[cfg(feature = "prover-hooks")]
+        policy.after_verifier(operation, &context, vm_main)?;
+        policy.after_exec(operation, &context)?;
+        stats.main_complexity = vm_main.core.ca();
+        stats.instructions = vm_main.core.cx.steps();
+
+        Ok(VerifiedRef {
+            opid: operation.opid(),
+            operation,
+            height: inputs.height,
+            stats,
+        })
+    }
+
+    /// Runs the operation verifier starting from the `entry_point`, returning the VM after a
+    /// successful execution.
+    pub(crate) fn exec_verifier(
+        &self,
+        entry_point: LibSite,
+        context: &VmContext,
+        repo: &impl LibRepo,
+    ) -> Result<Vm<Instr<LibId>>, CallError> {
+        let mut vm = self.verifier_vm();
+        self.run_verifier(&mut vm, entry_point, context, repo)?;
+        Ok(vm)
+    }
+
+    /// Runs the operation verifier in the provided VM, which must be in the reset state.
+    fn run_verifier(
+        &self,
+        vm: &mut Vm<Instr<LibId>>,
+        entry_point: LibSite,
+        context: &VmContext,
+        repo: &impl LibRepo,
+    ) -> Result<(), CallError> {
+        let resolver = |lib_id: LibId| repo.get_lib(lib_id);
+        match vm.exec(entry_point, context, resolver) {
             Status::Ok => Ok(()),
-            Status::Fail => {
-                if let Some(err_code) = vm_main.core.cx.get(RegE::E1) {
-                    Err(CallError::Script(err_code))
-                } else {
-                    Err(CallError::ScriptUnspecified)
+            Status::Fail if vm.core.cx.reserved_hit() => Err(CallError::UnknownInstruction),
+            Status::Fail => Err(match vm.core.cx.get(reg::ERR_CODE) {
+                Some(err_code) => CallError::Script(err_code),
+                None => CallError::ScriptUnspecified,
+            }),
+        }
+    }
+
+    /// Constructs VM for running operation verifiers.
+    fn verifier_vm(&self) -> Vm<Instr<LibId>> {
+        let mut vm = Vm::<Instr<LibId>>::with(self.verification_config, self.field_order);
+        vm.core
+            .cx
+            .set_reserved_nop(self.flags.contains(CodexFlags::RESERVED_NOP));
+        vm
+    }
+
+    /// Constructs VM for running lock scripts.
+    pub(crate) fn lock_vm(&self) -> Vm<Instr<LibId>> {
+        let mut vm = Vm::<Instr<LibId>>::with(self.input_config, self.field_order);
+        vm.core
+            .cx
+            .set_reserved_nop(self.flags.contains(CodexFlags::RESERVED_NOP));
+        vm
+    }
+
+    /// Checks that the cell lock conditions are satisfied, running lock scripts with
+    /// [`Self::run_lock`].
+    ///
+    /// For threshold locks, each of the scripts gets a single witness element matching its
+    /// position; the check succeeds once the threshold number of scripts is satisfied. Invalid
+    /// threshold locks (see [`CellLock::is_valid`]) are never satisfied.
+    ///
+    /// The complexity consumed by the scripts is added to `complexity`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn check_lock(
+        &self,
+        vm: &mut Vm<Instr<LibId>>,
+        lock: CellLock,
+        auth: AuthToken,
+        witness: &StateValue,
+        challenge: Option<fe256>,
+        context: &VmContext,
+        repo: &impl LibRepo,
+        complexity: &mut u64,
+    ) -> Result<(), Option<fe256>> {
+        match lock {
+            CellLock::None => Ok(()),
+            CellLock::Single(site) => {
+                self.run_lock(vm, site, auth, witness, challenge, context, repo, complexity)
+            }
+            CellLock::Threshold { .. } if !lock.is_valid() => Err(None),
+            CellLock::Threshold { threshold, .. } => {
+                let mut satisfied = 0u8;
+                for (no, site) in lock.sites().enumerate() {
+                    let witness = witness
+                        .get(no as u8)
+                        .map(|first| StateValue::Single { first })
+                        .unwrap_or_default();
+                    if self
+                        .run_lock(vm, site, auth, &witness, challenge, context, repo, complexity)
+                        .is_ok()
+                    {
+                        satisfied += 1;
+                    }
+                    if satisfied >= threshold {
+                        return Ok(());
+                    }
                 }
+                Err(None)
             }
         }
     }
+
+    /// Runs a lock script in the provided VM, putting the token of authority into `E1`, witness
+    /// elements into `E2`-`E5` and, if present, a control proof challenge into `E6` register.
+    ///
+    /// The script runs under the USONIC ISA with the `context` of the spending operation, such that
+    /// it can iterate over the operation outputs and constrain them (covenants).
+    ///
+    /// On failure, returns the error code read from `E8` register. The VM is reset after the
+    /// execution in any case, and the complexity consumed by the script is added to `complexity`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn run_lock(
+        &self,
+        vm: &mut Vm<Instr<LibId>>,
+        lock: LibSite,
+        auth: AuthToken,
+        witness: &StateValue,
+        challenge: Option<fe256>,
+        context: &VmContext,
+        repo: &impl LibRepo,
+        complexity: &mut u64,
+    ) -> Result<(), Option<fe256>> {
+        let resolver = |lib_id: LibId| repo.get_lib(lib_id);
+
+        // Put token of authority into a register
+        vm.core.cx.set(reg::AUTH, auth.to_fe256());
+
+        // Put witness into input registers
+        for (no, dst) in reg::WITNESS.into_iter().enumerate() {
+            let Some(el) = witness.get(no as u8) else {
+                break;
+            };
+            vm.core.cx.set(dst, el);
+        }
+        if let Some(challenge) = challenge {
+            vm.core.cx.set(reg::CHALLENGE, challenge);
+        }
+
+        let res = match vm.exec(lock, context, resolver) {
+            Status::Ok => Ok(()),
+            // Read error code from output register
+            Status::Fail => Err(vm.core.cx.get(reg::LOCK_ERR_CODE)),
+        };
+        *complexity = complexity.saturating_add(vm.core.ca());
+        vm.reset();
+        res
+    }
+
+    /// Estimates the complexity of satisfying the lock of a memory cell with the provided witness.
+    ///
+    /// Runs only the lock scripts of the cell, without any other verification, which allows
+    /// wallets to check whether the combined lock costs of the inputs fit the complexity limit of
+    /// [`Self::input_config`] before assembling the operation. Cells without a lock cost nothing.
+    ///
+    /// The scripts run with an empty [`VmContext`], thus the estimation fails for covenant locks
+    /// which require the spending operation outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CallError::Lock`] if the witness doesn't satisfy the lock.
+    pub fn estimate_lock_cost(
+        &self,
+        cell: &StateCell,
+        witness: &StateValue,
+        repo: &impl LibRepo,
+    ) -> Result<u64, CallError> {
+        let mut complexity = 0u64;
+        if cell.lock.is_locked() {
+            let mut vm = self.lock_vm();
+            self.check_lock(
+                &mut vm,
+                cell.lock,
+                cell.auth,
+                witness,
+                None,
+                &VmContext::EMPTY,
+                repo,
+                &mut complexity,
+            )
+            .map_err(CallError::Lock)?;
+        }
+        Ok(complexity)
+    }
+
+    /// Verifies the operation like [`Self::verify`], additionally recording all reads from the
+    /// `memory` performed during the verification.
+    ///
+    /// The returned audit can be used to generate proofs of the memory access or for cache
+    /// warm-up analysis.
+    pub fn verify_audited(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<(VerifiedOperation, MemoryAudit), CallError> {
+        let memory = AuditedMemory::new(memory);
+        let verified = self.verify(contract_id, operation, &memory, repo)?;
+        Ok((verified, memory.into_audit()))
+    }
+
+    /// Verifies the operation like [`Self::verify`], but protects against library repositories
+    /// returning a library with an id different from the requested one.
+    ///
+    /// While [`Self::verify`] panics on such a mismatch, this method reports it with
+    /// [`CallError::LibIntegrity`], and is intended for use with untrusted repository
+    /// implementations (like plugins).
+    pub fn verify_checked(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        let repo = CheckedRepo::new(repo);
+        let res = self.verify(contract_id, operation, memory, &repo);
+        // The VM fails on the filtered-out library, so the mismatch is the real failure reason
+        match repo.mismatch() {
+            Some(lib_id) => Err(CallError::LibIntegrity(lib_id)),
+            None => res,
+        }
+    }
+}
+
+/// Verifies the operation against the codex over fully materialized inputs.
+///
+/// This is the deterministic side-effect-free kernel of [`Codex::verify`], which doesn't access
+/// the contract memory, targeted by model checkers and differential testers. Unlike
+/// [`Codex::verify`], it doesn't check that the operation belongs to a specific contract.
+pub fn verify_pure(
+    codex: &Codex,
+    operation: Operation,
+    inputs: &OperationInputs,
+    libs: &impl LibRepo,
+) -> Result<VerifiedOperation, CallError> {
+    codex.check_operation(&operation)?;
+    let VerifiedRef { opid, height, stats, .. } =
+        codex.verify_materialized(&operation, inputs, libs, &mut (), &mut none!())?;
+    Ok(VerifiedOperation::new_unchecked(opid, operation, height).with_stats(stats))
+}
+
+/// VM instances reused across the verification of multiple operations.
+///
+/// The instances are constructed lazily, on the first use.
+#[derive(Default)]
+struct VmPool {
+    main: Option<Vm<Instr<LibId>>>,
+    lock: Option<Vm<Instr<LibId>>>,
+}
+
+/// Operation inputs materialized from the contract memory.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct OperationInputs {
+    /// Memory cells destroyed by the operation, in the order of [`Operation::destroying`].
+    pub read_once: SmallVec<StateCell>,
+    /// Immutable memory cells read by the operation, in the order of [`Operation::reading`].
+    pub immutable: SmallVec<StateValue>,
+    /// Height of the operation, which is by one greater than the height of its highest parent.
+    pub height: Height,
+}
+
+impl OperationInputs {
+    /// Reads the operation inputs from the memory and computes the operation height.
+    pub fn from_memory(operation: &Operation, memory: &impl Memory) -> Result<Self, CallError> {
+        let mut read_once = SmallVec::new();
+        for input in &operation.destroying {
+            let cell = memory
+                .read_once(input.addr)
+                .ok_or(CallError::NoReadOnceInput(input.addr))?;
+            let _ = read_once.push(cell);
+        }
+
+        let mut immutable = SmallVec::new();
+        for addr in &operation.reading {
+            let data = memory
+                .immutable(*addr)
+                .ok_or(CallError::NoImmutableInput(*addr))?;
+            let _ = immutable.push(data);
+        }
+
+        let mut height = Height::GENESIS;
+        let parents = operation
+            .destroying
+            .iter()
+            .map(|input| input.addr.opid)
+            .chain(operation.reading.iter().map(|addr| addr.opid));
+        for opid in parents {
+            let parent = memory.height(opid).ok_or(CallError::NoHeight(opid))?;
+            height = height.max(parent);
+        }
+        let height = height.checked_next().ok_or(CallError::HeightOverflow)?;
+
+        Ok(Self { read_once, immutable, height })
+    }
 }
 
 pub trait Memory {
     fn read_once(&self, addr: CellAddr) -> Option<StateCell>;
     fn immutable(&self, addr: CellAddr) -> Option<StateValue>;
+    /// Returns height of an already verified operation (or of the contract genesis).
+    fn height(&self, opid: Opid) -> Option<Height>;
+
+    /// Returns generation token of the memory, which must change each time the memory is
+    /// modified, or `None` if the memory doesn't track its generations.
+    ///
+    /// Used by [`Codex::verify_at_tip`] to detect reading from a stale or concurrently modified
+    /// state snapshot.
+    fn tip(&self) -> Option<u64> { None }
+}
+
+/// Kind of memory access performed during the operation verification.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum AccessKind {
+    /// Access to a read-once memory cell with [`Memory::read_once`].
+    ReadOnce,
+    /// Access to an immutable memory cell with [`Memory::immutable`].
+    Immutable,
+}
+
+/// Record of a single memory read performed during the operation verification.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MemoryAccess {
+    pub addr: CellAddr,
+    pub kind: AccessKind,
+    /// Whether the memory cell was present in the memory.
+    pub hit: bool,
+}
+
+/// Ordered log of all memory reads performed during the operation verification.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MemoryAudit(Vec<MemoryAccess>);
+
+impl MemoryAudit {
+    pub fn iter(&self) -> impl Iterator<Item = &MemoryAccess> { self.0.iter() }
+
+    /// Returns reads of the memory cells which were absent in the memory.
+    pub fn misses(&self) -> impl Iterator<Item = &MemoryAccess> { self.0.iter().filter(|a| !a.hit) }
+
+    pub fn len(&self) -> usize { self.0.len() }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+impl IntoIterator for MemoryAudit {
+    type Item = MemoryAccess;
+    type IntoIter = alloc::vec::IntoIter<MemoryAccess>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.into_iter() }
+}
+
+/// Library repository adaptor filtering out libraries which id doesn't match the requested one.
+///
+/// The first of the mismatches is recorded and can be retrieved with [`Self::mismatch`].
+pub struct CheckedRepo<'r, R: LibRepo> {
+    repo: &'r R,
+    mismatch: Cell<Option<LibId>>,
+}
+
+impl<'r, R: LibRepo> CheckedRepo<'r, R> {
+    pub fn new(repo: &'r R) -> Self { Self { repo, mismatch: Cell::new(None) } }
+
+    /// Returns the id of the first requested library for which the repository has returned a
+    /// different library.
+    pub fn mismatch(&self) -> Option<LibId> { self.mismatch.get() }
+}
+
+impl<R: LibRepo> LibRepo for CheckedRepo<'_, R> {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        let lib = self.repo.get_lib(lib_id)?;
+        if lib.lib_id() != lib_id {
+            if self.mismatch.get().is_none() {
+                self.mismatch.set(Some(lib_id));
+            }
+            return None;
+        }
+        Some(lib)
+    }
+}
+
+/// Library repository adaptor pinning the libraries required by a codex (see
+/// [`Codex::required_libs`]) for the duration of a verification batch.
+///
+/// Pinned libraries are looked up with a binary search over a contiguous array; libraries which
+/// are not pinned, like the ones of lock scripts, are requested from the underlying repository.
+pub struct ResolvedRepo<'r, R: LibRepo> {
+    pinned: Vec<(LibId, &'r Lib)>,
+    repo: &'r R,
+}
+
+impl<'r, R: LibRepo> ResolvedRepo<'r, R> {
+    /// Pins all the libraries required by the codex verifiers.
+    pub fn new(codex: &Codex, repo: &'r R) -> Self {
+        Self::with_libs(codex.required_libs(repo), repo)
+    }
+
+    /// Pins the libraries with the provided ids; libraries absent in the repository are skipped.
+    pub fn with_libs(lib_ids: impl IntoIterator<Item = LibId>, repo: &'r R) -> Self {
+        let mut pinned = lib_ids
+            .into_iter()
+            .filter_map(|lib_id| repo.get_lib(lib_id).map(|lib| (lib_id, lib)))
+            .collect::<Vec<_>>();
+        pinned.sort_by_key(|(lib_id, _)| *lib_id);
+        pinned.dedup_by_key(|(lib_id, _)| *lib_id);
+        Self { pinned, repo }
+    }
+
+    /// Iterates over the ids of the pinned libraries.
+    pub fn pinned(&self) -> impl Iterator<Item = LibId> + '_ {
+        self.pinned.iter().map(|(lib_id, _)| *lib_id)
+    }
+}
+
+impl<R: LibRepo> LibRepo for ResolvedRepo<'_, R> {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        match self.pinned.binary_search_by_key(&lib_id, |(id, _)| *id) {
+            Ok(pos) => Some(self.pinned[pos].1),
+            Err(_) => self.repo.get_lib(lib_id),
+        }
+    }
+}
+
+/// Memory adaptor recording all reads performed via the [`Memory`] trait.
+pub struct AuditedMemory<'m, M: Memory> {
+    memory: &'m M,
+    log: RefCell<Vec<MemoryAccess>>,
+}
+
+impl<'m, M: Memory> AuditedMemory<'m, M> {
+    pub fn new(memory: &'m M) -> Self { Self { memory, log: none!() } }
+
+    pub fn into_audit(self) -> MemoryAudit { MemoryAudit(self.log.into_inner()) }
+
+    fn record(&self, addr: CellAddr, kind: AccessKind, hit: bool) {
+        self.log.borrow_mut().push(MemoryAccess { addr, kind, hit });
+    }
+}
+
+impl<M: Memory> Memory for AuditedMemory<'_, M> {
+    fn read_once(&self, addr: CellAddr) -> Option<StateCell> {
+        let cell = self.memory.read_once(addr);
+        self.record(addr, AccessKind::ReadOnce, cell.is_some());
+        cell
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        let value = self.memory.immutable(addr);
+        self.record(addr, AccessKind::Immutable, value.is_some());
+        value
+    }
+
+    fn height(&self, opid: Opid) -> Option<Height> { self.memory.height(opid) }
+
+    fn tip(&self) -> Option<u64> { self.memory.tip() }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -154,14 +1033,75 @@ pub struct VmContext<'ctx> {
     pub immutable_input: &'ctx [StateValue],
     pub read_once_output: &'ctx [StateCell],
     pub immutable_output: &'ctx [StateData],
+    /// Witness commitment of the operation (see [`Operation::witness_commitment`]).
+    pub witness_commitment: Option<fe256>,
+}
+
+impl VmContext<'static> {
+    /// Context without any memory cells, used to run lock scripts outside of an operation.
+    pub const EMPTY: Self = VmContext {
+        read_once_input: &[],
+        immutable_input: &[],
+        read_once_output: &[],
+        immutable_output: &[],
+        witness_commitment: None,
+    };
 }
 
 pub trait LibRepo {
     fn get_lib(&self, lib_id: LibId) -> Option<&Lib>;
 }
 
+/// Entry point of a codex verifier for a specific call id.
+///
+/// The site can be constructed only with the offset validated against the library code, catching
+/// verifiers pointing to a wrong library or offset at codex construction time rather than at the
+/// operation verification.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct VerifierSite {
+    call_id: CallId,
+    site: LibSite,
+}
+
+impl VerifierSite {
+    /// Constructs the verifier site, checking that the `offset` is within the `lib` code.
+    pub fn new(call_id: CallId, lib: &Lib, offset: u16) -> Result<Self, VerifierSiteError> {
+        let code_len = lib.code.len();
+        if offset as usize >= code_len {
+            return Err(VerifierSiteError { call_id, offset, code_len });
+        }
+        Ok(Self { call_id, site: LibSite::new(lib.lib_id(), offset) })
+    }
+
+    pub fn call_id(&self) -> CallId { self.call_id }
+
+    pub fn lib_site(&self) -> LibSite { self.site }
+}
+
+impl From<VerifierSite> for LibSite {
+    fn from(site: VerifierSite) -> Self { site.site }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(
+    "verifier for call {call_id} points to offset {offset} outside of the library code of \
+     {code_len} bytes."
+)]
+pub struct VerifierSiteError {
+    pub call_id: CallId,
+    pub offset: u16,
+    pub code_len: usize,
+}
+
+/// Error returned by the operation verification.
+///
+/// The error is strict- and serde-serializable, such that it can be transported between processes
+/// (e.g. from a verification worker to an orchestrator) without lossy string conversion.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
 #[display(doc_comments)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub enum CallError {
     #[cfg_attr(
         feature = "baid64",
@@ -172,12 +1112,18 @@ pub enum CallError {
         not(feature = "baid64"),
         display = "operation doesn't belong to the current contract."
     )]
+    #[strict_type(tag = 0x00)]
     WrongContract {
         expected: ContractId,
         found: ContractId,
     },
 
+    /// operation contains field elements which are not canonical under the codex field order.
+    #[strict_type(tag = 0x01)]
+    NonCanonical,
+
     /// operation verifier {0} is not present in the codex.
+    #[strict_type(tag = 0x02)]
     NotFound(CallId),
 
     #[cfg_attr(
@@ -188,6 +1134,7 @@ pub enum CallError {
         not(feature = "baid64"),
         display = "operation references read-once memory cell {0:?} which was not defined."
     )]
+    #[strict_type(tag = 0x03)]
     NoReadOnceInput(CellAddr),
 
     #[cfg_attr(
@@ -200,36 +1147,127 @@ pub enum CallError {
     )]
 
     /// operation references immutable memory cell {0} which was not defined.
+    #[strict_type(tag = 0x04)]
     NoImmutableInput(CellAddr),
 
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation depends on operation {0} with unknown height."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation depends on operation {0:?} with unknown height."
+    )]
+    #[strict_type(tag = 0x05)]
+    NoHeight(Opid),
+
+    /// operation exceeds the maximum depth of the contract history.
+    #[strict_type(tag = 0x06)]
+    HeightOverflow,
+
     /// operation input locking conditions are unsatisfied.
+    #[strict_type(tag = 0x07)]
     Lock(Option<fe256>),
 
     /// verification failure {0}
+    #[strict_type(tag = 0x08)]
     Script(fe256),
 
     /// verification failure (details are unspecified).
+    #[strict_type(tag = 0x09, dumb)]
     ScriptUnspecified,
+
+    /// operation is rejected by the verification policy with code {0}.
+    #[strict_type(tag = 0x0A)]
+    Policy(u32),
+
+    /// codex requires verification rules (flags {0:#04x}) unknown to this library version.
+    #[strict_type(tag = 0x0B)]
+    UnknownFlags(u8),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation creates several read-once memory cells with the same token of \
+                   authority {0}, which is prohibited by the codex."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation creates several read-once memory cells with the same token of \
+                   authority {0:?}, which is prohibited by the codex."
+    )]
+    #[strict_type(tag = 0x0C)]
+    DuplicateAuth(AuthToken),
+
+    /// verifier code contains an instruction unknown to this library version.
+    #[strict_type(tag = 0x0D)]
+    UnknownInstruction,
+
+    /// contract memory is stale or was modified during the verification (expected generation
+    /// {expected}, found {found:?}).
+    #[strict_type(tag = 0x0E)]
+    StaleMemory { expected: u64, found: Option<u64> },
+
+    /// library repository has returned a library with an id different from the requested {0}.
+    #[strict_type(tag = 0x0F)]
+    LibIntegrity(LibId),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "operation reads immutable memory cell {0} without destroying any of the \
+                   read-once memory cells of the same operation, which is prohibited by the codex."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "operation reads immutable memory cell {0:?} without destroying any of the \
+                   read-once memory cells of the same operation, which is prohibited by the codex."
+    )]
+    #[strict_type(tag = 0x10)]
+    UnboundRead(CellAddr),
+
+    /// operation read intent refers to the position {0} which is out of the list of the read
+    /// immutable memory cells or doesn't follow the position of the previous intent.
+    #[strict_type(tag = 0x11)]
+    InvalidReadIntent(u16),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "immutable memory cell {0} doesn't contain the value the operation intends to \
+                   read."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "immutable memory cell {0:?} doesn't contain the value the operation intends to \
+                   read."
+    )]
+    #[strict_type(tag = 0x12)]
+    ReadIntentMismatch(CellAddr),
+
+    /// stream of the operation immutable outputs yields a number of items different from the
+    /// declared one, or more than the maximal number of outputs.
+    #[strict_type(tag = 0x13)]
+    OutputStream,
+
+    /// operation output #{0} has a malformed lock which can never be satisfied.
+    #[strict_type(tag = 0x14)]
+    InvalidLock(u16),
 }
 
+impl StrictSerialize for CallError {}
+impl StrictDeserialize for CallError {}
+
 /// Unique codex identifier - a commitment to all codex data
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
-#[cfg_attr(
-    all(feature = "serde", not(feature = "baid64")),
-    derive(Serialize, Deserialize),
-    serde(transparent)
-)]
 pub struct CodexId(
     #[from]
     #[from([u8; 32])]
     Bytes32,
 );
 
-impl From<Sha256> for CodexId {
-    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+impl From<IdHash> for CodexId {
+    fn from(hasher: IdHash) -> Self { hasher.finish().into() }
 }
 
 impl CommitmentId for CodexId {
@@ -244,6 +1282,7 @@ mod _baid4 {
     use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
 
     use super::*;
+    use crate::display::parse_id;
 
     impl DisplayBaid64 for CodexId {
         const HRI: &'static str = "codex";
@@ -256,45 +1295,204 @@ mod _baid4 {
     impl FromBaid64Str for CodexId {}
     impl FromStr for CodexId {
         type Err = Baid64ParseError;
-        fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+        fn from_str(s: &str) -> Result<Self, Self::Err> { parse_id(s) }
     }
     impl Display for CodexId {
         fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
     }
 }
 
-// TODO: Use Base64 macro
-#[cfg(all(feature = "serde", feature = "baid64"))]
-mod _serde {
-    use core::str::FromStr;
+#[cfg(feature = "serde")]
+crate::serde_id::impl_serde_id!(CodexId);
 
-    use amplify::ByteArray;
-    use serde::de::Error;
-    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, U24};
+    use strict_encoding::StrictDumb;
 
     use super::*;
 
-    impl Serialize for CodexId {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where S: Serializer {
-            if serializer.is_human_readable() {
-                self.to_string().serialize(serializer)
-            } else {
-                self.to_byte_array().serialize(serializer)
-            }
+    fn codex() -> Codex {
+        let site = LibSite::new(LibId::from([0xAB; 32]), 0);
+        let mut codex = Codex::strict_dumb();
+        for call_id in [2u16, 0, 1] {
+            codex.verifiers.insert(CallId::new(call_id), site).unwrap();
         }
+        codex
     }
 
-    impl<'de> Deserialize<'de> for CodexId {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer<'de> {
-            if deserializer.is_human_readable() {
-                let s = String::deserialize(deserializer)?;
-                Self::from_str(&s).map_err(D::Error::custom)
-            } else {
-                let bytes = <[u8; 32]>::deserialize(deserializer)?;
-                Ok(Self::from_byte_array(bytes))
-            }
+    /// Returns the position of the verifier entry with the given call id in the serialized codex.
+    fn entry_pos(data: &[u8], call_id: u16) -> usize {
+        let mut entry = call_id.to_le_bytes().to_vec();
+        entry.extend([0xAB; 32]);
+        data.windows(entry.len())
+            .position(|window| window == entry)
+            .unwrap()
+    }
+
+    #[test]
+    fn verifiers_ordered() {
+        let codex = codex();
+        let call_ids = codex.verifiers_ordered().map(|(call_id, _)| call_id);
+        assert_eq!(call_ids.collect::<Vec<_>>(), [CallId::new(0), CallId::new(1), CallId::new(2)]);
+
+        let data = codex.to_strict_serialized::<U24>().unwrap().release();
+        assert!(entry_pos(&data, 0) < entry_pos(&data, 1));
+        assert!(entry_pos(&data, 1) < entry_pos(&data, 2));
+    }
+
+    #[test]
+    fn verifiers_reject_repeated() {
+        let mut data = codex().to_strict_serialized::<U24>().unwrap().release();
+        let pos = entry_pos(&data, 1);
+        data[pos] = 0;
+        assert!(Codex::from_strict_serialized::<U24>(Confined::from_checked(data)).is_err());
+    }
+
+    #[test]
+    fn verifiers_reject_unsorted() {
+        let mut data = codex().to_strict_serialized::<U24>().unwrap().release();
+        let pos0 = entry_pos(&data, 0);
+        let pos2 = entry_pos(&data, 2);
+        data[pos0] = 2;
+        data[pos2] = 0;
+        assert!(Codex::from_strict_serialized::<U24>(Confined::from_checked(data)).is_err());
+    }
+
+    #[test]
+    fn bound_reads() {
+        let mut codex = Codex { field_order: crate::FIELD_ORDER_SECP, ..codex() };
+        codex.flags = CodexFlags::BOUND_READS;
+        let mut op = Operation::strict_dumb();
+        let read = CellAddr::new(Opid::from([1; 32]), 0);
+        op.reading.push(read).unwrap();
+        assert_eq!(codex.check_operation(&op), Err(CallError::UnboundRead(read)));
+
+        let input = crate::Input { addr: CellAddr::new(read.opid, 1), witness: none!() };
+        op.destroying.push(input).unwrap();
+        assert_eq!(codex.check_operation(&op), Ok(()));
+    }
+
+    #[test]
+    fn read_intents() {
+        let codex = Codex { field_order: crate::FIELD_ORDER_SECP, ..codex() };
+        let mut op = Operation::strict_dumb();
+        op.reading
+            .push(CellAddr::new(Opid::from([1; 32]), 0))
+            .unwrap();
+        op.reading
+            .push(CellAddr::new(Opid::from([1; 32]), 1))
+            .unwrap();
+        let intent = |pos| crate::ReadIntent::new(pos, &StateValue::None);
+
+        op.read_intents = small_vec![intent(0), intent(1)];
+        assert_eq!(codex.check_operation(&op), Ok(()));
+        op.read_intents = small_vec![intent(1), intent(0)];
+        assert_eq!(codex.check_operation(&op), Err(CallError::InvalidReadIntent(0)));
+        op.read_intents = small_vec![intent(0), intent(0)];
+        assert_eq!(codex.check_operation(&op), Err(CallError::InvalidReadIntent(0)));
+        op.read_intents = small_vec![intent(2)];
+        assert_eq!(codex.check_operation(&op), Err(CallError::InvalidReadIntent(2)));
+    }
+
+    struct LockLib(Lib);
+    impl LibRepo for LockLib {
+        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+            (lib_id == self.0.lib_id()).then_some(&self.0)
+        }
+    }
+
+    /// Lock script which is satisfied if the first witness element fits into 8 bits.
+    fn lock_lib() -> LockLib {
+        use aluvm::gfa::{Bits, FieldInstr};
+        use aluvm::isa::CtrlInstr;
+
+        let code: [Instr<LibId>; 3] = [
+            FieldInstr::Fits { src: reg::WITNESS0, bits: Bits::Bits8 }.into(),
+            CtrlInstr::Chk.into(),
+            CtrlInstr::Stop.into(),
+        ];
+        LockLib(Lib::assemble(&code).unwrap())
+    }
+
+    fn threshold_cell(threshold: u8, repo: &LockLib, third: bool, fourth: bool) -> StateCell {
+        let site = LibSite::new(repo.0.lib_id(), 0);
+        StateCell {
+            data: none!(),
+            auth: AuthToken::from([0u8; 30]),
+            lock: CellLock::Threshold {
+                threshold,
+                first: site,
+                second: site,
+                third: third.then_some(site),
+                fourth: fourth.then_some(site),
+            },
+        }
+    }
+
+    /// Witness with the elements satisfying the test lock script at the given positions.
+    fn threshold_witness(satisfied: [bool; 4]) -> StateValue {
+        StateValue::from(satisfied.map(|ok| if ok { u256::ONE } else { u256::from(0x1000u64) }))
+    }
+
+    #[test]
+    fn threshold_lock() {
+        let codex = Codex { field_order: crate::FIELD_ORDER_SECP, ..codex() };
+        let repo = lock_lib();
+        let cell = threshold_cell(2, &repo, true, false);
+
+        for satisfied in
+            [[true, true, false, false], [true, false, true, false], [false, true, true, false]]
+        {
+            let witness = threshold_witness(satisfied);
+            assert!(codex.estimate_lock_cost(&cell, &witness, &repo).is_ok());
+        }
+        for satisfied in [[true, false, false, true], [false, false, true, false], [false; 4]] {
+            let witness = threshold_witness(satisfied);
+            assert_eq!(
+                codex.estimate_lock_cost(&cell, &witness, &repo),
+                Err(CallError::Lock(None))
+            );
+        }
+    }
+
+    #[test]
+    fn output_lock_invalid() {
+        let codex = Codex { field_order: crate::FIELD_ORDER_SECP, ..codex() };
+        let repo = lock_lib();
+        let mut op = Operation::strict_dumb();
+        op.destructible
+            .push(threshold_cell(2, &repo, true, false))
+            .unwrap();
+        assert_eq!(codex.check_operation(&op), Ok(()));
+
+        op.destructible
+            .push(threshold_cell(0, &repo, false, false))
+            .unwrap();
+        assert_eq!(codex.check_operation(&op), Err(CallError::InvalidLock(1)));
+        op.destructible[1] = threshold_cell(3, &repo, false, false);
+        assert_eq!(codex.check_operation(&op), Err(CallError::InvalidLock(1)));
+        op.destructible[1] = threshold_cell(2, &repo, false, true);
+        assert_eq!(codex.check_operation(&op), Err(CallError::InvalidLock(1)));
+    }
+
+    #[test]
+    fn threshold_lock_invalid() {
+        let codex = Codex { field_order: crate::FIELD_ORDER_SECP, ..codex() };
+        let repo = lock_lib();
+        let witness = threshold_witness([true; 4]);
+
+        let zero = threshold_cell(0, &repo, true, true);
+        let exceeding = threshold_cell(3, &repo, false, false);
+        // The fourth script would have been run with the third witness element
+        let gap = threshold_cell(1, &repo, false, true);
+        for cell in [zero, exceeding, gap] {
+            assert!(!cell.lock.is_valid());
+            assert_eq!(
+                codex.estimate_lock_cost(&cell, &witness, &repo),
+                Err(CallError::Lock(None))
+            );
         }
+        assert!(threshold_cell(4, &repo, true, true).lock.is_valid());
     }
 }
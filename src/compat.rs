@@ -0,0 +1,389 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Decoding of consensus data structures serialized with the layouts of the previous releases.
+//!
+//! Each function decodes data from a specific historical layout and returns the current in-memory
+//! type. The conversion is lossless: all the fields which were added since then get values which
+//! preserve the original commitments.
+//!
+//! The historical layouts are defined by the frozen copies of the data structures, which must
+//! never be changed, even when the current types evolve.
+
+use aluvm::{fe256, CoreConfig, LibSite};
+use amplify::confinement::{SmallVec, TinyOrdMap, TinyString};
+use amplify::num::u256;
+use commit_verify::ReservedBytes;
+use strict_encoding::{DecodeError, StreamReader, StrictDecode, StrictReader};
+
+use crate::{
+    AuthToken, CallId, CellAddr, CellLock, Codex, CodexFlags, CodexId, ContractId, Genesis,
+    Identity, Input, Operation, RawData, StateCell, StateData, StateValue, Timestamp,
+    LIB_NAME_ULTRASONIC,
+};
+
+/// Maximal size of the data accepted by the decoders of this module.
+pub const MAX_LEGACY_DATA_LEN: usize = 0xFF_FFFF;
+
+fn decode<T: StrictDecode>(data: &[u8]) -> Result<T, DecodeError> {
+    let mut reader = StrictReader::with(StreamReader::cursor::<MAX_LEGACY_DATA_LEN>(data));
+    let val = T::strict_decode(&mut reader)?;
+    let cursor = reader.unbox().unconfine();
+    if cursor.position() != data.len() as u64 {
+        return Err(DecodeError::DataNotEntirelyConsumed);
+    }
+    Ok(val)
+}
+
+/// Decodes codex serialized with the layout of version 0.12.0-beta.4, where [`Codex`] had no
+/// state schema.
+pub fn decode_codex_v0_12_beta4(data: &[u8]) -> Result<Codex, DecodeError> {
+    decode::<v0_12_beta4::Codex>(data).map(Codex::from)
+}
+
+/// Decodes genesis serialized with the layout of version 0.12.0-beta.4, where [`StateData`] had
+/// no raw data encoding hint.
+pub fn decode_genesis_v0_12_beta4(data: &[u8]) -> Result<Genesis, DecodeError> {
+    decode::<v0_12_beta4::Genesis>(data).map(Genesis::from)
+}
+
+/// Decodes operation serialized with the layout of version 0.12.0-beta.4, where [`StateData`] had
+/// no raw data encoding hint.
+pub fn decode_operation_v0_12_beta4(data: &[u8]) -> Result<Operation, DecodeError> {
+    decode::<v0_12_beta4::Operation>(data).map(Operation::from)
+}
+
+mod v0_12_beta4 {
+    use super::*;
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+    pub enum StateValue {
+        #[strict_type(tag = 0x00, dumb)]
+        None,
+        #[strict_type(tag = 0x01)]
+        Single { first: fe256 },
+        #[strict_type(tag = 0x02)]
+        Double { first: fe256, second: fe256 },
+        #[strict_type(tag = 0x03)]
+        Three {
+            first: fe256,
+            second: fe256,
+            third: fe256,
+        },
+        #[strict_type(tag = 0x04)]
+        Four {
+            first: fe256,
+            second: fe256,
+            third: fe256,
+            fourth: fe256,
+        },
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct AuthToken(fe256);
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct StateCell {
+        pub data: StateValue,
+        pub auth: AuthToken,
+        pub lock: Option<LibSite>,
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct Input {
+        pub addr: CellAddr,
+        pub witness: StateValue,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct StateData {
+        pub value: StateValue,
+        pub raw: Option<RawData>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct Codex {
+        pub version: ReservedBytes<2>,
+        pub name: TinyString,
+        pub developer: Identity,
+        pub timestamp: Timestamp,
+        pub field_order: u256,
+        pub input_config: CoreConfig,
+        pub verification_config: CoreConfig,
+        pub verifiers: TinyOrdMap<CallId, LibSite>,
+        pub flags: CodexFlags,
+        pub reserved: ReservedBytes<7>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct Genesis {
+        pub codex_id: CodexId,
+        pub call_id: CallId,
+        pub nonce: fe256,
+        pub blank1: ReservedBytes<2>,
+        pub blank2: ReservedBytes<2>,
+        pub destructible: SmallVec<StateCell>,
+        pub immutable: SmallVec<StateData>,
+        pub reserved: ReservedBytes<8>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub struct Operation {
+        pub contract_id: ContractId,
+        pub call_id: CallId,
+        pub nonce: fe256,
+        pub destroying: SmallVec<Input>,
+        pub reading: SmallVec<CellAddr>,
+        pub destructible: SmallVec<StateCell>,
+        pub immutable: SmallVec<StateData>,
+        pub reserved: ReservedBytes<8>,
+    }
+
+    impl From<StateValue> for super::StateValue {
+        fn from(value: StateValue) -> Self {
+            match value {
+                StateValue::None => Self::None,
+                StateValue::Single { first } => Self::Single { first },
+                StateValue::Double { first, second } => Self::Double { first, second },
+                StateValue::Three { first, second, third } => Self::Three { first, second, third },
+                StateValue::Four { first, second, third, fourth } => {
+                    Self::Four { first, second, third, fourth }
+                }
+            }
+        }
+    }
+
+    impl From<StateCell> for super::StateCell {
+        fn from(cell: StateCell) -> Self {
+            Self {
+                data: cell.data.into(),
+                auth: super::AuthToken::from(cell.auth.0),
+                lock: CellLock::from(cell.lock),
+            }
+        }
+    }
+
+    impl From<Input> for super::Input {
+        fn from(input: Input) -> Self { Self { addr: input.addr, witness: input.witness.into() } }
+    }
+
+    fn convert<T, U: From<T>>(items: SmallVec<T>) -> SmallVec<U> {
+        SmallVec::from_iter_checked(items.into_iter().map(U::from))
+    }
+
+    fn convert_immutable(immutable: SmallVec<StateData>) -> SmallVec<super::StateData> {
+        SmallVec::from_iter_checked(immutable.into_iter().map(|data| super::StateData {
+            value: data.value.into(),
+            hint: None,
+            raw: data.raw,
+        }))
+    }
+
+    impl From<Codex> for super::Codex {
+        fn from(codex: Codex) -> Self {
+            Self {
+                version: codex.version,
+                name: codex.name,
+                developer: codex.developer,
+                timestamp: codex.timestamp,
+                field_order: codex.field_order,
+                input_config: codex.input_config,
+                verification_config: codex.verification_config,
+                verifiers: codex.verifiers,
+                flags: codex.flags,
+                schema: none!(),
+                reserved: codex.reserved,
+            }
+        }
+    }
+
+    impl From<Genesis> for super::Genesis {
+        fn from(genesis: Genesis) -> Self {
+            Self {
+                codex_id: genesis.codex_id,
+                call_id: genesis.call_id,
+                nonce: genesis.nonce,
+                blank1: genesis.blank1,
+                blank2: genesis.blank2,
+                destructible: convert(genesis.destructible),
+                immutable: convert_immutable(genesis.immutable),
+                reserved: genesis.reserved,
+            }
+        }
+    }
+
+    impl From<Operation> for super::Operation {
+        fn from(op: Operation) -> Self {
+            Self {
+                contract_id: op.contract_id,
+                call_id: op.call_id,
+                nonce: op.nonce,
+                destroying: convert(op.destroying),
+                reading: op.reading,
+                destructible: convert(op.destructible),
+                immutable: convert_immutable(op.immutable),
+                witness_commitment: None,
+                read_intents: none!(),
+                reserved: op.reserved,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use amplify::confinement::U24;
+    use amplify::hex::FromHex;
+    use strict_encoding::{StrictDumb, StrictSerialize};
+
+    use super::*;
+
+    // Operation with a single immutable output having raw data 0xDEAD, serialized by 0.12.0-beta.4
+    const OPERATION_V0_12_BETA4: &str = "\
+        1111111111111111111111111111111111111111111111111111111111111111\
+        0500\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0000\
+        0000\
+        0000\
+        0100\
+        00\
+        010200dead\
+        0000000000000000";
+
+    #[test]
+    fn operation_v0_12_beta4() {
+        let data = Vec::<u8>::from_hex(OPERATION_V0_12_BETA4).unwrap();
+        let op = decode_operation_v0_12_beta4(&data).unwrap();
+
+        let expected = Operation {
+            contract_id: ContractId::from([0x11; 32]),
+            call_id: CallId::new(5),
+            nonce: fe256::from(u256::ZERO),
+            destroying: none!(),
+            reading: none!(),
+            destructible: none!(),
+            immutable: small_vec![StateData::with_raw(
+                StateValue::None,
+                RawData::from_str("0xdead").unwrap()
+            )],
+            witness_commitment: None,
+            read_intents: none!(),
+            reserved: default!(),
+        };
+        assert_eq!(op, expected);
+    }
+
+    // Operation spending a single input and defining a locked and an unlocked read-once output,
+    // serialized by 0.12.0-beta.4
+    const OPERATION_LOCKED_V0_12_BETA4: &str = "\
+        1111111111111111111111111111111111111111111111111111111111111111\
+        0500\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        0100\
+        5555555555555555555555555555555555555555555555555555555555555555\
+        0100\
+        012222222222222222222222222222222222222222222222222222222222222222\
+        0000\
+        0200\
+        012222222222222222222222222222222222222222222222222222222222222222\
+        3333333333333333333333333333333333333333333333333333333333333333\
+        014444444444444444444444444444444444444444444444444444444444444444\
+        0201\
+        00\
+        3333333333333333333333333333333333333333333333333333333333333333\
+        00\
+        0000\
+        0000000000000000";
+
+    #[test]
+    fn operation_locked_v0_12_beta4() {
+        let data = Vec::<u8>::from_hex(OPERATION_LOCKED_V0_12_BETA4).unwrap();
+        let op = decode_operation_v0_12_beta4(&data).unwrap();
+
+        let fe = |byte: u8| fe256::from([byte; 32]);
+        let auth = AuthToken::from(fe(0x33));
+        let site = LibSite::new(aluvm::LibId::from([0x44; 32]), 0x0102);
+        let expected = Operation {
+            contract_id: ContractId::from([0x11; 32]),
+            call_id: CallId::new(5),
+            nonce: fe256::from(u256::ZERO),
+            destroying: small_vec![Input {
+                addr: CellAddr::new(crate::Opid::from([0x55; 32]), 1),
+                witness: StateValue::Single { first: fe(0x22) },
+            }],
+            reading: none!(),
+            destructible: small_vec![
+                StateCell {
+                    data: StateValue::Single { first: fe(0x22) },
+                    auth,
+                    lock: CellLock::Single(site),
+                },
+                StateCell { data: StateValue::None, auth, lock: CellLock::None },
+            ],
+            immutable: none!(),
+            witness_commitment: None,
+            read_intents: none!(),
+            reserved: default!(),
+        };
+        assert_eq!(op, expected);
+    }
+
+    #[test]
+    fn trailing_data() {
+        let mut data = Vec::<u8>::from_hex(OPERATION_V0_12_BETA4).unwrap();
+        data.push(0);
+        assert!(matches!(
+            decode_operation_v0_12_beta4(&data),
+            Err(DecodeError::DataNotEntirelyConsumed)
+        ));
+    }
+
+    #[test]
+    fn codex_v0_12_beta4() {
+        let codex = Codex::strict_dumb();
+        let mut data = codex.to_strict_serialized::<U24>().unwrap().release();
+        // Remove the length of the empty state schema, which precedes the reserved bytes
+        let pos = data.len() - 8;
+        assert_eq!(data.remove(pos), 0);
+        assert_eq!(decode_codex_v0_12_beta4(&data).unwrap(), codex);
+    }
+}
@@ -0,0 +1,531 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Partially revealed operations, carrying only Merkle leaf hashes for some of their outputs.
+//!
+//! An operation output committed to by the operation id may be concealed down to its Merkle leaf
+//! hash, allowing selective disclosure of the operation outputs to third parties. Concealed outputs
+//! can't be verified by a codex; a party receiving a partially revealed operation gets the full
+//! operation once all the outputs are revealed. A read-once memory cell which was received in a
+//! concealed form must be revealed when it is consumed, see [`PartialOperation::check_spend`]
+//! and [`RevealedMemory`].
+
+use std::collections::BTreeMap;
+
+use aluvm::fe256;
+use amplify::confinement::SmallVec;
+use amplify::num::u256;
+use commit_verify::{CommitEngine, CommitId, CommitmentId, MerkleHash, ReservedBytes};
+
+use crate::{
+    CallId, CellAddr, ContractId, Height, Input, Memory, Operation, Opid, ReadIntent, StateCell,
+    StateData, StateValue, LIB_NAME_ULTRASONIC,
+};
+
+/// Read-once memory cell which may be concealed down to its Merkle leaf hash.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum ConcealableCell {
+    #[strict_type(tag = 0x00, dumb)]
+    Revealed(StateCell),
+    #[strict_type(tag = 0x01)]
+    Concealed(MerkleHash),
+}
+
+/// Immutable memory cell which may be concealed down to its Merkle leaf hash.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum ConcealableData {
+    #[strict_type(tag = 0x00, dumb)]
+    Revealed(StateData),
+    #[strict_type(tag = 0x01)]
+    Concealed(MerkleHash),
+}
+
+impl ConcealableCell {
+    /// Returns Merkle leaf hash of the cell, which is committed to by the operation id.
+    pub fn leaf_hash(&self) -> MerkleHash {
+        match self {
+            ConcealableCell::Revealed(cell) => cell.commit_id(),
+            ConcealableCell::Concealed(hash) => *hash,
+        }
+    }
+
+    pub fn revealed(&self) -> Option<&StateCell> {
+        match self {
+            ConcealableCell::Revealed(cell) => Some(cell),
+            ConcealableCell::Concealed(_) => None,
+        }
+    }
+}
+
+impl ConcealableData {
+    /// Returns Merkle leaf hash of the cell, which is committed to by the operation id.
+    pub fn leaf_hash(&self) -> MerkleHash {
+        match self {
+            ConcealableData::Revealed(data) => data.commit_id(),
+            ConcealableData::Concealed(hash) => *hash,
+        }
+    }
+
+    pub fn revealed(&self) -> Option<&StateData> {
+        match self {
+            ConcealableData::Revealed(data) => Some(data),
+            ConcealableData::Concealed(_) => None,
+        }
+    }
+}
+
+/// Operation with some of its outputs concealed.
+///
+/// Inputs of the operation are always revealed, since they are required to track the contract
+/// state.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct PartialOperation {
+    /// Version of the partially revealed operation format; must be zero.
+    pub version: ReservedBytes<1>,
+    /// Id of the full operation.
+    pub opid: Opid,
+    pub contract_id: ContractId,
+    pub call_id: CallId,
+    pub nonce: fe256,
+    pub destroying: SmallVec<Input>,
+    pub reading: SmallVec<CellAddr>,
+    pub destructible: SmallVec<ConcealableCell>,
+    pub immutable: SmallVec<ConcealableData>,
+    pub witness_commitment: Option<fe256>,
+    pub read_intents: SmallVec<ReadIntent>,
+    pub reserved: ReservedBytes<8>,
+}
+
+impl Operation {
+    /// Conceals the read-once and immutable outputs of the operation at the provided positions.
+    ///
+    /// Errors if any of the positions doesn't refer to an existing output.
+    pub fn conceal(
+        &self,
+        destructible: &[u16],
+        immutable: &[u16],
+    ) -> Result<PartialOperation, RevealError> {
+        let opid = self.opid();
+        for (list, len) in
+            [(destructible, self.destructible.len()), (immutable, self.immutable.len())]
+        {
+            if let Some(pos) = list.iter().find(|pos| **pos as usize >= len) {
+                return Err(RevealError::UnknownOutput(CellAddr::new(opid, *pos)));
+            }
+        }
+        let conceal = |no: usize, list: &[u16]| list.contains(&(no as u16));
+        Ok(PartialOperation {
+            version: default!(),
+            opid,
+            contract_id: self.contract_id,
+            call_id: self.call_id,
+            nonce: self.nonce,
+            destroying: self.destroying.clone(),
+            reading: self.reading.clone(),
+            destructible: SmallVec::from_iter_checked(self.destructible.iter().enumerate().map(
+                |(no, cell)| {
+                    if conceal(no, destructible) {
+                        ConcealableCell::Concealed(cell.commit_id())
+                    } else {
+                        ConcealableCell::Revealed(*cell)
+                    }
+                },
+            )),
+            immutable: SmallVec::from_iter_checked(self.immutable.iter().enumerate().map(
+                |(no, data)| {
+                    if conceal(no, immutable) {
+                        ConcealableData::Concealed(data.commit_id())
+                    } else {
+                        ConcealableData::Revealed(data.clone())
+                    }
+                },
+            )),
+            witness_commitment: self.witness_commitment,
+            read_intents: self.read_intents.clone(),
+            reserved: self.reserved,
+        })
+    }
+}
+
+/// Computes Merkle root of the leaf hashes in the same way as [`MerkleHash::merklize`] does for
+/// the leaves themselves.
+fn merkle_root(mut leaves: impl ExactSizeIterator<Item = MerkleHash>) -> MerkleHash {
+    let width = leaves.len() as u32;
+    match width {
+        0 => MerkleHash::void(0u8, u256::ZERO),
+        1 => MerkleHash::single(0u8, 1u8, leaves.next().expect("length is 1")),
+        _ => merklize(&mut leaves, 0, width, width),
+    }
+}
+
+fn merklize(
+    leaves: &mut impl Iterator<Item = MerkleHash>,
+    depth: u8,
+    branch_width: u32,
+    base_width: u32,
+) -> MerkleHash {
+    if branch_width <= 1 {
+        return leaves
+            .next()
+            .expect("number of leaves matches the tree width");
+    }
+    let div = branch_width / 2 + branch_width % 2;
+    let node1 = merklize(leaves, depth + 1, div, base_width);
+    let node2 = merklize(leaves, depth + 1, branch_width - div, base_width);
+    MerkleHash::branches(depth, base_width, node1, node2)
+}
+
+impl PartialOperation {
+    /// Computes id of the operation from its data and the leaf hashes of its outputs.
+    pub fn compute_opid(&self) -> Opid {
+        let operation = Operation {
+            contract_id: self.contract_id,
+            call_id: self.call_id,
+            nonce: self.nonce,
+            destroying: self.destroying.clone(),
+            reading: self.reading.clone(),
+            destructible: none!(),
+            immutable: none!(),
+            witness_commitment: self.witness_commitment,
+            read_intents: self.read_intents.clone(),
+            reserved: self.reserved,
+        };
+        let mut e = CommitEngine::new(Opid::TAG);
+        operation.commit_encode_outputs(
+            &mut e,
+            |e| {
+                let leaves = self.destructible.iter().map(ConcealableCell::leaf_hash);
+                e.commit_to_serialized(&merkle_root(leaves))
+            },
+            |e| {
+                let leaves = self.immutable.iter().map(ConcealableData::leaf_hash);
+                e.commit_to_serialized(&merkle_root(leaves))
+            },
+        );
+        e.set_finished();
+        Opid::from(e.finish())
+    }
+
+    /// Checks that the operation id matches the operation data and the leaf hashes of its
+    /// outputs.
+    ///
+    /// Unless verified, the operation id of a partial operation is not bound to its outputs, and
+    /// thus can't be trusted.
+    pub fn verify(&self) -> Result<(), RevealError> {
+        if self.compute_opid() != self.opid {
+            return Err(RevealError::OpidMismatch(self.opid));
+        }
+        Ok(())
+    }
+
+    /// Detects whether all the operation outputs are revealed.
+    pub fn is_revealed(&self) -> bool {
+        self.destructible
+            .iter()
+            .all(|cell| cell.revealed().is_some())
+            && self.immutable.iter().all(|data| data.revealed().is_some())
+    }
+
+    /// Reveals a concealed read-once output, checking that it matches the committed leaf hash.
+    pub fn reveal_destructible(&mut self, pos: u16, cell: StateCell) -> Result<(), RevealError> {
+        self.verify()?;
+        let addr = CellAddr::new(self.opid, pos);
+        let slot = self
+            .destructible
+            .get_mut(pos as usize)
+            .ok_or(RevealError::UnknownOutput(addr))?;
+        if slot.leaf_hash() != cell.commit_id() {
+            return Err(RevealError::Mismatch(addr));
+        }
+        *slot = ConcealableCell::Revealed(cell);
+        Ok(())
+    }
+
+    /// Reveals a concealed immutable output, checking that it matches the committed leaf hash.
+    pub fn reveal_immutable(&mut self, pos: u16, data: StateData) -> Result<(), RevealError> {
+        self.verify()?;
+        let addr = CellAddr::new(self.opid, pos);
+        let slot = self
+            .immutable
+            .get_mut(pos as usize)
+            .ok_or(RevealError::UnknownOutput(addr))?;
+        if slot.leaf_hash() != data.commit_id() {
+            return Err(RevealError::Mismatch(addr));
+        }
+        *slot = ConcealableData::Revealed(data);
+        Ok(())
+    }
+
+    /// Checks that a read-once cell revealed by the operation spending it matches the leaf hash
+    /// committed to by this operation.
+    ///
+    /// A cell which was received in a concealed form can't be consumed unless it is revealed.
+    pub fn check_spend(&self, pos: u16, cell: &StateCell) -> Result<(), RevealError> {
+        self.verify()?;
+        let addr = CellAddr::new(self.opid, pos);
+        let slot = self
+            .destructible
+            .get(pos as usize)
+            .ok_or(RevealError::UnknownOutput(addr))?;
+        if slot.leaf_hash() != cell.commit_id() {
+            return Err(RevealError::Mismatch(addr));
+        }
+        Ok(())
+    }
+
+    /// Converts fully revealed operation into [`Operation`], checking that its id matches the one
+    /// of the partial operation.
+    pub fn into_operation(self) -> Result<Operation, RevealError> {
+        let opid = self.opid;
+        let destructible = self
+            .destructible
+            .iter()
+            .enumerate()
+            .map(|(no, cell)| {
+                cell.revealed()
+                    .copied()
+                    .ok_or(RevealError::Concealed(CellAddr::new(opid, no as u16)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let immutable = self
+            .immutable
+            .iter()
+            .enumerate()
+            .map(|(no, data)| {
+                data.revealed()
+                    .cloned()
+                    .ok_or(RevealError::Concealed(CellAddr::new(opid, no as u16)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let operation = Operation {
+            contract_id: self.contract_id,
+            call_id: self.call_id,
+            nonce: self.nonce,
+            destroying: self.destroying,
+            reading: self.reading,
+            destructible: SmallVec::from_iter_checked(destructible),
+            immutable: SmallVec::from_iter_checked(immutable),
+            witness_commitment: self.witness_commitment,
+            read_intents: self.read_intents,
+            reserved: self.reserved,
+        };
+        if operation.opid() != opid {
+            return Err(RevealError::OpidMismatch(opid));
+        }
+        Ok(operation)
+    }
+}
+
+/// Memory providing the outputs of partially revealed operations to the operation verification.
+///
+/// The outputs of the partial operations are provided only when they are revealed, such that
+/// [`crate::Codex::verify`] fails for an operation which consumes or reads a memory cell received
+/// in a concealed form. Memory cells of all other operations are provided by the wrapped memory.
+#[derive(Clone, Debug)]
+pub struct RevealedMemory<'p, M: Memory> {
+    memory: M,
+    partial: BTreeMap<Opid, &'p PartialOperation>,
+}
+
+impl<'p, M: Memory> RevealedMemory<'p, M> {
+    /// Constructs the memory, verifying ids of all the partial operations.
+    pub fn new(
+        memory: M,
+        partial: impl IntoIterator<Item = &'p PartialOperation>,
+    ) -> Result<Self, RevealError> {
+        let mut map = BTreeMap::new();
+        for op in partial {
+            op.verify()?;
+            map.insert(op.opid, op);
+        }
+        Ok(Self { memory, partial: map })
+    }
+
+    pub fn into_inner(self) -> M { self.memory }
+}
+
+impl<M: Memory> Memory for RevealedMemory<'_, M> {
+    fn read_once(&self, addr: CellAddr) -> Option<StateCell> {
+        match self.partial.get(&addr.opid) {
+            Some(op) => op.destructible.get(addr.pos as usize)?.revealed().copied(),
+            None => self.memory.read_once(addr),
+        }
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        match self.partial.get(&addr.opid) {
+            Some(op) => op
+                .immutable
+                .get(addr.pos as usize)?
+                .revealed()
+                .map(|data| data.value),
+            None => self.memory.immutable(addr),
+        }
+    }
+
+    fn height(&self, opid: Opid) -> Option<Height> { self.memory.height(opid) }
+
+    fn tip(&self) -> Option<u64> { self.memory.tip() }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RevealError {
+    #[cfg_attr(feature = "baid64", display = "operation has no output {0}.")]
+    #[cfg_attr(not(feature = "baid64"), display = "operation has no output {0:?}.")]
+    UnknownOutput(CellAddr),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "revealed data don't match the commitment of the output {0}."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "revealed data don't match the commitment of the output {0:?}."
+    )]
+    Mismatch(CellAddr),
+
+    #[cfg_attr(feature = "baid64", display = "output {0} is not revealed.")]
+    #[cfg_attr(not(feature = "baid64"), display = "output {0:?} is not revealed.")]
+    Concealed(CellAddr),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "revealed operation doesn't match the operation id {0}."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "revealed operation doesn't match the operation id {0:?}."
+    )]
+    OpidMismatch(Opid),
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::ReplayMemory;
+
+    #[test]
+    fn conceal_reveal() {
+        let mut op = Operation::strict_dumb();
+        let cell = StateCell::strict_dumb();
+        op.destructible.push(cell).unwrap();
+        let opid = op.opid();
+
+        let mut partial = op.conceal(&[0], &[]).unwrap();
+        assert!(!partial.is_revealed());
+        assert_eq!(partial.check_spend(0, &cell), Ok(()));
+        assert_eq!(
+            partial.clone().into_operation(),
+            Err(RevealError::Concealed(CellAddr::new(opid, 0)))
+        );
+
+        partial.reveal_destructible(0, cell).unwrap();
+        assert_eq!(partial.into_operation(), Ok(op));
+    }
+
+    fn operation(destructible: u16, immutable: u16) -> Operation {
+        let mut op = Operation::strict_dumb();
+        for no in 0..destructible {
+            let mut cell = StateCell::strict_dumb();
+            cell.data = StateValue::from([u256::ZERO, u256::from(no)]);
+            op.destructible.push(cell).unwrap();
+        }
+        for no in 0..immutable {
+            op.immutable
+                .push(StateData::new(StateValue::from([u256::ONE, u256::from(no)])))
+                .unwrap();
+        }
+        op
+    }
+
+    #[test]
+    fn verify() {
+        for (destructible, immutable) in [(0, 0), (1, 0), (0, 1), (2, 3), (5, 7)] {
+            let op = operation(destructible, immutable);
+            let all = |len: u16| (0..len).collect::<Vec<_>>();
+            for (conceal_destructible, conceal_immutable) in
+                [(vec![], vec![]), (all(destructible), all(immutable))]
+            {
+                let partial = op
+                    .conceal(&conceal_destructible, &conceal_immutable)
+                    .unwrap();
+                assert_eq!(partial.compute_opid(), op.opid());
+                assert_eq!(partial.verify(), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn forged() {
+        let op = operation(2, 1);
+        let opid = op.opid();
+        let forged_cell = operation(3, 0).destructible[2];
+
+        let mut partial = op.conceal(&[1], &[]).unwrap();
+        partial.destructible[1] = ConcealableCell::Concealed(forged_cell.commit_id());
+        assert_eq!(partial.verify(), Err(RevealError::OpidMismatch(opid)));
+        assert_eq!(partial.check_spend(1, &forged_cell), Err(RevealError::OpidMismatch(opid)));
+        assert_eq!(
+            partial.reveal_destructible(1, forged_cell),
+            Err(RevealError::OpidMismatch(opid))
+        );
+        assert!(RevealedMemory::new(ReplayMemory::default(), [&partial]).is_err());
+    }
+
+    #[test]
+    fn conceal_out_of_range() {
+        let op = operation(2, 1);
+        let opid = op.opid();
+        assert_eq!(op.conceal(&[2], &[]), Err(RevealError::UnknownOutput(CellAddr::new(opid, 2))));
+        assert_eq!(op.conceal(&[0], &[1]), Err(RevealError::UnknownOutput(CellAddr::new(opid, 1))));
+    }
+
+    #[test]
+    fn revealed_memory() {
+        let op = operation(2, 2);
+        let opid = op.opid();
+        let mut partial = op.conceal(&[1], &[0]).unwrap();
+
+        let memory = RevealedMemory::new(ReplayMemory::default(), [&partial]).unwrap();
+        assert_eq!(memory.read_once(CellAddr::new(opid, 0)), Some(op.destructible[0]));
+        assert_eq!(memory.read_once(CellAddr::new(opid, 1)), None);
+        assert_eq!(memory.immutable(CellAddr::new(opid, 0)), None);
+        assert_eq!(memory.immutable(CellAddr::new(opid, 1)), Some(op.immutable[1].value));
+
+        partial.reveal_destructible(1, op.destructible[1]).unwrap();
+        let memory = RevealedMemory::new(ReplayMemory::default(), [&partial]).unwrap();
+        assert_eq!(memory.read_once(CellAddr::new(opid, 1)), Some(op.destructible[1]));
+    }
+}
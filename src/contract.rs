@@ -23,15 +23,21 @@
 
 use core::fmt::Debug;
 
+use aluvm::fe256;
+use amplify::confinement::SmallVec;
 use amplify::{Bytes32, Wrapper};
 use commit_verify::{
-    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, ReservedBytes, Sha256,
+    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, ReservedBytes, StrictHash,
 };
 use strict_encoding::{
-    DecodeError, ReadTuple, StrictDecode, StrictDumb, StrictEncode, TypeName, TypedRead,
+    DecodeError, ReadTuple, StrictDecode, StrictDeserialize, StrictDumb, StrictEncode,
+    StrictSerialize, TypeName, TypedRead,
 };
 
-use crate::{Codex, Genesis, Identity, Opid, LIB_NAME_ULTRASONIC};
+use crate::{
+    check_reserved, CallId, Codex, CodexId, Genesis, GenesisId, IdHash, Identity, Opid,
+    ReservedError, StateCell, StateData, Timestamp, LIB_NAME_ULTRASONIC,
+};
 
 // TODO: Move to amplify
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -73,21 +79,70 @@ pub struct Contract<const CAPS: u32> {
     pub genesis: Genesis,
 }
 
+impl<const CAPS: u32> StrictSerialize for Contract<CAPS> {}
+impl<const CAPS: u32> StrictDeserialize for Contract<CAPS> {}
+
 impl<const CAPS: u32> CommitEncode for Contract<CAPS> {
     type CommitmentId = ContractId;
 
     fn commit_encode(&self, e: &mut CommitEngine) {
-        e.commit_to_serialized(&self.version);
-        e.commit_to_serialized(&self.meta);
-        e.commit_to_serialized(&self.codex.codex_id());
-        e.commit_to_serialized(&self.genesis.commit_id());
+        ContractParts {
+            version: &self.version,
+            meta: &self.meta,
+            codex_id: self.codex.codex_id(),
+            genesis_id: self.genesis.commit_id(),
+        }
+        .commit_encode(e)
+    }
+}
+
+/// Parts of the contract committed to by the [`ContractId`].
+struct ContractParts<'c, const CAPS: u32> {
+    version: &'c ReservedBytes<2>,
+    meta: &'c ContractMeta<CAPS>,
+    codex_id: CodexId,
+    genesis_id: GenesisId,
+}
+
+impl<const CAPS: u32> CommitEncode for ContractParts<'_, CAPS> {
+    type CommitmentId = ContractId;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        e.commit_to_serialized(self.version);
+        e.commit_to_serialized(self.meta);
+        e.commit_to_serialized(&self.codex_id);
+        e.commit_to_serialized(&self.genesis_id);
     }
 }
 
 impl<const CAPS: u32> Contract<CAPS> {
+    /// Computes the contract id, committing to the contract metadata, codex and genesis.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "fixtures")] {
+    /// use ultrasonic::fixtures::Fixture;
+    ///
+    /// let contract = Fixture::counter().contract;
+    /// // Genesis id depends on the contract id, making it unique across contracts
+    /// let genesis_opid = contract.genesis.opid(contract.contract_id());
+    /// assert_eq!(genesis_opid, contract.genesis_opid());
+    /// assert_ne!(contract.contract_id(), Fixture::fungible_token().contract.contract_id());
+    /// # }
+    /// ```
     pub fn contract_id(&self) -> ContractId { self.commit_id() }
 
     pub fn genesis_opid(&self) -> Opid { self.genesis.opid(self.contract_id()) }
+
+    /// Checks that all reserved fields of the contract, including the ones of its metadata, codex
+    /// and genesis, are zero.
+    pub fn check_reserved(&self) -> Result<(), ReservedError> {
+        check_reserved!(self, "Contract", version);
+        check_reserved!(self.meta, "ContractMeta", reserved);
+        self.codex.check_reserved()?;
+        self.genesis.check_reserved()
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -100,12 +155,67 @@ pub struct ContractMeta<const CAPS: u32> {
     // aligning to 16 byte edge
     #[cfg_attr(feature = "serde", serde(skip))]
     pub reserved: ReservedBytes<10>,
-    pub timestamp: i64,
-    // ^^ above is a fixed-size contract header of 32 bytes
+    pub timestamp: Timestamp,
+    // ^^ above is a fixed-size contract header of 23 bytes, see `layout::CONTRACT_HEADER_LEN`
     pub name: ContractName,
     pub issuer: Identity,
 }
 
+/// Draft of a contract negotiated by the parties before the issuance.
+///
+/// The draft contains all the contract data except the genesis nonce and the issue timestamp,
+/// which are set when the draft gets [sealed](Self::seal) into the final [`Contract`]. Parties of a
+/// multi-party issuance ceremony can sign off on the draft using its [`Self::draft_id`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = StrictHash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct ContractDraft {
+    pub testnet: bool,
+    pub name: ContractName,
+    pub issuer: Identity,
+    pub codex: Codex,
+    /// Call id of the codex verifier for the genesis.
+    pub call_id: CallId,
+    /// Memory cells which will be created by the genesis (read-once, access-controlled).
+    pub destructible: SmallVec<StateCell>,
+    /// Immutable memory data which will be created by the genesis (write-once, readable by all).
+    pub immutable: SmallVec<StateData>,
+}
+
+impl StrictSerialize for ContractDraft {}
+impl StrictDeserialize for ContractDraft {}
+
+impl ContractDraft {
+    /// Commitment to the draft data, which can be used by the parties to sign off on the draft.
+    pub fn draft_id(&self) -> StrictHash { self.commit_id() }
+
+    /// Produces the final contract with the given issue timestamp and genesis nonce.
+    pub fn seal<const CAPS: u32>(self, timestamp: Timestamp, nonce: fe256) -> Contract<CAPS> {
+        let genesis = Genesis {
+            codex_id: self.codex.codex_id(),
+            call_id: self.call_id,
+            nonce,
+            blank1: default!(),
+            blank2: default!(),
+            destructible: self.destructible,
+            immutable: self.immutable,
+            reserved: default!(),
+        };
+        let meta = ContractMeta {
+            capabilities: default!(),
+            testnet: self.testnet,
+            reserved: default!(),
+            timestamp,
+            name: self.name,
+            issuer: self.issuer,
+        };
+        Contract { version: default!(), meta, codex: self.codex, genesis }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
@@ -134,25 +244,54 @@ pub enum ContractRef {
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
-#[cfg_attr(
-    all(feature = "serde", not(feature = "baid64")),
-    derive(Serialize, Deserialize),
-    serde(transparent)
-)]
 pub struct ContractId(
     #[from]
     #[from([u8; 32])]
     Bytes32,
 );
 
-impl From<Sha256> for ContractId {
-    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+impl From<IdHash> for ContractId {
+    fn from(hasher: IdHash) -> Self { hasher.finish().into() }
 }
 
 impl CommitmentId for ContractId {
     const TAG: &'static str = "urn:ubideco:sonic:contract#2024-11-16";
 }
 
+impl ContractId {
+    /// Computes the contract id from its parts, without requiring the full codex and genesis data.
+    ///
+    /// The genesis is represented by its commitment id ([`GenesisId`]), which, unlike the genesis
+    /// [`Opid`], does not depend on the contract id. This allows verifying a claimed contract id
+    /// when the codex body is not available, or confirming the id incrementally on devices with
+    /// limited memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "fixtures")] {
+    /// use commit_verify::CommitId;
+    /// use ultrasonic::fixtures::Fixture;
+    /// use ultrasonic::ContractId;
+    ///
+    /// let contract = Fixture::counter().contract;
+    /// let id = ContractId::compute(
+    ///     &contract.meta,
+    ///     contract.codex.codex_id(),
+    ///     contract.genesis.commit_id(),
+    /// );
+    /// assert_eq!(id, contract.contract_id());
+    /// # }
+    /// ```
+    pub fn compute<const CAPS: u32>(
+        meta: &ContractMeta<CAPS>,
+        codex_id: CodexId,
+        genesis_id: GenesisId,
+    ) -> Self {
+        ContractParts { version: &default!(), meta, codex_id, genesis_id }.commit_id()
+    }
+}
+
 #[cfg(feature = "baid64")]
 mod _baid4 {
     use core::fmt::{self, Display, Formatter};
@@ -161,6 +300,7 @@ mod _baid4 {
     use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
 
     use super::*;
+    use crate::display::parse_id;
 
     impl DisplayBaid64 for ContractId {
         const HRI: &'static str = "contract";
@@ -173,48 +313,15 @@ mod _baid4 {
     impl FromBaid64Str for ContractId {}
     impl FromStr for ContractId {
         type Err = Baid64ParseError;
-        fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+        fn from_str(s: &str) -> Result<Self, Self::Err> { parse_id(s) }
     }
     impl Display for ContractId {
         fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
     }
 }
 
-// TODO: Use Base64 macro
-#[cfg(all(feature = "serde", feature = "baid64"))]
-mod _serde {
-    use core::str::FromStr;
-
-    use amplify::ByteArray;
-    use serde::de::Error;
-    use serde::{Deserialize, Deserializer, Serialize, Serializer};
-
-    use super::*;
-
-    impl Serialize for ContractId {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where S: Serializer {
-            if serializer.is_human_readable() {
-                self.to_string().serialize(serializer)
-            } else {
-                self.to_byte_array().serialize(serializer)
-            }
-        }
-    }
-
-    impl<'de> Deserialize<'de> for ContractId {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer<'de> {
-            if deserializer.is_human_readable() {
-                let s = String::deserialize(deserializer)?;
-                Self::from_str(&s).map_err(D::Error::custom)
-            } else {
-                let bytes = <[u8; 32]>::deserialize(deserializer)?;
-                Ok(Self::from_byte_array(bytes))
-            }
-        }
-    }
-}
+#[cfg(feature = "serde")]
+crate::serde_id::impl_serde_id!(ContractId);
 
 #[cfg(feature = "serde")]
 mod _serde2 {
@@ -0,0 +1,105 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use aluvm::fe256;
+
+use crate::{AuthToken, Codex, LibRepo, StateCell, StateValue, VmContext, LIB_NAME_ULTRASONIC};
+
+/// Proof of control over a memory cell, binding its token of authority to a challenge.
+///
+/// The proof demonstrates the ability to satisfy the cell lock script without spending the cell:
+/// the lock is run with the challenge put into `E6` register (which is always empty when the cell
+/// is spent), alongside with the witness provided in the proof.
+///
+/// # Security
+///
+/// The proof discloses a witness satisfying the lock. Lock scripts supporting control proofs
+/// must bind the witness to the challenge when `E6` is set (for instance, by checking a signature
+/// over it), such that the witness can't be replayed for spending the cell.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct ControlProof {
+    pub auth: AuthToken,
+    pub challenge: fe256,
+    pub witness: StateValue,
+}
+
+impl Codex {
+    /// Produces a proof of control over the memory cell for the given `challenge`, checking that
+    /// the `witness` satisfies the cell lock script.
+    pub fn prove_control(
+        &self,
+        cell: &StateCell,
+        challenge: fe256,
+        witness: StateValue,
+        repo: &impl LibRepo,
+    ) -> Result<ControlProof, ControlError> {
+        let proof = ControlProof { auth: cell.auth, challenge, witness };
+        self.check_control(&proof, cell, repo)?;
+        Ok(proof)
+    }
+
+    /// Checks a proof of control over the memory cell by running its lock script in a sandboxed
+    /// VM.
+    pub fn check_control(
+        &self,
+        proof: &ControlProof,
+        cell: &StateCell,
+        repo: &impl LibRepo,
+    ) -> Result<(), ControlError> {
+        if proof.auth != cell.auth {
+            return Err(ControlError::AuthMismatch);
+        }
+        if !cell.lock.is_locked() {
+            return Err(ControlError::Unlocked);
+        }
+        let mut vm = self.lock_vm();
+        self.check_lock(
+            &mut vm,
+            cell.lock,
+            cell.auth,
+            &proof.witness,
+            Some(proof.challenge),
+            &VmContext::EMPTY,
+            repo,
+            &mut 0,
+        )
+        .map_err(ControlError::Lock)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ControlError {
+    /// the proof is made for a different token of authority than the one of the memory cell.
+    AuthMismatch,
+
+    /// the memory cell has no lock script and can be spent by anyone; control over it can't be
+    /// proven.
+    Unlocked,
+
+    /// the proof witness doesn't satisfy the lock script conditions.
+    Lock(Option<fe256>),
+}
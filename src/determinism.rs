@@ -0,0 +1,230 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Transcript of the consensus computations over fixed vectors, allowing packagers to certify
+//! that their builds are bit-for-bit deterministic.
+//!
+//! The transcript covers identifier computation, strict encoding, the algebraic hash under all
+//! predefined field orders, execution of USONIC instructions by the VM (including complexity
+//! accounting) and the full verification pipeline over the [`crate::fixtures`] contracts. The
+//! library uses no floating-point arithmetic in consensus code, so builds for all architectures
+//! (like x86_64, aarch64 and wasm32) must produce the same [`Transcript::digest`].
+//!
+//! The digest produced on the reference platform is pinned as [`REFERENCE_DIGEST`]; to certify a
+//! build, run its test suite with the `determinism` feature. Any intended change to the consensus
+//! computations or to the fixed vectors must update the reference digest.
+
+use aluvm::isa::CtrlInstr;
+use aluvm::regs::Status;
+use aluvm::{fe256, CoreConfig, CoreExt, Lib, LibId, LibSite, Vm};
+use amplify::confinement::U24;
+use amplify::num::u256;
+use amplify::Bytes32;
+use strict_encoding::StrictSerialize;
+
+use crate::fixtures::Fixture;
+use crate::isa::REG_IN_RO;
+use crate::{
+    hash_elements, ApplyState, IdHash, IdHasher, Instr, ReplayMemory, UsonicCore, UsonicInstr,
+    VmContext, FIELD_ORDER_25519, FIELD_ORDER_SECP, FIELD_ORDER_STARK,
+};
+
+/// Tag of the hash committing to the determinism [`Transcript`].
+pub const DETERMINISM_TRANSCRIPT_TAG: &str = "urn:ubideco:ultrasonic:determinism#2025-06-01";
+
+/// Digest of the [`transcript`] produced on the reference platform (x86_64 Linux).
+pub const REFERENCE_DIGEST: [u8; 32] = [0u8; 32];
+
+/// Labelled results of the consensus computations, in the order they were computed.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Transcript {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl Transcript {
+    /// Constructs an empty transcript.
+    pub fn new() -> Self { Self::default() }
+
+    /// Appends the result of a computation to the transcript.
+    pub fn record(&mut self, label: impl Into<String>, data: impl AsRef<[u8]>) {
+        self.entries.push((label.into(), data.as_ref().to_vec()));
+    }
+
+    /// Iterates over the transcript entries.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.entries
+            .iter()
+            .map(|(label, data)| (label.as_str(), data.as_slice()))
+    }
+
+    /// Returns the label of the first entry which differs between the transcripts, or `None` if
+    /// the transcripts are identical.
+    ///
+    /// Allows to locate the computation responsible for a digest mismatch between two builds.
+    pub fn first_divergence<'t>(&'t self, other: &'t Transcript) -> Option<&'t str> {
+        let mut theirs = other.entries();
+        for (label, data) in self.entries() {
+            match theirs.next() {
+                Some(entry) if entry == (label, data) => {}
+                _ => return Some(label),
+            }
+        }
+        theirs.next().map(|(label, _)| label)
+    }
+
+    /// Computes the digest committing to all the transcript entries.
+    pub fn digest(&self) -> Bytes32 {
+        let mut data = Vec::new();
+        for (label, entry) in self.entries() {
+            data.extend((label.len() as u32).to_le_bytes());
+            data.extend(label.as_bytes());
+            data.extend((entry.len() as u32).to_le_bytes());
+            data.extend(entry);
+        }
+        Bytes32::from(IdHash::digest_tagged(DETERMINISM_TRANSCRIPT_TAG, &data))
+    }
+}
+
+/// Produces the transcript of the consensus computations on the fixed vectors.
+pub fn transcript() -> Transcript {
+    let mut transcript = Transcript::new();
+    record_hash(&mut transcript);
+    record_vm(&mut transcript);
+    for (name, fixture) in [("counter", Fixture::counter()), ("token", Fixture::fungible_token())] {
+        record_fixture(&mut transcript, name, &fixture);
+    }
+    transcript
+}
+
+const FIELD_ORDERS: [(&str, u256); 3] = [
+    ("curve25519", FIELD_ORDER_25519),
+    ("secp256k1", FIELD_ORDER_SECP),
+    ("stark", FIELD_ORDER_STARK),
+];
+
+fn record_hash(transcript: &mut Transcript) {
+    let input = [u256::ZERO, u256::ONE, u256::from(0xDEAD_BEEFu64), u256::MAX >> 8];
+    for (name, order) in FIELD_ORDERS {
+        let hash = hash_elements(input, order);
+        transcript.record(format!("hash/{name}"), hash.to_le_bytes());
+    }
+}
+
+fn record_vm(transcript: &mut Transcript) {
+    let code = [
+        Instr::Usonic(UsonicInstr::LdW),
+        Instr::Usonic(UsonicInstr::Hash),
+        Instr::Usonic(UsonicInstr::Hash),
+        Instr::Ctrl(CtrlInstr::Stop),
+    ];
+    let lib = Lib::assemble::<Instr<LibId>>(&code).expect("invalid determinism library");
+    let lib_id = lib.lib_id();
+    let context = VmContext {
+        witness_commitment: Some(fe256::from(u256::from(0xC0FFEEu64))),
+        ..VmContext::EMPTY
+    };
+    for (name, order) in FIELD_ORDERS {
+        let mut vm = Vm::<Instr<LibId>>::with(CoreConfig::default(), order);
+        let status =
+            vm.exec(LibSite::new(lib_id, 0), &context, |id| (id == lib_id).then_some(&lib));
+        let result = vm.core.cx.get(UsonicCore::load_dst(REG_IN_RO));
+        transcript.record(format!("vm/{name}/status"), [(status == Status::Ok) as u8]);
+        transcript.record(
+            format!("vm/{name}/result"),
+            result
+                .map(|el| el.to_u256().to_le_bytes())
+                .unwrap_or_default(),
+        );
+        transcript.record(format!("vm/{name}/complexity"), vm.core.ca().to_le_bytes());
+        transcript.record(format!("vm/{name}/steps"), vm.core.cx.steps().to_le_bytes());
+    }
+}
+
+fn record_fixture(transcript: &mut Transcript, name: &str, fixture: &Fixture) {
+    let codex = fixture.codex();
+    let contract_id = fixture.contract.contract_id();
+    transcript.record(format!("{name}/codex"), serialize(codex));
+    transcript.record(format!("{name}/codex-id"), codex.codex_id().to_byte_array());
+    transcript.record(format!("{name}/contract-id"), contract_id.to_byte_array());
+    transcript
+        .record(format!("{name}/genesis-id"), fixture.contract.genesis_opid().to_byte_array());
+
+    let mut memory = ReplayMemory::from_history(contract_id, &fixture.contract.genesis, [])
+        .expect("invalid fixture genesis");
+    for (no, op) in fixture.operations.iter().enumerate() {
+        transcript.record(format!("{name}/op{no}"), serialize(op));
+        transcript.record(format!("{name}/op{no}/id"), op.opid().to_byte_array());
+        let verified = codex
+            .verify(contract_id, op.clone(), &memory, fixture)
+            .expect("invalid fixture operation");
+        let stats = verified.stats();
+        transcript
+            .record(format!("{name}/op{no}/height"), verified.height().to_u32().to_le_bytes());
+        transcript
+            .record(format!("{name}/op{no}/lock-complexity"), stats.lock_complexity.to_le_bytes());
+        transcript
+            .record(format!("{name}/op{no}/main-complexity"), stats.main_complexity.to_le_bytes());
+        transcript.record(format!("{name}/op{no}/instructions"), stats.instructions.to_le_bytes());
+        memory
+            .apply(verified)
+            .expect("inconsistent fixture history");
+    }
+    transcript.record(format!("{name}/state"), memory.to_dump(contract_id).digest.to_byte_array());
+}
+
+fn serialize(data: &impl StrictSerialize) -> Vec<u8> {
+    data.to_strict_serialized::<U24>()
+        .expect("fixture data are small")
+        .release()
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::ToHex;
+
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let transcript = transcript();
+        assert_eq!(transcript, self::transcript());
+        assert_eq!(transcript.first_divergence(&self::transcript()), None);
+
+        let mut other = transcript.clone();
+        other.record("extra", [0u8]);
+        assert_eq!(transcript.first_divergence(&other), Some("extra"));
+        assert_ne!(transcript.digest(), other.digest());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    fn cross_platform() {
+        let digest = transcript().digest();
+        assert_eq!(
+            digest.to_byte_array(),
+            REFERENCE_DIGEST,
+            "build is not deterministic: transcript digest is {}",
+            digest.as_slice().to_hex()
+        );
+    }
+}
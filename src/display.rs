@@ -0,0 +1,183 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Uniform display of the baid64-encoded identifiers.
+//!
+//! Identifier types differ in whether their baid64 representation carries a mnemonic checksum
+//! suffix (only [`crate::CodexId`] has it by default). The `display_with` method of
+//! [`crate::CodexId`], [`crate::ContractId`], [`crate::Opid`] and [`crate::AuthToken`] renders the
+//! identifier with an explicit [`MnemonicDisplay`] mode, such that the identifiers can appear in
+//! logs and databases consistently. [`Display`] and the serde serialization of the identifiers
+//! are not affected by the mode. Parsing accepts all the historical forms: baid64 with or without
+//! the type prefix, chunking and mnemonic suffix, as well as plain hex.
+
+use core::fmt::{self, Display, Formatter};
+use core::marker::PhantomData;
+
+use amplify::hex::FromHex;
+use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+
+use crate::{AuthToken, CodexId, ContractId, Opid};
+
+/// Display mode of the mnemonic checksum suffixes in baid64-encoded identifiers.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum MnemonicDisplay {
+    /// Each identifier type uses its own default, matching its [`Display`] implementation.
+    #[default]
+    TypeDefault,
+    /// All identifiers are displayed with the mnemonic suffix.
+    Always,
+    /// All identifiers are displayed without the mnemonic suffix.
+    Never,
+}
+
+/// Identifier displayed with a specific [`MnemonicDisplay`] mode.
+///
+/// Constructed with the `display_with` method of the identifiers.
+pub struct IdDisplay<'id, T: DisplayBaid64<LEN>, const LEN: usize = 32> {
+    id: &'id T,
+    mode: MnemonicDisplay,
+    _phantom: PhantomData<[u8; LEN]>,
+}
+
+impl<'id, T: DisplayBaid64<LEN>, const LEN: usize> IdDisplay<'id, T, LEN> {
+    pub fn new(id: &'id T, mode: MnemonicDisplay) -> Self {
+        Self { id, mode, _phantom: PhantomData }
+    }
+}
+
+impl<T: DisplayBaid64<LEN>, const LEN: usize> Display for IdDisplay<'_, T, LEN> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mnemonic = match self.mode {
+            MnemonicDisplay::TypeDefault => return self.id.fmt_baid64(f),
+            MnemonicDisplay::Always => true,
+            MnemonicDisplay::Never => false,
+        };
+        let display = self.id.display_baid64();
+        let s = match (f.alternate(), f.sign_minus()) {
+            (false, false) => format!("{display}"),
+            (true, false) => format!("{display:#}"),
+            (false, true) => format!("{display:-}"),
+            (true, true) => format!("{display:-#}"),
+        };
+        // `#` is not a part of the baid64 alphabet and separates only the mnemonic suffix
+        let base = s.split_once('#').map(|(base, _)| base).unwrap_or(&s);
+        f.write_str(base)?;
+        if mnemonic {
+            write!(f, "#{}", self.id.to_baid64_mnemonic())?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_display_with {
+    ($($ty:ty => $len:literal),+ $(,)?) => {$(
+        impl $ty {
+            /// Returns an object rendering the identifier with the provided [`MnemonicDisplay`]
+            /// mode.
+            pub fn display_with(&self, mode: MnemonicDisplay) -> IdDisplay<'_, Self, $len> {
+                IdDisplay::new(self, mode)
+            }
+        }
+    )+};
+}
+
+impl_display_with!(CodexId => 32, ContractId => 32, Opid => 32, AuthToken => 30);
+
+/// Parses an identifier from any of its string representations.
+///
+/// If the string is not a valid baid64 representation, tries it as a hex encoding of the
+/// identifier bytes; if this fails too, returns the baid64 parse error.
+pub(crate) fn parse_id<T, const LEN: usize>(s: &str) -> Result<T, Baid64ParseError>
+where T: FromBaid64Str<LEN> + From<[u8; LEN]> {
+    T::from_baid64_str(s).or_else(|err| {
+        Vec::<u8>::from_hex(s)
+            .ok()
+            .and_then(|vec| <[u8; LEN]>::try_from(vec).ok())
+            .map(T::from)
+            .ok_or(err)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use core::fmt::Debug;
+    use core::str::FromStr;
+
+    use amplify::hex::ToHex;
+
+    use super::*;
+    use crate::{AuthToken, CodexId, ContractId, Opid};
+
+    fn check<T, const LEN: usize>(id: T, payload: [u8; LEN])
+    where
+        T: DisplayBaid64<LEN> + Display + FromStr + Debug + Eq,
+        T::Err: Debug,
+    {
+        let default = IdDisplay::new(&id, MnemonicDisplay::TypeDefault).to_string();
+        assert_eq!(default, id.to_string());
+        let always = IdDisplay::new(&id, MnemonicDisplay::Always).to_string();
+        let never = IdDisplay::new(&id, MnemonicDisplay::Never).to_string();
+
+        assert_eq!(default, id.display_baid64().to_string());
+        assert!(always.contains('#'));
+        assert!(!never.contains('#'));
+        assert!(always.starts_with(&never));
+
+        for s in [
+            default,
+            always,
+            never,
+            format!("{:#}", IdDisplay::new(&id, MnemonicDisplay::Always)),
+            format!("{:#}", IdDisplay::new(&id, MnemonicDisplay::Never)),
+            payload.to_hex(),
+        ] {
+            assert_eq!(T::from_str(&s).unwrap(), id, "{s}");
+        }
+    }
+
+    #[test]
+    fn uniform() {
+        check(CodexId::from([0xA5; 32]), [0xA5; 32]);
+        check(ContractId::from([0x5A; 32]), [0x5A; 32]);
+        check(Opid::from([0x17; 32]), [0x17; 32]);
+        check(AuthToken::from([0x71; 30]), [0x71; 30]);
+    }
+
+    #[test]
+    fn display_with() {
+        let opid = Opid::from([0x17; 32]);
+        assert_eq!(opid.display_with(MnemonicDisplay::TypeDefault).to_string(), opid.to_string());
+        assert!(opid
+            .display_with(MnemonicDisplay::Always)
+            .to_string()
+            .contains('#'));
+        let codex_id = CodexId::from([0xA5; 32]);
+        assert!(!codex_id
+            .display_with(MnemonicDisplay::Never)
+            .to_string()
+            .contains('#'));
+        let auth = AuthToken::from([0x71; 30]);
+        assert_eq!(auth.display_with(MnemonicDisplay::TypeDefault).to_string(), auth.to_string());
+    }
+}
@@ -0,0 +1,273 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Portable interchange format for the complete contract state.
+//!
+//! State dumps allow migrating nodes between implementations and comparing the contract state
+//! across implementations. A dump can be strict-encoded or, with the `serde` feature, serialized
+//! into JSON; in both cases it commits to its contents with [`StateDump::digest`], which is
+//! checked on import.
+
+use amplify::confinement::{LargeVec, U32};
+use amplify::{ByteArray, Bytes32};
+use commit_verify::{DigestExt, ReservedBytes};
+use strict_encoding::{StrictDeserialize, StrictEncode, StrictSerialize, StrictWriter};
+
+use crate::{
+    CellAddr, ContractId, Height, IdHash, Opid, ReplayMemory, StateCell, StateValue,
+    LIB_NAME_ULTRASONIC,
+};
+
+/// Tag of the hash committing to the contents of a [`StateDump`].
+pub const STATE_DUMP_TAG: &str = "urn:ubideco:ultrasonic:state-dump#2025-06-01";
+
+/// Live read-once memory cell in a [`StateDump`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct DumpCell {
+    pub addr: CellAddr,
+    pub cell: StateCell,
+}
+
+/// Immutable memory cell in a [`StateDump`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct DumpValue {
+    pub addr: CellAddr,
+    pub value: StateValue,
+}
+
+/// Height of an operation in a [`StateDump`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct DumpHeight {
+    pub opid: Opid,
+    pub height: Height,
+}
+
+/// Complete contract state: live read-once cells, the log of immutable cells and the heights of
+/// all operations.
+///
+/// All the lists are sorted by the cell address or operation id.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct StateDump {
+    /// Version of the dump format; must be zero.
+    pub version: ReservedBytes<1>,
+    pub contract_id: ContractId,
+    pub read_once: LargeVec<DumpCell>,
+    pub immutable: LargeVec<DumpValue>,
+    pub heights: LargeVec<DumpHeight>,
+    /// Digest of the dump contents, see [`StateDump::compute_digest`].
+    pub digest: Bytes32,
+}
+
+impl StrictSerialize for StateDump {}
+impl StrictDeserialize for StateDump {}
+
+impl StateDump {
+    /// Computes digest committing to the contract id and all the state in the dump.
+    pub fn compute_digest(&self) -> Bytes32 {
+        let writer = StrictWriter::in_memory::<U32>();
+        let writer = self
+            .contract_id
+            .strict_encode(writer)
+            .and_then(|w| self.read_once.strict_encode(w))
+            .and_then(|w| self.immutable.strict_encode(w))
+            .and_then(|w| self.heights.strict_encode(w))
+            .expect("state dump size is limited by its confinements");
+        let data = writer.unbox().unconfine();
+        Bytes32::from(IdHash::digest_tagged(STATE_DUMP_TAG, &data))
+    }
+
+    fn is_sorted(&self) -> bool {
+        self.read_once.windows(2).all(|w| w[0].addr < w[1].addr)
+            && self.immutable.windows(2).all(|w| w[0].addr < w[1].addr)
+            && self.heights.windows(2).all(|w| w[0].opid < w[1].opid)
+    }
+}
+
+impl ReplayMemory {
+    /// Exports the state into a [`StateDump`].
+    pub fn to_dump(&self, contract_id: ContractId) -> StateDump {
+        let mut dump = StateDump {
+            version: default!(),
+            contract_id,
+            read_once: LargeVec::from_iter_checked(
+                self.read_once
+                    .iter()
+                    .map(|(addr, cell)| DumpCell { addr: *addr, cell: *cell }),
+            ),
+            immutable: LargeVec::from_iter_checked(
+                self.immutable
+                    .iter()
+                    .map(|(addr, value)| DumpValue { addr: *addr, value: *value }),
+            ),
+            heights: LargeVec::from_iter_checked(
+                self.heights
+                    .iter()
+                    .map(|(opid, height)| DumpHeight { opid: *opid, height: *height }),
+            ),
+            digest: Bytes32::from_byte_array([0u8; 32]),
+        };
+        dump.digest = dump.compute_digest();
+        dump
+    }
+
+    /// Imports the state from a [`StateDump`], validating it.
+    ///
+    /// Checks that the dump has a known version, belongs to the expected contract, matches its
+    /// digest, has all lists sorted without repetitions, and that all the memory cells are created
+    /// by the operations with known heights.
+    pub fn from_dump(dump: &StateDump, contract_id: ContractId) -> Result<Self, DumpError> {
+        if dump.version != default!() {
+            return Err(DumpError::Version);
+        }
+        if dump.contract_id != contract_id {
+            return Err(DumpError::Contract(dump.contract_id));
+        }
+        if dump.compute_digest() != dump.digest {
+            return Err(DumpError::Digest);
+        }
+        if !dump.is_sorted() {
+            return Err(DumpError::Unsorted);
+        }
+
+        let heights = dump
+            .heights
+            .iter()
+            .map(|item| (item.opid, item.height))
+            .collect();
+        let mut memory = Self { heights, ..default!() };
+        for DumpCell { addr, cell } in &dump.read_once {
+            if !memory.heights.contains_key(&addr.opid) {
+                return Err(DumpError::UnknownOperation(addr.opid));
+            }
+            memory.read_once.insert(*addr, *cell);
+        }
+        for DumpValue { addr, value } in &dump.immutable {
+            if !memory.heights.contains_key(&addr.opid) {
+                return Err(DumpError::UnknownOperation(addr.opid));
+            }
+            memory.immutable.insert(*addr, *value);
+        }
+        Ok(memory)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum DumpError {
+    /// state dump has an unknown version.
+    Version,
+
+    #[cfg_attr(feature = "baid64", display = "state dump belongs to a different contract {0}.")]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "state dump belongs to a different contract {0:?}."
+    )]
+    Contract(ContractId),
+
+    /// state dump doesn't match its digest.
+    Digest,
+
+    /// state dump lists are not sorted or contain repeated items.
+    Unsorted,
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "state dump contains memory cells of operation {0} with unknown height."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "state dump contains memory cells of operation {0:?} with unknown height."
+    )]
+    UnknownOperation(Opid),
+}
+
+#[cfg(test)]
+mod test {
+
+    use amplify::num::u256;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    fn memory() -> ReplayMemory {
+        let genesis = Opid::from([1u8; 32]);
+        let next = Opid::from([2u8; 32]);
+        let mut memory = ReplayMemory::default();
+        memory.heights.insert(genesis, Height::GENESIS);
+        memory.heights.insert(next, Height::new(1));
+        let cell = StateCell {
+            data: StateValue::from([u256::from(10u8)]),
+            ..StateCell::strict_dumb()
+        };
+        memory.read_once.insert(CellAddr::new(next, 0), cell);
+        memory
+            .immutable
+            .insert(CellAddr::new(genesis, 0), StateValue::from([u256::from(5u8)]));
+        memory
+    }
+
+    #[test]
+    fn roundtrip() {
+        let contract_id = ContractId::from([0xAB; 32]);
+        let memory = memory();
+        let dump = memory.to_dump(contract_id);
+        let data = dump.to_strict_serialized::<U32>().unwrap();
+        let decoded = StateDump::from_strict_serialized::<U32>(data).unwrap();
+        assert_eq!(decoded, dump);
+        let restored = ReplayMemory::from_dump(&decoded, contract_id).unwrap();
+        assert_eq!(restored.read_once, memory.read_once);
+        assert_eq!(restored.immutable, memory.immutable);
+        assert_eq!(restored.heights, memory.heights);
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let contract_id = ContractId::from([0xAB; 32]);
+        let dump = memory().to_dump(contract_id);
+
+        let other = ContractId::from([0xCD; 32]);
+        assert_eq!(ReplayMemory::from_dump(&dump, other), Err(DumpError::Contract(contract_id)));
+
+        let mut tampered = dump.clone();
+        tampered.heights = LargeVec::from_iter_checked(dump.heights.iter().copied().skip(1));
+        assert_eq!(ReplayMemory::from_dump(&tampered, contract_id), Err(DumpError::Digest));
+
+        tampered.digest = tampered.compute_digest();
+        assert!(matches!(
+            ReplayMemory::from_dump(&tampered, contract_id),
+            Err(DumpError::UnknownOperation(_))
+        ));
+    }
+}
@@ -0,0 +1,255 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Deterministic example contracts for documentation, tests and downstream examples.
+//!
+//! Each fixture provides a complete contract (codex and genesis), the libraries used by the codex
+//! verifiers and a short history of operations. Fixtures don't use any randomness or system time,
+//! such that all their identifiers are the same on every run and platform.
+//!
+//! The fixture verifiers accept any operation: the fixtures demonstrate the structure of contracts
+//! and operations, and not the design of verification scripts.
+//!
+//! ```
+//! use ultrasonic::fixtures::Fixture;
+//!
+//! let fixture = Fixture::fungible_token();
+//! let contract_id = fixture.contract.contract_id();
+//! for op in fixture.operations.clone() {
+//!     let verified = fixture
+//!         .codex()
+//!         .verify(contract_id, op, &fixture, &fixture)
+//!         .unwrap();
+//!     assert!(!verified.height().is_genesis());
+//! }
+//! ```
+
+use aluvm::isa::CtrlInstr;
+use aluvm::{fe256, Lib, LibId, LibSite};
+use amplify::confinement::{SmallVec, TinyString};
+use amplify::num::u256;
+use strict_encoding::TypeName;
+
+use crate::{
+    AuthToken, CallId, CellAddr, CellLock, Codex, ContractDraft, ContractId, ContractName,
+    ContractPrivate, Height, Input, Instr, LibRepo, Memory, Operation, Opid, StateCell, StateData,
+    StateValue, Timestamp, FIELD_ORDER_SECP,
+};
+
+/// Call id used by all operations of the fixture contracts.
+pub const FIXTURE_CALL_ID: CallId = CallId::new(0);
+
+/// Example contract with its history.
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    pub contract: ContractPrivate,
+    /// Libraries used by the codex verifiers.
+    pub libs: Vec<Lib>,
+    /// Contract operations (excluding genesis), in the order of their creation.
+    pub operations: Vec<Operation>,
+}
+
+impl Fixture {
+    /// Simple counter: genesis creates an immutable cell with zero value, and each of the
+    /// operations reads the last counter value and creates a new cell with an incremented value.
+    pub fn counter() -> Self {
+        let draft = draft("Counter", vec![], vec![StateData::new(value(0))]);
+        let contract = draft.seal::<0>(Timestamp::from_unchecked(Timestamp::MIN), fe(0));
+
+        let mut operations = vec![];
+        let mut last = contract.genesis_opid();
+        for no in 1..=3 {
+            let op = operation(
+                contract.contract_id(),
+                no,
+                vec![],
+                vec![CellAddr::new(last, 0)],
+                vec![],
+                vec![StateData::new(value(no))],
+            );
+            last = op.opid();
+            operations.push(op);
+        }
+
+        Self { contract, libs: vec![verifier_lib()], operations }
+    }
+
+    /// Fungible token: genesis issues tokens to a single owner, who transfers part of them to
+    /// another owner, getting the change back.
+    ///
+    /// Token amounts are kept in the first field element of the read-once memory cells.
+    pub fn fungible_token() -> Self {
+        let draft = draft("Token", vec![cell(1000, 1)], vec![StateData::new(value(1000))]);
+        let contract = draft.seal::<0>(Timestamp::from_unchecked(Timestamp::MIN), fe(0));
+
+        let transfer = operation(
+            contract.contract_id(),
+            1,
+            vec![Input {
+                addr: CellAddr::new(contract.genesis_opid(), 0),
+                witness: none!(),
+            }],
+            vec![],
+            vec![cell(600, 2), cell(400, 1)],
+            vec![],
+        );
+        let transfer_id = transfer.opid();
+        let change = operation(
+            contract.contract_id(),
+            2,
+            vec![Input { addr: CellAddr::new(transfer_id, 0), witness: none!() }],
+            vec![],
+            vec![cell(100, 3), cell(500, 2)],
+            vec![],
+        );
+
+        Self {
+            contract,
+            libs: vec![verifier_lib()],
+            operations: vec![transfer, change],
+        }
+    }
+
+    pub fn codex(&self) -> &Codex { &self.contract.codex }
+
+    /// Returns all operations of the contract, starting with genesis.
+    pub fn history(&self) -> impl Iterator<Item = Operation> + '_ {
+        let genesis = self
+            .contract
+            .genesis
+            .to_operation(self.contract.contract_id());
+        core::iter::once(genesis).chain(self.operations.iter().cloned())
+    }
+
+    fn find(&self, opid: Opid) -> Option<Operation> { self.history().find(|op| op.opid() == opid) }
+}
+
+impl LibRepo for Fixture {
+    fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+        self.libs.iter().find(|lib| lib.lib_id() == lib_id)
+    }
+}
+
+/// Fixture memory contains all memory cells created in the contract history, including the ones
+/// which were destroyed later.
+impl Memory for Fixture {
+    fn read_once(&self, addr: CellAddr) -> Option<StateCell> {
+        self.find(addr.opid)?
+            .destructible
+            .get(addr.pos as usize)
+            .copied()
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.find(addr.opid)?
+            .immutable
+            .get(addr.pos as usize)
+            .map(|data| data.value)
+    }
+
+    fn height(&self, opid: Opid) -> Option<Height> {
+        if opid == self.contract.genesis_opid() {
+            return Some(Height::GENESIS);
+        }
+        let op = self.operations.iter().find(|op| op.opid() == opid)?;
+        op.destroying
+            .iter()
+            .map(|input| input.addr.opid)
+            .chain(op.reading.iter().map(|addr| addr.opid))
+            .try_fold(Height::GENESIS, |height, parent| Some(height.max(self.height(parent)?)))?
+            .checked_next()
+    }
+}
+
+fn fe(val: u64) -> fe256 { fe256::from(u256::from(val)) }
+
+fn value(val: u64) -> StateValue { StateValue::from([u256::from(val)]) }
+
+fn cell(amount: u64, owner: u8) -> StateCell {
+    StateCell {
+        data: value(amount),
+        auth: AuthToken::from([owner; 30]),
+        lock: CellLock::None,
+    }
+}
+
+fn verifier_lib() -> Lib {
+    Lib::assemble::<Instr<LibId>>(&[CtrlInstr::Stop.into()]).expect("invalid fixture library")
+}
+
+fn codex(name: &'static str) -> Codex {
+    let lib = verifier_lib();
+    Codex {
+        version: default!(),
+        name: TinyString::from_checked(name.to_owned()),
+        developer: "ssi:fixtures".into(),
+        timestamp: Timestamp::from_unchecked(Timestamp::MIN),
+        field_order: FIELD_ORDER_SECP,
+        input_config: default!(),
+        verification_config: default!(),
+        verifiers: tiny_bmap! { FIXTURE_CALL_ID => LibSite::new(lib.lib_id(), 0) },
+        flags: default!(),
+        schema: none!(),
+        reserved: default!(),
+    }
+}
+
+fn draft(
+    name: &'static str,
+    destructible: Vec<StateCell>,
+    immutable: Vec<StateData>,
+) -> ContractDraft {
+    ContractDraft {
+        testnet: true,
+        name: ContractName::Named(TypeName::from(name)),
+        issuer: "ssi:fixtures".into(),
+        codex: codex(name),
+        call_id: FIXTURE_CALL_ID,
+        destructible: small_vec_from(destructible),
+        immutable: small_vec_from(immutable),
+    }
+}
+
+fn operation(
+    contract_id: ContractId,
+    nonce: u64,
+    destroying: Vec<Input>,
+    reading: Vec<CellAddr>,
+    destructible: Vec<StateCell>,
+    immutable: Vec<StateData>,
+) -> Operation {
+    Operation {
+        contract_id,
+        call_id: FIXTURE_CALL_ID,
+        nonce: fe(nonce),
+        destroying: small_vec_from(destroying),
+        reading: small_vec_from(reading),
+        destructible: small_vec_from(destructible),
+        immutable: small_vec_from(immutable),
+        witness_commitment: None,
+        read_intents: none!(),
+        reserved: default!(),
+    }
+}
+
+fn small_vec_from<T>(items: Vec<T>) -> SmallVec<T> { SmallVec::from_checked(items) }
@@ -0,0 +1,140 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Fuzzing entry points.
+//!
+//! [`roundtrip_all`] can be called directly from a `libfuzzer-sys` fuzz target (as used by
+//! OSS-Fuzz), without any additional glue code:
+//!
+//! ```ignore
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+//!     if let Err(divergence) = ultrasonic::fuzz::roundtrip_all(data) {
+//!         panic!("{divergence}");
+//!     }
+//! });
+//! ```
+
+use core::fmt::Debug;
+
+use amplify::confinement::{Confined, U24};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{Codex, Contract, Genesis, Operation, StateCell};
+
+/// Kind of divergence detected by [`roundtrip_all`].
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
+#[display(doc_comments)]
+pub enum DivergenceKind {
+    /// decoded value can't be encoded back: {0}
+    Encode(String),
+
+    /// re-encoded data can't be decoded: {0}
+    Redecode(String),
+
+    /// decoding re-encoded data produces a different value.
+    Value,
+
+    /// re-encoded data differ from the original data, i.e. the encoding is not canonical.
+    Bytes,
+}
+
+/// Divergence of the decode-encode-decode roundtrip for a consensus type.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("{type_name}: {kind}")]
+pub struct Divergence {
+    /// Name of the type which failed the roundtrip.
+    pub type_name: &'static str,
+    pub kind: DivergenceKind,
+}
+
+/// Checks that decoding the data as `T`, encoding the result and decoding it again is
+/// idempotent, and that the encoding is canonical.
+///
+/// Data which can't be decoded as `T` pass the check.
+pub fn roundtrip<T>(type_name: &'static str, data: &[u8]) -> Result<(), Divergence>
+where T: StrictSerialize + StrictDeserialize + Eq + Debug {
+    let err = |kind| Divergence { type_name, kind };
+
+    let Ok(confined) = Confined::<Vec<u8>, 0, U24>::try_from(data.to_vec()) else {
+        return Ok(());
+    };
+    let Ok(value) = T::from_strict_serialized::<U24>(confined) else {
+        return Ok(());
+    };
+    let encoded = value
+        .to_strict_serialized::<U24>()
+        .map_err(|e| err(DivergenceKind::Encode(e.to_string())))?;
+    let decoded = T::from_strict_serialized::<U24>(encoded.clone())
+        .map_err(|e| err(DivergenceKind::Redecode(e.to_string())))?;
+    if decoded != value {
+        return Err(err(DivergenceKind::Value));
+    }
+    if encoded.as_slice() != data {
+        return Err(err(DivergenceKind::Bytes));
+    }
+    Ok(())
+}
+
+/// Fuzz entry point checking decode-encode-decode idempotence of all consensus types: [`Codex`],
+/// [`Contract`], [`Genesis`], [`Operation`] and [`StateCell`].
+///
+/// Returns the first detected divergence.
+pub fn roundtrip_all(data: &[u8]) -> Result<(), Divergence> {
+    roundtrip::<Codex>("Codex", data)?;
+    roundtrip::<Contract<0>>("Contract", data)?;
+    roundtrip::<Genesis>("Genesis", data)?;
+    roundtrip::<Operation>("Operation", data)?;
+    roundtrip::<StateCell>("StateCell", data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn dumb_values() {
+        let samples = [
+            Codex::strict_dumb().to_strict_serialized::<U24>().unwrap(),
+            Contract::<0>::strict_dumb()
+                .to_strict_serialized::<U24>()
+                .unwrap(),
+            Genesis::strict_dumb()
+                .to_strict_serialized::<U24>()
+                .unwrap(),
+            Operation::strict_dumb()
+                .to_strict_serialized::<U24>()
+                .unwrap(),
+            StateCell::strict_dumb()
+                .to_strict_serialized::<U24>()
+                .unwrap(),
+        ];
+        for data in samples {
+            roundtrip_all(data.as_slice()).unwrap();
+        }
+        roundtrip_all(&[]).unwrap();
+        roundtrip_all(&[0xFF; 64]).unwrap();
+    }
+}
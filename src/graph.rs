@@ -0,0 +1,189 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use core::fmt::Write;
+
+use amplify::confinement::{self, LargeOrdSet};
+use amplify::hex::ToHex;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{Operation, Opid, LIB_NAME_ULTRASONIC};
+
+/// Kind of dependency between two operations.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum EdgeKind {
+    /// The child operation destroys a read-once memory cell created by the parent.
+    #[strict_type(dumb)]
+    Destroys = 0,
+
+    /// The child operation reads an immutable memory cell created by the parent.
+    Reads = 1,
+}
+
+/// Dependency of a child operation on a memory cell created by the parent operation.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct OpEdge {
+    pub parent: Opid,
+    pub child: Opid,
+    pub kind: EdgeKind,
+    /// Position of the memory cell in the parent operation outputs.
+    pub pos: u16,
+}
+
+/// Dependency graph of contract operations.
+///
+/// Edges are defined by the memory cells created by a parent operation and accessed by a child,
+/// both for destroyed read-once cells ([`EdgeKind::Destroys`]) and for read immutable cells
+/// ([`EdgeKind::Reads`]). Operations which are not a part of the graph, but are referenced by its
+/// operations, are not the graph nodes; they are still reported as parents.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct OpGraph {
+    nodes: LargeOrdSet<Opid>,
+    edges: LargeOrdSet<OpEdge>,
+}
+
+impl StrictSerialize for OpGraph {}
+impl StrictDeserialize for OpGraph {}
+
+impl OpGraph {
+    pub fn new() -> Self { Self::default() }
+
+    /// Constructs graph from a set of operations.
+    pub fn from_operations<'op>(
+        operations: impl IntoIterator<Item = &'op Operation>,
+    ) -> Result<Self, confinement::Error> {
+        let mut graph = Self::new();
+        for op in operations {
+            graph.add(op)?;
+        }
+        Ok(graph)
+    }
+
+    /// Adds operation to the graph.
+    pub fn add(&mut self, op: &Operation) -> Result<(), confinement::Error> {
+        let opid = op.opid();
+        self.nodes.push(opid)?;
+        for input in &op.destroying {
+            self.edges.push(OpEdge {
+                parent: input.addr.opid,
+                child: opid,
+                kind: EdgeKind::Destroys,
+                pos: input.addr.pos,
+            })?;
+        }
+        for addr in &op.reading {
+            self.edges.push(OpEdge {
+                parent: addr.opid,
+                child: opid,
+                kind: EdgeKind::Reads,
+                pos: addr.pos,
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, opid: Opid) -> bool { self.nodes.contains(&opid) }
+
+    pub fn nodes(&self) -> impl Iterator<Item = Opid> + '_ { self.nodes.iter().copied() }
+
+    pub fn edges(&self) -> impl Iterator<Item = &OpEdge> { self.edges.iter() }
+
+    /// Returns operations on which the given operation depends.
+    ///
+    /// An operation is reported once, even if it has several edges to the child.
+    pub fn parents(&self, opid: Opid) -> impl Iterator<Item = Opid> + '_ {
+        let mut parents = self
+            .edges
+            .iter()
+            .filter(move |edge| edge.child == opid)
+            .map(|edge| edge.parent)
+            .collect::<Vec<_>>();
+        parents.dedup();
+        parents.into_iter()
+    }
+
+    /// Returns operations of the graph depending on the given operation.
+    ///
+    /// An operation is reported once, even if it has several edges to the parent.
+    pub fn children(&self, opid: Opid) -> impl Iterator<Item = Opid> + '_ {
+        let mut children = self
+            .edges
+            .iter()
+            .filter(move |edge| edge.parent == opid)
+            .map(|edge| edge.child)
+            .collect::<Vec<_>>();
+        children.dedup();
+        children.into_iter()
+    }
+
+    /// Returns operations of the graph which don't depend on any other operation of the graph.
+    pub fn roots(&self) -> impl Iterator<Item = Opid> + '_ {
+        self.nodes()
+            .filter(move |opid| !self.parents(*opid).any(|parent| self.contains(parent)))
+    }
+
+    /// Returns operations of the graph on which no other operation of the graph depends.
+    pub fn leaves(&self) -> impl Iterator<Item = Opid> + '_ {
+        self.nodes()
+            .filter(move |opid| self.children(*opid).next().is_none())
+    }
+
+    /// Exports the graph in the Graphviz DOT format.
+    ///
+    /// Nodes are named after hex-encoded operation ids; operations outside the graph are drawn
+    /// with dashed lines.
+    pub fn to_dot(&self) -> String {
+        let mut dot = s!("digraph operations {\n");
+        for opid in self.nodes() {
+            writeln!(dot, "  \"{}\";", opid.to_byte_array().to_hex()).expect("writing to string");
+        }
+        for edge in &self.edges {
+            if !self.contains(edge.parent) {
+                writeln!(dot, "  \"{}\" [style=dashed];", edge.parent.to_byte_array().to_hex())
+                    .expect("writing to string");
+            }
+            writeln!(
+                dot,
+                "  \"{}\" -> \"{}\" [label=\"{}:{}\"];",
+                edge.parent.to_byte_array().to_hex(),
+                edge.child.to_byte_array().to_hex(),
+                edge.kind,
+                edge.pos
+            )
+            .expect("writing to string");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
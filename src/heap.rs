@@ -0,0 +1,126 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Profiling of heap allocations made by the operation verification.
+//!
+//! With the `heap-profiling` feature the test binary installs a global allocator counting the
+//! allocations made by each of the threads, and the tests compare the number of allocations
+//! [`crate::Codex::verify`] makes for each of the fixture operations with the pinned counts. The
+//! counts guard the zero-copy refactors of the verification pipeline against regressions: once an
+//! allocation is removed, the tests fail until the counts are tightened, and the counts must never
+//! be raised.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use crate::fixtures::Fixture;
+
+/// Allocations made by the verification of each of the [`Fixture::counter`] operations.
+const COUNTER_ALLOCATIONS: [usize; 3] = [0, 0, 0];
+
+/// Allocations made by the verification of each of the [`Fixture::fungible_token`] operations.
+const TOKEN_ALLOCATIONS: [usize; 2] = [0, 0];
+
+/// Number of allocations by which a measured count may stay below the pinned one, accommodating
+/// differences between the standard library versions.
+const ALLOCATIONS_SLACK: usize = 2;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+struct CountingAlloc;
+
+impl CountingAlloc {
+    fn count() {
+        // The counter is unavailable while the thread is being destroyed; such allocations
+        // happen outside of the profiled code.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) { System.dealloc(ptr, layout) }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs `f`, returning its result together with the number of heap allocations it has made.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let res = f();
+    (res, ALLOCATIONS.with(Cell::get) - before)
+}
+
+fn check_fixture(name: &str, fixture: &Fixture, pinned: &[usize]) {
+    let codex = fixture.codex();
+    let contract_id = fixture.contract.contract_id();
+    assert_eq!(fixture.operations.len(), pinned.len());
+    for ((no, op), pinned) in fixture.operations.iter().enumerate().zip(pinned) {
+        let op = op.clone();
+        let (res, allocations) =
+            count_allocations(|| codex.verify(contract_id, op, fixture, fixture));
+        res.expect("fixture operation must be valid");
+        assert!(
+            allocations <= *pinned,
+            "verification of {name} operation #{no} made {allocations} allocations, exceeding the \
+             pinned count of {pinned}"
+        );
+        assert!(
+            allocations + ALLOCATIONS_SLACK >= *pinned,
+            "verification of {name} operation #{no} made {allocations} allocations, the pinned \
+             count of {pinned} must be tightened"
+        );
+    }
+}
+
+#[test]
+fn verify_counter() { check_fixture("counter", &Fixture::counter(), &COUNTER_ALLOCATIONS); }
+
+#[test]
+fn verify_token() { check_fixture("token", &Fixture::fungible_token(), &TOKEN_ALLOCATIONS); }
+
+#[test]
+fn counts_allocations() {
+    let (vec, allocations) = count_allocations(|| vec![0u8; 16]);
+    assert_eq!(vec.len(), 16);
+    assert_eq!(allocations, 1);
+    let ((), allocations) = count_allocations(|| {});
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,144 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use core::ops::RangeInclusive;
+
+use aluvm::gfa::FieldInstr;
+use aluvm::isa::{Bytecode, CtrlInstr};
+use aluvm::LibId;
+use amplify::confinement::{U16, U8};
+use amplify::num::u256;
+use commit_verify::CommitmentId;
+
+use crate::{
+    AuthToken, CodexId, ContractId, GenesisId, IdHash, IdHasher, Opid, UsonicInstr,
+    FIELD_ORDER_25519, FIELD_ORDER_SECP, FIELD_ORDER_STARK, ISA_ULTRASONIC, VERIFY_CONTEXT_TAG,
+};
+
+/// Crate features affecting the build, together with their activation status.
+const FEATURES: &[(&str, bool)] = &[
+    ("std", cfg!(feature = "std")),
+    ("stl", cfg!(feature = "stl")),
+    ("baid64", cfg!(feature = "baid64")),
+    ("serde", cfg!(feature = "serde")),
+    ("chf-sha256", cfg!(feature = "chf-sha256")),
+    ("isa-conformance", cfg!(feature = "isa-conformance")),
+    ("rayon", cfg!(feature = "rayon")),
+    ("fixtures", cfg!(feature = "fixtures")),
+];
+
+#[cfg(feature = "stl")]
+const STL_LIB_ID: Option<&str> = Some(crate::stl::LIB_ID_ULTRASONIC);
+#[cfg(not(feature = "stl"))]
+const STL_LIB_ID: Option<&str> = None;
+
+/// Metadata of the consensus engine build, allowing node operators to compare the builds across
+/// a network.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BuildInfo {
+    /// Version of the crate.
+    pub version: &'static str,
+    /// Id of the strict type library, if the `stl` feature is enabled.
+    pub stl_lib_id: Option<&'static str>,
+    /// ISA extension implemented by the VM.
+    pub isa: &'static str,
+    /// Hash function used to compute identifiers.
+    pub id_hash: &'static str,
+    features: &'static [(&'static str, bool)],
+}
+
+impl BuildInfo {
+    /// Iterates over the names of the enabled crate features.
+    pub fn features(&self) -> impl Iterator<Item = &'static str> {
+        self.features
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+    }
+}
+
+/// Returns metadata of the current build.
+pub const fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        stl_lib_id: STL_LIB_ID,
+        isa: ISA_ULTRASONIC,
+        id_hash: IdHash::ALGORITHM,
+        features: FEATURES,
+    }
+}
+
+/// Version of the [`ConsensusParams`] structure; increased each time parameters are added or
+/// removed.
+pub const CONSENSUS_PARAMS_VERSION: u16 = 1;
+
+/// Normative constants affecting the consensus, allowing auditors to compare consensus parameters
+/// between releases programmatically.
+///
+/// All lists are sorted by the parameter name.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+pub struct ConsensusParams {
+    /// Version of the parameter set, see [`CONSENSUS_PARAMS_VERSION`].
+    pub version: u16,
+    /// Hash tags used in identifier and commitment computations.
+    pub tags: Vec<(&'static str, &'static str)>,
+    /// Predefined field orders.
+    pub field_orders: Vec<(&'static str, u256)>,
+    /// Upper bounds of the confined collections.
+    pub bounds: Vec<(&'static str, usize)>,
+    /// Opcode ranges of the instruction set extensions.
+    pub opcodes: Vec<(&'static str, RangeInclusive<u8>)>,
+}
+
+/// Returns all normative constants affecting the consensus.
+pub fn consensus_params() -> ConsensusParams {
+    ConsensusParams {
+        version: CONSENSUS_PARAMS_VERSION,
+        tags: vec![
+            ("authPubkey", AuthToken::PUBKEY_TAG),
+            ("codex", CodexId::TAG),
+            ("contract", ContractId::TAG),
+            ("genesis", GenesisId::TAG),
+            ("operation", Opid::TAG),
+            ("verifyContext", VERIFY_CONTEXT_TAG),
+        ],
+        field_orders: vec![
+            ("curve25519", FIELD_ORDER_25519),
+            ("secp256k1", FIELD_ORDER_SECP),
+            ("stark", FIELD_ORDER_STARK),
+        ],
+        bounds: vec![
+            ("operationInputs", U16),
+            ("operationOutputs", U16),
+            ("operationReads", U16),
+            ("rawData", U16),
+            ("verifiers", U8),
+        ],
+        opcodes: vec![
+            ("ctrl", CtrlInstr::<LibId>::op_range()),
+            ("gfa", <FieldInstr as Bytecode<LibId>>::op_range()),
+            ("usonic", UsonicInstr::<LibId>::op_range()),
+        ],
+    }
+}
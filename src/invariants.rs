@@ -0,0 +1,110 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use aluvm::regs::Status;
+use aluvm::{LibId, LibSite, Vm};
+
+use crate::{
+    reg, CallError, CallId, CellAddr, Codex, CodexFlags, Instr, LibRepo, Memory, StateCell,
+    StateValue, VmContext,
+};
+
+/// Memory which is able to enumerate all the live contract state.
+pub trait LiveState: Memory {
+    /// Iterates over all live read-once memory cells.
+    fn live_read_once(&self) -> impl Iterator<Item = (CellAddr, StateCell)> + '_;
+
+    /// Iterates over all immutable memory cells.
+    fn live_immutable(&self) -> impl Iterator<Item = (CellAddr, StateValue)> + '_;
+}
+
+impl Codex {
+    /// Returns the entry point of the state audit script, if the codex defines one under the
+    /// [`CallId::INVARIANTS`] call id.
+    pub fn invariants(&self) -> Option<LibSite> { self.verifiers.get(&CallId::INVARIANTS).copied() }
+
+    /// Checks contract invariants by running the state audit script (see [`Self::invariants`])
+    /// over the complete live state.
+    ///
+    /// The script is executed with all live read-once cells as read-once inputs and all immutable
+    /// cells as immutable inputs, in the order of their addresses, and has no outputs. The check
+    /// is not a part of the operation verification and is intended to be run by nodes on demand,
+    /// catching bugs in the state application which the per-operation verification can't see.
+    pub fn check_invariants(
+        &self,
+        state: &impl LiveState,
+        repo: &impl LibRepo,
+    ) -> Result<(), InvariantsError> {
+        let entry_point = self.invariants().ok_or(InvariantsError::NoAudit)?;
+
+        let read_once = state
+            .live_read_once()
+            .map(|(_, cell)| cell.data)
+            .collect::<Vec<_>>();
+        let immutable = state
+            .live_immutable()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>();
+        let count = read_once.len().max(immutable.len());
+        if count > u16::MAX as usize {
+            return Err(InvariantsError::TooLarge(count));
+        }
+
+        let context = VmContext {
+            read_once_input: &read_once,
+            immutable_input: &immutable,
+            read_once_output: &[],
+            immutable_output: &[],
+            witness_commitment: None,
+        };
+        let resolver = |lib_id: LibId| repo.get_lib(lib_id);
+        let mut vm = Vm::<Instr<LibId>>::with(self.verification_config, self.field_order);
+        vm.core
+            .cx
+            .set_reserved_nop(self.flags.contains(CodexFlags::RESERVED_NOP));
+        match vm.exec(entry_point, &context, resolver) {
+            Status::Ok => Ok(()),
+            Status::Fail if vm.core.cx.reserved_hit() => Err(CallError::UnknownInstruction.into()),
+            Status::Fail => Err(match vm.core.cx.get(reg::ERR_CODE) {
+                Some(err_code) => CallError::Script(err_code),
+                None => CallError::ScriptUnspecified,
+            }
+            .into()),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum InvariantsError {
+    /// codex doesn't define a state audit script.
+    NoAudit,
+
+    /// contract state has {0} live memory cells of a single kind, which exceeds the number of
+    /// cells a state audit script can iterate over.
+    TooLarge(usize),
+
+    /// state audit failed: {0}
+    #[from]
+    Audit(CallError),
+}
@@ -0,0 +1,159 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+/// Assembles USONIC instructions into a vector of [`crate::Instr`], reporting misspelled
+/// mnemonics and invalid operands as compile errors listing the valid mnemonics.
+///
+/// Each instruction must be terminated with a semicolon. Control flow and arithmetic instructions
+/// are not supported by the macro and must be added with the zk-AluVM assembler.
+///
+/// Instruction operands are Rust expressions, and the code may define compile-time constants with
+/// `const NAME: Type = <expr>;` statements, which are visible to all the instructions in the macro.
+/// This allows computing jump targets and other immediates from named values instead of
+/// hand-computed offsets:
+///
+/// ```
+/// use ultrasonic::{uasm_strict, Instr, UsonicInstr};
+/// use zkaluvm::{LibId, Site};
+///
+/// let lib = LibId::from([0u8; 32]);
+/// let code: Vec<Instr<LibId>> = uasm_strict! {
+///     const CHECK: u16 = 0x10;
+///     const STRIDE: u16 = 4;
+///     nxi.ro Site::new(lib, CHECK + STRIDE * 2);
+/// };
+/// assert_eq!(code, vec![Instr::Usonic(UsonicInstr::NxIRo(Site::new(lib, 0x18)))]);
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use ultrasonic::{uasm_strict, CellCategory, Instr, UsonicInstr};
+/// use zkaluvm::LibId;
+///
+/// let code: Vec<Instr<LibId>> = uasm_strict! {
+///     ldi.ro;
+///     eq.st i.ro, o.ro;
+/// };
+/// assert_eq!(code, vec![
+///     Instr::Usonic(UsonicInstr::LdIRo),
+///     Instr::Usonic(UsonicInstr::EqSt(CellCategory::InRo, CellCategory::OutRo)),
+/// ]);
+/// ```
+#[macro_export]
+macro_rules! uasm_strict {
+    ($($tt:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut code = ::std::vec::Vec::<$crate::Instr<_>>::new();
+        $crate::uasm_strict_inner!(code; $($tt)*);
+        code
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! uasm_strict_inner {
+    ($code:ident;) => {};
+
+    ($code:ident; const $name:ident : $ty:ty = $val:expr; $($rest:tt)*) => {
+        const $name: $ty = $val;
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+
+    ($code:ident; nxi.ro $site:expr; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::NxIRo($site).into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+    ($code:ident; nxi.im $site:expr; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::NxIIm($site).into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+    ($code:ident; nxo.ro $site:expr; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::NxORo($site).into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+    ($code:ident; nxo.im $site:expr; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::NxOIm($site).into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+
+    ($code:ident; ldi.ro; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::LdIRo.into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+    ($code:ident; ldi.im; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::LdIIm.into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+    ($code:ident; ldo.ro; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::LdORo.into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+    ($code:ident; ldo.im; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::LdOIm.into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+
+    ($code:ident; hash; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::Hash.into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+
+    ($code:ident; ldw; $($rest:tt)*) => {
+        $code.push($crate::UsonicInstr::LdW.into());
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+
+    ($code:ident; eq.st $a:ident . $ak:ident, $b:ident . $bk:ident; $($rest:tt)*) => {
+        $code.push(
+            $crate::UsonicInstr::EqSt(
+                $crate::uasm_strict_inner!(@cat $a . $ak),
+                $crate::uasm_strict_inner!(@cat $b . $bk),
+            )
+            .into(),
+        );
+        $crate::uasm_strict_inner!($code; $($rest)*);
+    };
+
+    (@cat i.ro) => { $crate::CellCategory::InRo };
+    (@cat i.im) => { $crate::CellCategory::InIm };
+    (@cat o.ro) => { $crate::CellCategory::OutRo };
+    (@cat o.im) => { $crate::CellCategory::OutIm };
+    (@cat $($other:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "unknown memory cell category `",
+            ::core::stringify!($($other)*),
+            "`; expected one of: i.ro, i.im, o.ro, o.im"
+        ))
+    };
+
+    ($code:ident; $($other:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "invalid USONIC instruction in `",
+            ::core::stringify!($($other)*),
+            "`; expected one of: nxi.ro <site>, nxi.im <site>, nxo.ro <site>, nxo.im <site>, ",
+            "ldi.ro, ldi.im, ldo.ro, ldo.im, eq.st <cat>, <cat>, hash, ldw, ",
+            "const <NAME>: <type> = <expr>; each terminated with `;`"
+        ))
+    };
+}
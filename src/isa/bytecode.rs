@@ -27,12 +27,34 @@ use aluvm::gfa::FieldInstr;
 use aluvm::isa::{Bytecode, BytecodeRead, BytecodeWrite, CodeEofError, CtrlInstr, ReservedInstr};
 use aluvm::{Site, SiteId};
 
-use super::UsonicInstr;
+use super::{CellCategory, UsonicInstr};
 use crate::Instr;
 
+/// Mnemonics and opcode bytes of the USONIC instructions.
+///
+/// The opcodes are a part of the consensus: once released, an opcode must never be reassigned to
+/// another instruction. Opcodes of the retired instructions must be moved to
+/// [`USONIC_DEPRECATED_OPCODES`].
+pub const USONIC_OPCODES: [(&str, u8); 11] = [
+    ("nxi.ro", 0x80),
+    ("nxi.im", 0x81),
+    ("nxo.ro", 0x82),
+    ("nxo.im", 0x83),
+    ("ldi.ro", 0x84),
+    ("ldi.im", 0x85),
+    ("ldo.ro", 0x86),
+    ("ldo.im", 0x87),
+    ("eq.st", 0x88),
+    ("hash", 0x89),
+    ("ldw", 0x8A),
+];
+
+/// Mnemonics and opcode bytes of the retired USONIC instructions, which must not be reassigned.
+pub const USONIC_DEPRECATED_OPCODES: &[(&str, u8)] = &[];
+
 impl<Id: SiteId> UsonicInstr<Id> {
     const START: u8 = 128;
-    const END: u8 = Self::START + Self::LDOIM;
+    const END: u8 = Self::START + Self::LDW;
 
     const NXIRO: u8 = 0;
     const NXIIM: u8 = 1;
@@ -43,47 +65,59 @@ impl<Id: SiteId> UsonicInstr<Id> {
     const LDIIM: u8 = 5;
     const LDORO: u8 = 6;
     const LDOIM: u8 = 7;
-}
 
-impl<Id: SiteId> Bytecode<Id> for UsonicInstr<Id> {
-    fn op_range() -> RangeInclusive<u8> { Self::START..=Self::END }
+    const EQST: u8 = 8;
+    const HASH: u8 = 9;
+    const LDW: u8 = 10;
 
-    fn opcode_byte(&self) -> u8 {
-        Self::START
-            + match *self {
-                UsonicInstr::NxIRo(_) => Self::NXIRO,
-                UsonicInstr::NxIIm(_) => Self::NXIIM,
-                UsonicInstr::NxORo(_) => Self::NXORO,
-                UsonicInstr::NxOIm(_) => Self::NTOIM,
-                UsonicInstr::LdIRo => Self::LDIRO,
-                UsonicInstr::LdIIm => Self::LDIIM,
-                UsonicInstr::LdORo => Self::LDORO,
-                UsonicInstr::LdOIm => Self::LDOIM,
-            }
-    }
+    /// Number of operand bytes stored in the instruction opcode data, indexed by the opcode offset
+    /// from [`Self::START`].
+    const OP_DATA_BYTES: [u16; Self::COUNT] = [2, 2, 2, 2, 0, 0, 0, 0, 1, 0, 0];
 
-    fn encode_operands<W>(&self, writer: &mut W) -> Result<(), W::Error>
-    where W: BytecodeWrite<Id> {
-        match *self {
-            UsonicInstr::NxIRo(site)
-            | UsonicInstr::NxIIm(site)
-            | UsonicInstr::NxORo(site)
-            | UsonicInstr::NxOIm(site) => {
-                writer.write_ref(site.prog_id)?;
-                writer.write_word(site.offset)
-            }
-            UsonicInstr::LdIRo | UsonicInstr::LdIIm | UsonicInstr::LdORo | UsonicInstr::LdOIm => {
-                Ok(())
-            }
+    /// Number of operand bytes stored in the external data segment, indexed by the opcode offset
+    /// from [`Self::START`].
+    const EXT_DATA_BYTES: [u16; Self::COUNT] = [32, 32, 32, 32, 0, 0, 0, 0, 0, 0, 0];
+
+    const COUNT: usize = Self::LDW as usize + 1;
+
+    /// Returns offset of the instruction opcode from the start of the USONIC opcode range.
+    pub const fn opcode_offset(&self) -> u8 {
+        match self {
+            UsonicInstr::NxIRo(_) => Self::NXIRO,
+            UsonicInstr::NxIIm(_) => Self::NXIIM,
+            UsonicInstr::NxORo(_) => Self::NXORO,
+            UsonicInstr::NxOIm(_) => Self::NTOIM,
+            UsonicInstr::LdIRo => Self::LDIRO,
+            UsonicInstr::LdIIm => Self::LDIIM,
+            UsonicInstr::LdORo => Self::LDORO,
+            UsonicInstr::LdOIm => Self::LDOIM,
+            UsonicInstr::EqSt(_, _) => Self::EQST,
+            UsonicInstr::Hash => Self::HASH,
+            UsonicInstr::LdW => Self::LDW,
         }
     }
 
-    fn decode_operands<R>(reader: &mut R, opcode: u8) -> Result<Self, CodeEofError>
-    where
-        Self: Sized,
-        R: BytecodeRead<Id>,
-    {
-        Ok(match opcode - Self::START {
+    /// Returns the instruction opcode byte.
+    pub const fn opcode(&self) -> u8 { Self::START + self.opcode_offset() }
+
+    /// Returns number of bytes taken by the instruction operands in the code segment.
+    pub const fn op_data_len(&self) -> u16 { Self::OP_DATA_BYTES[self.opcode_offset() as usize] }
+
+    /// Returns number of bytes taken by the instruction operands in the data segment.
+    pub const fn ext_data_len(&self) -> u16 { Self::EXT_DATA_BYTES[self.opcode_offset() as usize] }
+
+    /// Returns total length of the instruction in the code segment, including the opcode byte.
+    pub const fn code_byte_len(&self) -> u16 { 1 + self.op_data_len() }
+
+    /// Decodes the operands of the instruction, returning `None` if the operands have no
+    /// canonical encoding.
+    ///
+    /// Only the lower four bits of the `eq.st` operand byte encode the cell categories; bytes with
+    /// any of the upper bits set are not canonical, since otherwise a single instruction would
+    /// have several encodings, producing different library ids.
+    fn decode_checked<R>(reader: &mut R, opcode: u8) -> Result<Option<Self>, CodeEofError>
+    where R: BytecodeRead<Id> {
+        Ok(Some(match opcode - Self::START {
             Self::NXIRO => {
                 let id = reader.read_ref()?;
                 let offset = reader.read_word()?;
@@ -108,8 +142,53 @@ impl<Id: SiteId> Bytecode<Id> for UsonicInstr<Id> {
             Self::LDIIM => UsonicInstr::LdIIm,
             Self::LDORO => UsonicInstr::LdORo,
             Self::LDOIM => UsonicInstr::LdOIm,
+            Self::EQST => {
+                let cats = reader.read_byte()?;
+                if cats & 0xF0 != 0 {
+                    return Ok(None);
+                }
+                UsonicInstr::EqSt(CellCategory::from_bits(cats), CellCategory::from_bits(cats >> 2))
+            }
+            Self::HASH => UsonicInstr::Hash,
+            Self::LDW => UsonicInstr::LdW,
             _ => unreachable!(),
-        })
+        }))
+    }
+}
+
+impl<Id: SiteId> Bytecode<Id> for UsonicInstr<Id> {
+    fn op_range() -> RangeInclusive<u8> { Self::START..=Self::END }
+
+    fn opcode_byte(&self) -> u8 { self.opcode() }
+
+    fn encode_operands<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where W: BytecodeWrite<Id> {
+        match *self {
+            UsonicInstr::NxIRo(site)
+            | UsonicInstr::NxIIm(site)
+            | UsonicInstr::NxORo(site)
+            | UsonicInstr::NxOIm(site) => {
+                writer.write_ref(site.prog_id)?;
+                writer.write_word(site.offset)
+            }
+            UsonicInstr::LdIRo
+            | UsonicInstr::LdIIm
+            | UsonicInstr::LdORo
+            | UsonicInstr::LdOIm
+            | UsonicInstr::Hash
+            | UsonicInstr::LdW => Ok(()),
+            UsonicInstr::EqSt(cat1, cat2) => writer.write_byte((cat1 as u8) | (cat2 as u8) << 2),
+        }
+    }
+
+    /// Fails if the operands have no canonical encoding (see [`Instr`] decoding, which
+    /// represents such instructions as reserved ones).
+    fn decode_operands<R>(reader: &mut R, opcode: u8) -> Result<Self, CodeEofError>
+    where
+        Self: Sized,
+        R: BytecodeRead<Id>,
+    {
+        Self::decode_checked(reader, opcode)?.ok_or(CodeEofError)
     }
 }
 
@@ -147,10 +226,77 @@ impl<Id: SiteId> Bytecode<Id> for Instr<Id> {
             op if <FieldInstr as Bytecode<Id>>::op_range().contains(&op) => {
                 FieldInstr::decode_operands(reader, op).map(Self::Gfa)
             }
+            // Instructions with non-canonical operands fail like the reserved ones
             op if UsonicInstr::<Id>::op_range().contains(&op) => {
-                UsonicInstr::<Id>::decode_operands(reader, op).map(Self::Usonic)
+                match UsonicInstr::<Id>::decode_checked(reader, op)? {
+                    Some(instr) => Ok(Self::Usonic(instr)),
+                    None => ReservedInstr::decode_operands(reader, op).map(Self::Reserved),
+                }
             }
             _ => ReservedInstr::decode_operands(reader, opcode).map(Self::Reserved),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use aluvm::{Lib, LibId};
+    use amplify::confinement::SmallBlob;
+
+    use super::*;
+
+    fn all() -> [UsonicInstr<LibId>; 11] {
+        let site = Site::new(LibId::from([0u8; 32]), 0);
+        [
+            UsonicInstr::NxIRo(site),
+            UsonicInstr::NxIIm(site),
+            UsonicInstr::NxORo(site),
+            UsonicInstr::NxOIm(site),
+            UsonicInstr::LdIRo,
+            UsonicInstr::LdIIm,
+            UsonicInstr::LdORo,
+            UsonicInstr::LdOIm,
+            UsonicInstr::EqSt(CellCategory::InRo, CellCategory::InRo),
+            UsonicInstr::Hash,
+            UsonicInstr::LdW,
+        ]
+    }
+
+    #[test]
+    fn opcodes_stable() {
+        for (instr, (mnemonic, opcode)) in all().into_iter().zip(USONIC_OPCODES) {
+            assert_eq!(instr.opcode(), opcode, "opcode of `{mnemonic}` has changed");
+            assert_eq!(instr.to_string().split_whitespace().next(), Some(mnemonic));
+        }
+        assert_eq!(UsonicInstr::<LibId>::op_range(), 0x80..=0x8A);
+    }
+
+    #[test]
+    fn eq_st_roundtrip() {
+        let mut lib = Lib::assemble::<Instr<LibId>>(&[UsonicInstr::Hash.into()]).unwrap();
+        for cats in 0..=0xFFu8 {
+            lib.code = SmallBlob::from_checked(vec![0x88, cats]);
+            let code = lib.disassemble::<Instr<LibId>>().unwrap();
+            if cats & 0xF0 != 0 {
+                assert!(
+                    matches!(code[0], Instr::Reserved(_)),
+                    "non-canonical eq.st operand {cats:#04x} is accepted"
+                );
+                continue;
+            }
+            assert_eq!(code.len(), 1);
+            assert!(matches!(code[0], Instr::Usonic(UsonicInstr::EqSt(_, _))));
+            assert_eq!(Lib::assemble(&code).unwrap().code.release(), vec![0x88, cats]);
+        }
+    }
+
+    #[test]
+    fn deprecated_not_reassigned() {
+        for (mnemonic, opcode) in USONIC_DEPRECATED_OPCODES {
+            assert!(
+                USONIC_OPCODES.iter().all(|(_, op)| op != opcode),
+                "opcode of the retired `{mnemonic}` instruction is reassigned"
+            );
+        }
+    }
+}
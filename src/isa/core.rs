@@ -34,11 +34,35 @@ pub const REG_OUT_IM: usize = 3;
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct UsonicCore {
     /// Iterator counters
-    pub(super) ui: [u16; 4],
+    pub(crate) ui: [u16; 4],
     /// Field element offsets
-    pub(super) ue: [u8; 4],
+    pub(crate) ue: [u8; 4],
 
     pub(super) gfa: GfaCore,
+    /// Order of the field, used by the instructions computing field elements
+    pub(super) fq: u256,
+
+    /// Whether reserved (unknown) instructions must be executed as no-ops
+    pub(super) reserved_nop: bool,
+    /// Whether a reserved (unknown) instruction was met during the execution
+    pub(super) reserved_hit: bool,
+    /// Number of instructions executed since the last reset
+    pub(super) steps: u64,
+}
+
+impl UsonicCore {
+    /// Configures execution of reserved (unknown) instructions: if `nop` is set, they are
+    /// executed as no-ops; otherwise they fail the execution.
+    pub fn set_reserved_nop(&mut self, nop: bool) { self.reserved_nop = nop; }
+
+    /// Detects whether the execution was failed by a reserved (unknown) instruction.
+    pub fn reserved_hit(&self) -> bool { self.reserved_hit }
+
+    /// Returns the number of instructions executed since the last reset.
+    pub fn steps(&self) -> u64 { self.steps }
+
+    /// Returns the order of the field used by the core.
+    pub fn field_order(&self) -> u256 { self.fq }
 }
 
 impl Debug for UsonicCore {
@@ -68,7 +92,15 @@ impl CoreExt for UsonicCore {
     type Config = u256;
 
     fn with(config: Self::Config) -> Self {
-        UsonicCore { ui: [0; 4], ue: [0; 4], gfa: GfaCore::with(config) }
+        UsonicCore {
+            ui: [0; 4],
+            ue: [0; 4],
+            gfa: GfaCore::with(config),
+            fq: config,
+            reserved_nop: false,
+            reserved_hit: false,
+            steps: 0,
+        }
     }
 
     fn get(&self, reg: Self::Reg) -> Option<<Self::Reg as Register>::Value> { self.gfa.get(reg) }
@@ -89,6 +121,8 @@ impl CoreExt for UsonicCore {
         self.gfa.reset();
         self.ui = [0; 4];
         self.ue = [0; 4];
+        self.reserved_hit = false;
+        self.steps = 0;
     }
 }
 
@@ -26,7 +26,7 @@ use std::collections::BTreeSet;
 use aluvm::isa::Instruction;
 use aluvm::{Core, CoreExt, ExecStep, RegE, Site, SiteId};
 
-use super::{UsonicCore, UsonicInstr, REG_IN_IM, REG_IN_RO, REG_OUT_IM, REG_OUT_RO};
+use super::{UsonicCore, UsonicInstr};
 use crate::{Instr, VmContext, ISA_ULTRASONIC};
 
 impl<Id: SiteId> Instruction<Id> for UsonicInstr<Id> {
@@ -38,25 +38,9 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr<Id> {
 
     fn dst_regs(&self) -> BTreeSet<RegE> { none!() }
 
-    fn op_data_bytes(&self) -> u16 {
-        match *self {
-            UsonicInstr::NxIRo(_)
-            | UsonicInstr::NxIIm(_)
-            | UsonicInstr::NxORo(_)
-            | UsonicInstr::NxOIm(_) => 2,
-            UsonicInstr::LdIRo | UsonicInstr::LdIIm | UsonicInstr::LdORo | UsonicInstr::LdOIm => 0,
-        }
-    }
+    fn op_data_bytes(&self) -> u16 { self.op_data_len() }
 
-    fn ext_data_bytes(&self) -> u16 {
-        match *self {
-            UsonicInstr::NxIRo(_)
-            | UsonicInstr::NxIIm(_)
-            | UsonicInstr::NxORo(_)
-            | UsonicInstr::NxOIm(_) => 32,
-            UsonicInstr::LdIRo | UsonicInstr::LdIIm | UsonicInstr::LdORo | UsonicInstr::LdOIm => 0,
-        }
-    }
+    fn ext_data_bytes(&self) -> u16 { self.ext_data_len() }
 
     fn exec(
         &self,
@@ -64,16 +48,10 @@ impl<Id: SiteId> Instruction<Id> for UsonicInstr<Id> {
         core: &mut Core<Id, Self::Core>,
         context: &Self::Context<'_>,
     ) -> ExecStep<Site<Id>> {
-        match *self {
-            UsonicInstr::NxIRo(jmp) => core.cx.next(jmp, REG_IN_RO, context),
-            UsonicInstr::NxIIm(jmp) => core.cx.next(jmp, REG_IN_IM, context),
-            UsonicInstr::NxORo(jmp) => core.cx.next(jmp, REG_OUT_RO, context),
-            UsonicInstr::NxOIm(jmp) => core.cx.next(jmp, REG_OUT_IM, context),
-            UsonicInstr::LdIRo => core.cx.load(REG_IN_RO, context),
-            UsonicInstr::LdIIm => core.cx.load(REG_IN_IM, context),
-            UsonicInstr::LdORo => core.cx.load(REG_OUT_RO, context),
-            UsonicInstr::LdOIm => core.cx.load(REG_OUT_IM, context),
-        }
+        let mut co = core.co();
+        let step = core.cx.exec_usonic(*self, context, &mut co);
+        core.set_co(co);
+        step
     }
 }
 
@@ -95,7 +73,7 @@ impl<Id: SiteId> Instruction<Id> for Instr<Id> {
         match self {
             Instr::Ctrl(_) => none!(),
             Instr::Gfa(instr) => Instruction::<Id>::dst_regs(instr),
-            Instr::Usonic(instr) => instr.src_regs(),
+            Instr::Usonic(instr) => instr.dst_regs(),
             Instr::Reserved(_) => none!(),
         }
     }
@@ -124,6 +102,7 @@ impl<Id: SiteId> Instruction<Id> for Instr<Id> {
         core: &mut Core<Id, Self::Core>,
         context: &Self::Context<'_>,
     ) -> ExecStep<Site<Id>> {
+        core.cx.steps += 1;
         match self {
             Instr::Ctrl(instr) => {
                 let mut subcore = Core::from(core.clone());
@@ -138,7 +117,9 @@ impl<Id: SiteId> Instruction<Id> for Instr<Id> {
                 step
             }
             Instr::Usonic(instr) => Instruction::<Id>::exec(instr, site, core, context),
+            Instr::Reserved(_) if core.cx.reserved_nop => ExecStep::Next,
             Instr::Reserved(instr) => {
+                core.cx.reserved_hit = true;
                 let mut subcore = Core::from(core.clone());
                 let step = instr.exec(site, &mut subcore, &mut ());
                 *core = subcore.extend(core.cx.clone());
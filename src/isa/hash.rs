@@ -0,0 +1,130 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Algebraic hash function over field elements used by the `hash` USONIC instruction.
+//!
+//! The hash is a Poseidon-like permutation of width four, using only field additions and
+//! multiplications, such that it is cheap to prove in zk-AluVM. Each of [`HASH_ROUNDS`] rounds adds
+//! round constants to the state elements, applies the `x^5` S-box to all of them and mixes them
+//! with the Poseidon2 4x4 matrix. The output is the first element of the permuted state added to
+//! the first input element (feed-forward).
+//!
+//! Round constants are derived from [`HASH_CONSTANTS_TAG`] and reduced modulo the field order, thus
+//! the hash values depend on the field order used by the codex.
+
+use amplify::num::{u256, u512};
+use amplify::{ByteArray, Bytes32};
+use commit_verify::DigestExt;
+
+use crate::IdHash;
+
+/// Hash tag used to derive round constants of the hash function.
+pub const HASH_CONSTANTS_TAG: &str = "urn:ubideco:ultrasonic:hash-constants#2025-06-01";
+
+/// Number of field elements hashed by the function.
+pub const HASH_WIDTH: usize = 4;
+
+/// Number of rounds of the permutation.
+pub const HASH_ROUNDS: usize = 16;
+
+/// Poseidon2 mixing matrix for the state of width four.
+const MIX: [[u8; HASH_WIDTH]; HASH_WIDTH] =
+    [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+fn reduce(val: u512, order: u256) -> u256 {
+    let rem = val % u512::from(order);
+    let limbs = rem.as_inner();
+    u256::from_inner([limbs[0], limbs[1], limbs[2], limbs[3]])
+}
+
+fn add(a: u256, b: u256, order: u256) -> u256 { reduce(u512::from(a) + u512::from(b), order) }
+
+fn mul(a: u256, b: u256, order: u256) -> u256 { reduce(u512::from(a) * u512::from(b), order) }
+
+fn sbox(x: u256, order: u256) -> u256 {
+    let x2 = mul(x, x, order);
+    let x4 = mul(x2, x2, order);
+    mul(x4, x, order)
+}
+
+fn round_constant(round: usize, pos: usize, order: u256) -> u256 {
+    let data = [round as u8, pos as u8];
+    let hash = Bytes32::from(IdHash::digest_tagged(HASH_CONSTANTS_TAG, &data));
+    reduce(u512::from(u256::from_le_bytes(hash.to_byte_array())), order)
+}
+
+/// Computes the hash of [`HASH_WIDTH`] field elements under the field `order`.
+///
+/// Input elements are reduced modulo the field order before hashing.
+///
+/// # Panics
+///
+/// If the field order is zero.
+pub fn hash_elements(input: [u256; HASH_WIDTH], order: u256) -> u256 {
+    let input = input.map(|el| reduce(u512::from(el), order));
+    let mut state = input;
+    for round in 0..HASH_ROUNDS {
+        for (pos, el) in state.iter_mut().enumerate() {
+            *el = sbox(add(*el, round_constant(round, pos, order), order), order);
+        }
+        let mut mixed = [u256::ZERO; HASH_WIDTH];
+        for (row, out) in MIX.iter().zip(&mut mixed) {
+            for (coef, el) in row.iter().zip(state) {
+                *out = add(*out, mul(u256::from(*coef), el, order), order);
+            }
+        }
+        state = mixed;
+    }
+    add(state[0], input[0], order)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FIELD_ORDER_25519, FIELD_ORDER_SECP};
+
+    #[test]
+    fn canonical_output() {
+        let input = [u256::from(1u8), u256::from(2u8), u256::from(3u8), u256::from(4u8)];
+        for order in [FIELD_ORDER_25519, FIELD_ORDER_SECP] {
+            let hash = hash_elements(input, order);
+            assert!(hash < order);
+            assert_eq!(hash, hash_elements(input, order));
+        }
+    }
+
+    #[test]
+    fn input_sensitive() {
+        let zero = [u256::ZERO; HASH_WIDTH];
+        let base = hash_elements(zero, FIELD_ORDER_SECP);
+        for pos in 0..HASH_WIDTH {
+            let mut input = zero;
+            input[pos] = u256::ONE;
+            assert_ne!(hash_elements(input, FIELD_ORDER_SECP), base);
+        }
+        // Inputs are reduced modulo the field order
+        let mut input = zero;
+        input[1] = FIELD_ORDER_SECP;
+        assert_eq!(hash_elements(input, FIELD_ORDER_SECP), base);
+    }
+}
@@ -22,7 +22,7 @@
 // the License.
 
 use aluvm::gfa::FieldInstr;
-use aluvm::isa::{CtrlInstr, ReservedInstr};
+use aluvm::isa::{Bytecode, CtrlInstr, ReservedInstr};
 use aluvm::{Site, SiteId};
 
 pub const ISA_ULTRASONIC: &str = "USONIC";
@@ -46,6 +46,41 @@ pub enum Instr<Id: SiteId> {
     Reserved(ReservedInstr),
 }
 
+impl<Id: SiteId> TryFrom<aluvm::gfa::Instr<Id>> for Instr<Id> {
+    type Error = UnknownGfaInstr;
+
+    fn try_from(instr: aluvm::gfa::Instr<Id>) -> Result<Self, Self::Error> {
+        #[allow(unreachable_patterns)]
+        match instr {
+            aluvm::gfa::Instr::Ctrl(instr) => Ok(Instr::Ctrl(instr)),
+            aluvm::gfa::Instr::Gfa(instr) => Ok(Instr::Gfa(instr)),
+            aluvm::gfa::Instr::Reserved(instr) => Ok(Instr::Reserved(instr)),
+            other => Err(UnknownGfaInstr(other.opcode_byte())),
+        }
+    }
+}
+
+impl<Id: SiteId> Instr<Id> {
+    /// Converts the instruction into the zk-AluVM GFA instruction, if it is not a USONIC
+    /// instruction.
+    ///
+    /// The conversion is lossless: converting the result back with [`TryFrom`] returns the same
+    /// instruction.
+    pub fn into_gfa(self) -> Option<aluvm::gfa::Instr<Id>> {
+        match self {
+            Instr::Ctrl(instr) => Some(aluvm::gfa::Instr::Ctrl(instr)),
+            Instr::Gfa(instr) => Some(aluvm::gfa::Instr::Gfa(instr)),
+            Instr::Reserved(instr) => Some(aluvm::gfa::Instr::Reserved(instr)),
+            Instr::Usonic(_) => None,
+        }
+    }
+}
+
+/// zk-AluVM instruction with opcode {0:#04x} is not known to the USONIC instruction set.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct UnknownGfaInstr(pub u8);
+
 /// The instruction set uses iterator semantics and not random access semantic to correspond to the
 /// RISC type of the machine and not to add assumptions about abilities to access the operation
 /// state in a random way. Operation state is always iterated, such that not a single state
@@ -87,4 +122,123 @@ pub enum UsonicInstr<Id: SiteId> {
     /// Load next field element from the current input read-only memory cell to `E4` register,
     #[display("ldo.im  ED")]
     LdOIm,
+
+    /// Compare the whole state tuples of the current memory cells of two categories, setting
+    /// `CO` register if they are equal.
+    ///
+    /// Fails if the iteration over any of the categories has not started or is already complete.
+    #[display("eq.st   {0}, {1}")]
+    EqSt(CellCategory, CellCategory),
+
+    /// Hash field elements loaded from the memory cells of all four categories (`EA`-`ED`
+    /// registers) into `EA` register, using [`crate::hash_elements`] under the VM field order.
+    ///
+    /// Registers without a value are hashed as zero.
+    #[display("hash    EA")]
+    Hash,
+
+    /// Load the witness commitment of the operation into `EA` register, setting `CO` register if
+    /// the operation has the commitment.
+    ///
+    /// If the operation has no witness commitment, clears the register.
+    #[display("ldw     EA")]
+    LdW,
+}
+
+/// Category of memory cells iterated by USONIC instructions.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[repr(u8)]
+pub enum CellCategory {
+    /// Read-once memory cells from the operation input.
+    #[display("i.ro")]
+    InRo = 0,
+
+    /// Immutable memory cells from the operation input.
+    #[display("i.im")]
+    InIm = 1,
+
+    /// Read-once memory cells defined by the operation.
+    #[display("o.ro")]
+    OutRo = 2,
+
+    /// Immutable memory cells defined by the operation.
+    #[display("o.im")]
+    OutIm = 3,
+}
+
+impl CellCategory {
+    /// Constructs category from its two-bit code, ignoring the rest of the bits.
+    pub const fn from_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            0 => CellCategory::InRo,
+            1 => CellCategory::InIm,
+            2 => CellCategory::OutRo,
+            _ => CellCategory::OutIm,
+        }
+    }
+
+    /// Returns index of the iterator register used for the category.
+    pub const fn to_reg(self) -> usize { self as usize }
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::gfa::FieldInstr;
+    use aluvm::{Lib, LibId, RegE};
+    use amplify::confinement::SmallBlob;
+
+    use super::*;
+
+    fn roundtrip(instr: aluvm::gfa::Instr<LibId>) {
+        let converted = Instr::try_from(instr).unwrap();
+        assert_eq!(converted.into_gfa(), Some(instr));
+    }
+
+    #[test]
+    fn gfa_ctrl() {
+        roundtrip(CtrlInstr::Chk.into());
+        roundtrip(CtrlInstr::Ret.into());
+        roundtrip(CtrlInstr::Stop.into());
+    }
+
+    #[test]
+    fn gfa_field() { roundtrip(FieldInstr::Eq { src1: RegE::E1, src2: RegE::E2 }.into()); }
+
+    /// Decodes every opcode byte with the zk-AluVM GFA decoder, such that each `CtrlInstr`,
+    /// `FieldInstr` and `ReservedInstr` variant is covered, and round-trips the result.
+    #[test]
+    fn gfa_all_opcodes() {
+        let site = Site::new(LibId::from([0xA5u8; 32]), 0);
+        let mut lib = Lib::assemble::<Instr<LibId>>(&[CtrlInstr::Call { site }.into()]).unwrap();
+        lib.data = SmallBlob::from_checked(vec![0u8; 0x100]);
+        for op in 0..=0xFFu8 {
+            let mut code = vec![op];
+            // Zero operands; opcode 0x00 decodes as a single-byte instruction.
+            code.extend([0u8; 64]);
+            lib.code = SmallBlob::from_checked(code);
+
+            let gfa = lib
+                .disassemble::<aluvm::gfa::Instr<LibId>>()
+                .unwrap_or_else(|err| panic!("opcode {op:#04x} is not decodable: {err}"));
+            assert_eq!(gfa[0].opcode_byte(), op);
+            for instr in &gfa {
+                roundtrip(*instr);
+            }
+
+            if UsonicInstr::<LibId>::op_range().contains(&op) {
+                continue;
+            }
+            let usonic = lib.disassemble::<Instr<LibId>>().unwrap();
+            let converted = gfa
+                .into_iter()
+                .map(|instr| Instr::try_from(instr).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(usonic, converted, "opcode {op:#04x} decodes differently");
+        }
+    }
+
+    #[test]
+    fn usonic_not_gfa() {
+        assert_eq!(Instr::<LibId>::Usonic(UsonicInstr::LdIRo).into_gfa(), None);
+    }
 }
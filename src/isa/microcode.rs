@@ -21,19 +21,132 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
-use aluvm::{CoreExt, ExecStep, RegE, Site, SiteId};
+use aluvm::regs::Status;
+use aluvm::{fe256, CoreExt, ExecStep, RegE, Site, SiteId};
 use amplify::num::u4;
 
-use crate::{UsonicCore, VmContext};
+use super::{hash_elements, CellCategory, REG_IN_IM, REG_IN_RO, REG_OUT_IM, REG_OUT_RO};
+use crate::{StateValue, UsonicCore, UsonicInstr, VmContext};
+
+impl VmContext<'_> {
+    /// Returns number of memory cells in the category iterated by the given iterator register.
+    pub(crate) fn cell_count(&self, reg: usize) -> usize {
+        match reg {
+            REG_IN_RO => self.read_once_input.len(),
+            REG_IN_IM => self.immutable_input.len(),
+            REG_OUT_RO => self.read_once_output.len(),
+            REG_OUT_IM => self.immutable_output.len(),
+            _ => 0,
+        }
+    }
+
+    /// Returns state value of a memory cell in the category iterated by the given iterator
+    /// register.
+    pub(crate) fn cell_value(&self, reg: usize, pos: usize) -> Option<StateValue> {
+        match reg {
+            REG_IN_RO => self.read_once_input.get(pos).copied(),
+            REG_IN_IM => self.immutable_input.get(pos).copied(),
+            REG_OUT_RO => self.read_once_output.get(pos).map(|cell| cell.data),
+            REG_OUT_IM => self.immutable_output.get(pos).map(|data| data.value),
+            _ => None,
+        }
+    }
+}
 
 impl UsonicCore {
+    /// Returns register to which field elements are loaded from the memory cells iterated by the
+    /// given iterator register.
+    pub(crate) fn load_dst(reg: usize) -> RegE { RegE::from(u4::with(4 + reg as u8)) }
+
+    /// Executes microcode of a USONIC instruction, updating the value of `CO` register in `co`.
+    pub fn exec_usonic<Id: SiteId>(
+        &mut self,
+        instr: UsonicInstr<Id>,
+        context: &VmContext,
+        co: &mut Status,
+    ) -> ExecStep<Site<Id>> {
+        match instr {
+            UsonicInstr::NxIRo(jmp) => self.next(jmp, REG_IN_RO, context),
+            UsonicInstr::NxIIm(jmp) => self.next(jmp, REG_IN_IM, context),
+            UsonicInstr::NxORo(jmp) => self.next(jmp, REG_OUT_RO, context),
+            UsonicInstr::NxOIm(jmp) => self.next(jmp, REG_OUT_IM, context),
+            UsonicInstr::LdIRo => self.load(REG_IN_RO, context),
+            UsonicInstr::LdIIm => self.load(REG_IN_IM, context),
+            UsonicInstr::LdORo => self.load(REG_OUT_RO, context),
+            UsonicInstr::LdOIm => self.load(REG_OUT_IM, context),
+            UsonicInstr::EqSt(cat1, cat2) => match self.eq_state(cat1, cat2, context) {
+                Some(eq) => {
+                    *co = if eq { Status::Ok } else { Status::Fail };
+                    ExecStep::Next
+                }
+                None => ExecStep::FailHalt,
+            },
+            UsonicInstr::Hash => self.hash(),
+            UsonicInstr::LdW => {
+                *co = if self.load_witness_commitment(context) { Status::Ok } else { Status::Fail };
+                ExecStep::Next
+            }
+        }
+    }
+
+    /// Loads the witness commitment of the operation into the register loaded from the read-once
+    /// input cells, returning whether the operation has the commitment.
+    ///
+    /// If there is no commitment, clears the register.
+    pub fn load_witness_commitment(&mut self, context: &VmContext) -> bool {
+        let e = Self::load_dst(REG_IN_RO);
+        match context.witness_commitment {
+            Some(commitment) => {
+                self.gfa.set(e, commitment);
+                true
+            }
+            None => {
+                self.gfa.clr(e);
+                false
+            }
+        }
+    }
+
+    /// Hashes field elements in the registers loaded from all four memory cell categories,
+    /// putting the result into the register loaded from the read-once input cells.
+    pub fn hash<Id: SiteId>(&mut self) -> ExecStep<Site<Id>> {
+        let input = [REG_IN_RO, REG_IN_IM, REG_OUT_RO, REG_OUT_IM].map(|reg| {
+            self.gfa
+                .get(Self::load_dst(reg))
+                .map(|el| el.to_u256())
+                .unwrap_or_default()
+        });
+        let hash = hash_elements(input, self.fq);
+        self.gfa.set(Self::load_dst(REG_IN_RO), fe256::from(hash));
+        ExecStep::Next
+    }
+
+    /// Compares state tuples of the current memory cells of two categories.
+    ///
+    /// Returns `None` if any of the categories has no current cell.
+    pub fn eq_state(
+        &self,
+        cat1: CellCategory,
+        cat2: CellCategory,
+        context: &VmContext,
+    ) -> Option<bool> {
+        let current = |cat: CellCategory| {
+            let reg = cat.to_reg();
+            let pos = self.ui[reg].checked_sub(1)?;
+            context.cell_value(reg, pos as usize)
+        };
+        Some(current(cat1)? == current(cat2)?)
+    }
+
+    /// Advances iterator over the memory cells of a category, calling `jmp` if there is a next
+    /// cell; otherwise proceeds to the next instruction.
     pub fn next<Id: SiteId>(
         &mut self,
         jmp: Site<Id>,
         reg: usize,
         context: &VmContext,
     ) -> ExecStep<Site<Id>> {
-        if !context.read_once_input.len() <= self.ui[reg] as usize {
+        if self.ui[reg] as usize >= context.cell_count(reg) {
             return ExecStep::Next;
         };
         self.ui[reg] += 1;
@@ -41,11 +154,18 @@ impl UsonicCore {
         ExecStep::Call(jmp)
     }
 
+    /// Loads next field element of the current memory cell of a category into the register; if
+    /// there are no more elements, clears the register.
+    ///
+    /// Fails if the iteration over the category has not started yet.
     pub fn load<Id: SiteId>(&mut self, reg: usize, context: &VmContext) -> ExecStep<Site<Id>> {
-        let Some(data) = context.read_once_input.get(self.ui[reg] as usize) else {
+        let Some(pos) = self.ui[reg].checked_sub(1) else {
             return ExecStep::FailHalt;
         };
-        let e = RegE::from(u4::with(4 + reg as u8));
+        let Some(data) = context.cell_value(reg, pos as usize) else {
+            return ExecStep::FailHalt;
+        };
+        let e = Self::load_dst(reg);
         if let Some(el) = data.get(self.ue[reg]) {
             self.gfa.set(e, el);
             self.ue[reg] += 1;
@@ -55,3 +175,107 @@ impl UsonicCore {
         ExecStep::Next
     }
 }
+
+#[cfg(test)]
+mod test {
+    use aluvm::LibId;
+    use amplify::num::u256;
+
+    use super::*;
+    use crate::{AuthToken, CellLock, StateCell, StateData, FIELD_ORDER_SECP};
+
+    /// Microcode of `nx*` instructions in v0.12.0-beta.4, kept to pin the consensus-breaking
+    /// difference.
+    fn legacy_next(core: &mut UsonicCore, reg: usize, context: &VmContext) -> bool {
+        #[allow(clippy::nonminimal_bool)]
+        if !context.read_once_input.len() <= core.ui[reg] as usize {
+            return false;
+        };
+        core.ui[reg] += 1;
+        core.ue[reg] = 0;
+        true
+    }
+
+    /// Microcode of `ld*` instructions in v0.12.0-beta.4, kept to pin the consensus-breaking
+    /// difference.
+    fn legacy_load(core: &mut UsonicCore, reg: usize, context: &VmContext) -> Option<fe256> {
+        let data = context.read_once_input.get(core.ui[reg] as usize)?;
+        let el = data.get(core.ue[reg])?;
+        core.ue[reg] += 1;
+        Some(el)
+    }
+
+    fn site() -> Site<LibId> { Site::new(LibId::from([0xA5u8; 32]), 0x0100) }
+
+    fn value(base: u64) -> StateValue { StateValue::from([u256::from(base), u256::from(base + 1)]) }
+
+    fn with_context<T>(f: impl FnOnce(&VmContext) -> T) -> T {
+        let read_once_input = [value(10), value(20)];
+        let immutable_input = [value(30), value(40)];
+        let read_once_output = [value(50), value(60)].map(|data| StateCell {
+            data,
+            auth: AuthToken::from([0u8; 30]),
+            lock: CellLock::None,
+        });
+        let immutable_output = [value(70), value(80)].map(StateData::new);
+        f(&VmContext {
+            read_once_input: &read_once_input,
+            immutable_input: &immutable_input,
+            read_once_output: &read_once_output,
+            immutable_output: &immutable_output,
+            witness_commitment: None,
+        })
+    }
+
+    #[test]
+    fn next_terminates() {
+        let mut core = UsonicCore::with(FIELD_ORDER_SECP);
+        let mut legacy = core;
+        for reg in [REG_IN_RO, REG_IN_IM, REG_OUT_RO, REG_OUT_IM] {
+            assert!(matches!(core.next(site(), reg, &VmContext::EMPTY), ExecStep::Next));
+            // The bitwise negation made the legacy bound check never hold, so the iteration over
+            // an empty category was entered
+            assert!(legacy_next(&mut legacy, reg, &VmContext::EMPTY));
+        }
+    }
+
+    #[test]
+    fn load_current_cell() {
+        with_context(|context| {
+            let mut core = UsonicCore::with(FIELD_ORDER_SECP);
+            let mut legacy = core;
+            assert!(matches!(core.next(site(), REG_IN_RO, context), ExecStep::Call(_)));
+            assert!(legacy_next(&mut legacy, REG_IN_RO, context));
+
+            assert!(matches!(core.load::<LibId>(REG_IN_RO, context), ExecStep::Next));
+            let e = UsonicCore::load_dst(REG_IN_RO);
+            assert_eq!(core.gfa.get(e), Some(fe256::from(u256::from(10u64))));
+            // The legacy microcode has read the cell following the current one
+            assert_eq!(
+                legacy_load(&mut legacy, REG_IN_RO, context),
+                Some(fe256::from(u256::from(20u64)))
+            );
+        })
+    }
+
+    #[test]
+    fn load_category() {
+        with_context(|context| {
+            for (reg, first) in [(REG_IN_IM, 30u64), (REG_OUT_RO, 50), (REG_OUT_IM, 70)] {
+                let mut core = UsonicCore::with(FIELD_ORDER_SECP);
+                let mut legacy = core;
+                assert!(matches!(core.next(site(), reg, context), ExecStep::Call(_)));
+                assert!(legacy_next(&mut legacy, reg, context));
+
+                assert!(matches!(core.load::<LibId>(reg, context), ExecStep::Next));
+                let e = UsonicCore::load_dst(reg);
+                assert_eq!(core.gfa.get(e), Some(fe256::from(u256::from(first))));
+                // The legacy microcode has always read the read-once input cells
+                assert_eq!(
+                    legacy_load(&mut legacy, reg, context),
+                    Some(fe256::from(u256::from(20u64)))
+                );
+            }
+        })
+    }
+}
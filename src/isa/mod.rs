@@ -26,7 +26,11 @@ mod core;
 mod bytecode;
 mod exec;
 mod microcode;
+mod asm;
+mod hash;
 
 pub use core::{UsonicCore, REG_IN_IM, REG_IN_RO, REG_OUT_IM, REG_OUT_RO};
 
-pub use instr::{Instr, UsonicInstr, ISA_ULTRASONIC};
+pub use bytecode::{USONIC_DEPRECATED_OPCODES, USONIC_OPCODES};
+pub use hash::{hash_elements, HASH_CONSTANTS_TAG, HASH_ROUNDS, HASH_WIDTH};
+pub use instr::{CellCategory, Instr, UnknownGfaInstr, UsonicInstr, ISA_ULTRASONIC};
@@ -0,0 +1,402 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Conformance suite for the USONIC instruction set.
+//!
+//! The suite allows alternative VM backends (for instance, circuit-based or hardware-accelerated
+//! ones) to certify that they execute USONIC instructions exactly in the same way as the
+//! reference [`UsonicCore`] implementation. A backend has to implement [`ConformanceBackend`]
+//! trait, and then [`run_all`] is used to run all the test cases against it.
+
+use aluvm::regs::Status;
+use aluvm::{CoreExt, ExecStep, LibId, RegE, Site};
+use amplify::num::u256;
+
+use crate::isa::{REG_IN_IM, REG_IN_RO, REG_OUT_IM, REG_OUT_RO};
+use crate::{
+    fe256, hash_elements, AuthToken, CellCategory, CellLock, StateCell, StateData, StateValue,
+    UsonicCore, UsonicInstr, VmContext,
+};
+
+/// VM backend which can be tested for conformance with the USONIC instruction set.
+pub trait ConformanceBackend {
+    /// Resets the backend to the initial state, clearing all registers and iterator counters.
+    fn reset(&mut self);
+
+    /// Executes a single USONIC instruction, updating the value of `CO` register in `co`.
+    fn exec(
+        &mut self,
+        instr: UsonicInstr<LibId>,
+        context: &VmContext,
+        co: &mut Status,
+    ) -> ExecStep<Site<LibId>>;
+
+    /// Returns the value of a field element register.
+    fn reg(&self, reg: RegE) -> Option<fe256>;
+
+    /// Returns the order of the field used by the backend.
+    fn field_order(&self) -> u256;
+}
+
+impl ConformanceBackend for UsonicCore {
+    fn reset(&mut self) { CoreExt::reset(self) }
+
+    fn exec(
+        &mut self,
+        instr: UsonicInstr<LibId>,
+        context: &VmContext,
+        co: &mut Status,
+    ) -> ExecStep<Site<LibId>> {
+        self.exec_usonic(instr, context, co)
+    }
+
+    fn reg(&self, reg: RegE) -> Option<fe256> { self.get(reg) }
+
+    fn field_order(&self) -> u256 { UsonicCore::field_order(self) }
+}
+
+/// Failed conformance test case.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("USONIC conformance test `{case}` has failed: {details}.")]
+pub struct ConformanceFailure {
+    /// Name of the failed test case.
+    pub case: &'static str,
+    /// Details on the failure.
+    pub details: String,
+}
+
+/// Test case of the conformance suite.
+pub type ConformanceCase<B> = fn(&mut B) -> Result<(), String>;
+
+/// Returns all test cases of the conformance suite together with their names.
+pub fn cases<B: ConformanceBackend>() -> [(&'static str, ConformanceCase<B>); 9] {
+    [
+        ("next_empty", next_empty::<B>),
+        ("next_exhausts", next_exhausts::<B>),
+        ("load_before_next", load_before_next::<B>),
+        ("load_elements", load_elements::<B>),
+        ("next_resets_element", next_resets_element::<B>),
+        ("categories_independent", categories_independent::<B>),
+        ("eq_state", eq_state::<B>),
+        ("hash_state", hash_state::<B>),
+        ("load_witness", load_witness::<B>),
+    ]
+}
+
+/// Runs all test cases of the conformance suite against a backend, stopping on the first
+/// failure.
+///
+/// The backend is reset before each of the test cases.
+pub fn run_all<B: ConformanceBackend>(backend: &mut B) -> Result<(), ConformanceFailure> {
+    for (case, test) in cases::<B>() {
+        backend.reset();
+        test(backend).map_err(|details| ConformanceFailure { case, details })?;
+    }
+    Ok(())
+}
+
+const ALL_REGS: [usize; 4] = [REG_IN_RO, REG_IN_IM, REG_OUT_RO, REG_OUT_IM];
+
+fn site() -> Site<LibId> { Site::new(LibId::from([0xA5u8; 32]), 0x0100) }
+
+fn next_instr(reg: usize) -> UsonicInstr<LibId> {
+    match reg {
+        REG_IN_RO => UsonicInstr::NxIRo(site()),
+        REG_IN_IM => UsonicInstr::NxIIm(site()),
+        REG_OUT_RO => UsonicInstr::NxORo(site()),
+        REG_OUT_IM => UsonicInstr::NxOIm(site()),
+        _ => unreachable!("unknown iterator register"),
+    }
+}
+
+fn load_instr(reg: usize) -> UsonicInstr<LibId> {
+    match reg {
+        REG_IN_RO => UsonicInstr::LdIRo,
+        REG_IN_IM => UsonicInstr::LdIIm,
+        REG_OUT_RO => UsonicInstr::LdORo,
+        REG_OUT_IM => UsonicInstr::LdOIm,
+        _ => unreachable!("unknown iterator register"),
+    }
+}
+
+fn value(base: u64) -> StateValue { StateValue::from([u256::from(base), u256::from(base + 1)]) }
+
+fn fe(val: u64) -> fe256 { fe256::from(u256::from(val)) }
+
+/// Runs a test case with a context having two memory cells in each of the categories.
+fn with_context<T>(f: impl FnOnce(&VmContext) -> T) -> T {
+    let read_once_input = [value(10), value(20)];
+    let immutable_input = [value(30), value(40)];
+    let read_once_output = [value(50), value(60)].map(|data| StateCell {
+        data,
+        auth: AuthToken::from([0u8; 30]),
+        lock: CellLock::None,
+    });
+    let immutable_output = [value(70), value(80)].map(StateData::new);
+    f(&VmContext {
+        read_once_input: &read_once_input,
+        immutable_input: &immutable_input,
+        read_once_output: &read_once_output,
+        immutable_output: &immutable_output,
+        witness_commitment: Some(fe(WITNESS_COMMITMENT)),
+    })
+}
+
+/// Witness commitment of the operation in the test context.
+const WITNESS_COMMITMENT: u64 = 90;
+
+/// Returns the first field element value of the memory cell at `pos` in the test context.
+fn first_el(reg: usize, pos: usize) -> u64 { 10 + reg as u64 * 20 + pos as u64 * 10 }
+
+fn exec<B: ConformanceBackend>(
+    backend: &mut B,
+    instr: UsonicInstr<LibId>,
+    context: &VmContext,
+) -> ExecStep<Site<LibId>> {
+    let mut co = Status::Ok;
+    backend.exec(instr, context, &mut co)
+}
+
+fn expect_next(step: ExecStep<Site<LibId>>, instr: UsonicInstr<LibId>) -> Result<(), String> {
+    match step {
+        ExecStep::Next => Ok(()),
+        other => Err(format!("`{instr}` must proceed to the next instruction, got {other:?}")),
+    }
+}
+
+fn expect_call(step: ExecStep<Site<LibId>>, instr: UsonicInstr<LibId>) -> Result<(), String> {
+    match step {
+        ExecStep::Call(jmp) if jmp == site() => Ok(()),
+        other => Err(format!("`{instr}` must call {}, got {other:?}", site())),
+    }
+}
+
+fn expect_reg<B: ConformanceBackend>(
+    backend: &B,
+    reg: usize,
+    expected: Option<fe256>,
+) -> Result<(), String> {
+    let dst = UsonicCore::load_dst(reg);
+    let found = backend.reg(dst);
+    if found != expected {
+        return Err(format!("register {dst} must have value {expected:?}, found {found:?}"));
+    }
+    Ok(())
+}
+
+/// Iteration over empty memory categories never calls the iteration routine.
+fn next_empty<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    let context = VmContext {
+        read_once_input: &[],
+        immutable_input: &[],
+        read_once_output: &[],
+        immutable_output: &[],
+        witness_commitment: None,
+    };
+    for reg in ALL_REGS {
+        let instr = next_instr(reg);
+        expect_next(exec(backend, instr, &context), instr)?;
+    }
+    Ok(())
+}
+
+/// Iteration calls the iteration routine once per memory cell and then proceeds further.
+fn next_exhausts<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    with_context(|context| {
+        for reg in ALL_REGS {
+            let instr = next_instr(reg);
+            expect_call(exec(backend, instr, context), instr)?;
+            expect_call(exec(backend, instr, context), instr)?;
+            expect_next(exec(backend, instr, context), instr)?;
+            expect_next(exec(backend, instr, context), instr)?;
+        }
+        Ok(())
+    })
+}
+
+/// Loading data before the iteration has started fails.
+fn load_before_next<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    with_context(|context| {
+        for reg in ALL_REGS {
+            let instr = load_instr(reg);
+            match exec(backend, instr, context) {
+                ExecStep::FailHalt => {}
+                other => return Err(format!("`{instr}` must fail, got {other:?}")),
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Loading returns field elements of the current memory cell one by one, clearing the register
+/// once they are exhausted.
+fn load_elements<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    with_context(|context| {
+        for reg in ALL_REGS {
+            let instr = next_instr(reg);
+            expect_call(exec(backend, instr, context), instr)?;
+
+            let first = first_el(reg, 0);
+            let instr = load_instr(reg);
+            expect_next(exec(backend, instr, context), instr)?;
+            expect_reg(backend, reg, Some(fe(first)))?;
+            expect_next(exec(backend, instr, context), instr)?;
+            expect_reg(backend, reg, Some(fe(first + 1)))?;
+            expect_next(exec(backend, instr, context), instr)?;
+            expect_reg(backend, reg, None)?;
+        }
+        Ok(())
+    })
+}
+
+/// Advancing the iteration restarts loading from the first field element of the next cell.
+fn next_resets_element<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    with_context(|context| {
+        for reg in ALL_REGS {
+            let next = next_instr(reg);
+            let load = load_instr(reg);
+            expect_call(exec(backend, next, context), next)?;
+            expect_next(exec(backend, load, context), load)?;
+            expect_call(exec(backend, next, context), next)?;
+            expect_next(exec(backend, load, context), load)?;
+            expect_reg(backend, reg, Some(fe(first_el(reg, 1))))?;
+        }
+        Ok(())
+    })
+}
+
+/// Iteration over one of the memory categories doesn't affect iteration over the others.
+fn categories_independent<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    with_context(|context| {
+        let instr = next_instr(REG_IN_RO);
+        expect_call(exec(backend, instr, context), instr)?;
+        expect_call(exec(backend, instr, context), instr)?;
+        expect_next(exec(backend, instr, context), instr)?;
+        for reg in [REG_IN_IM, REG_OUT_RO, REG_OUT_IM] {
+            let next = next_instr(reg);
+            let load = load_instr(reg);
+            expect_call(exec(backend, next, context), next)?;
+            expect_next(exec(backend, load, context), load)?;
+            expect_reg(backend, reg, Some(fe(first_el(reg, 0))))?;
+        }
+        Ok(())
+    })
+}
+
+/// State tuples of the current cells are compared as a whole, failing if there is no current
+/// cell.
+fn eq_state<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    let read_once_input = [value(10), value(20), StateValue::from([u256::from(10u64)])];
+    let read_once_output = [value(10), value(30), value(10)].map(|data| StateCell {
+        data,
+        auth: AuthToken::from([0u8; 30]),
+        lock: CellLock::None,
+    });
+    let context = VmContext {
+        read_once_input: &read_once_input,
+        immutable_input: &[],
+        read_once_output: &read_once_output,
+        immutable_output: &[],
+        witness_commitment: None,
+    };
+    let eq = UsonicInstr::EqSt(CellCategory::InRo, CellCategory::OutRo);
+
+    match exec(backend, eq, &context) {
+        ExecStep::FailHalt => {}
+        other => return Err(format!("`{eq}` must fail before the iteration, got {other:?}")),
+    }
+    for expected in [Status::Ok, Status::Fail, Status::Fail] {
+        for next in [next_instr(REG_IN_RO), next_instr(REG_OUT_RO)] {
+            expect_call(exec(backend, next, &context), next)?;
+        }
+        let mut co = if expected == Status::Ok { Status::Fail } else { Status::Ok };
+        expect_next(backend.exec(eq, &context, &mut co), eq)?;
+        if co != expected {
+            return Err(format!("`{eq}` must set CO to {expected:?}, got {co:?}"));
+        }
+    }
+    Ok(())
+}
+
+/// Field elements loaded from all four categories are hashed into the register of the read-once
+/// inputs; registers without a value are hashed as zero.
+fn hash_state<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    let hash = UsonicInstr::Hash;
+    with_context(|context| {
+        let order = backend.field_order();
+
+        expect_next(exec(backend, hash, context), hash)?;
+        let expected = hash_elements([u256::ZERO; 4], order);
+        expect_reg(backend, REG_IN_RO, Some(fe256::from(expected)))?;
+
+        for reg in [REG_IN_IM, REG_OUT_IM] {
+            expect_call(exec(backend, next_instr(reg), context), next_instr(reg))?;
+            expect_next(exec(backend, load_instr(reg), context), load_instr(reg))?;
+        }
+        expect_next(exec(backend, hash, context), hash)?;
+        let input = [
+            expected,
+            u256::from(first_el(REG_IN_IM, 0)),
+            u256::ZERO,
+            u256::from(first_el(REG_OUT_IM, 0)),
+        ];
+        expect_reg(backend, REG_IN_RO, Some(fe256::from(hash_elements(input, order))))?;
+        expect_reg(backend, REG_IN_IM, Some(fe(first_el(REG_IN_IM, 0))))
+    })
+}
+
+/// The witness commitment is loaded into the register of the read-once inputs, setting `CO`;
+/// without the commitment the register is cleared and `CO` is reset.
+fn load_witness<B: ConformanceBackend>(backend: &mut B) -> Result<(), String> {
+    let ldw = UsonicInstr::LdW;
+    let expect_co = |co: Status, expected: Status| {
+        if co != expected {
+            return Err(format!("`{ldw}` must set CO to {expected:?}, got {co:?}"));
+        }
+        Ok(())
+    };
+    with_context(|context| {
+        let mut co = Status::Fail;
+        expect_next(backend.exec(ldw, context, &mut co), ldw)?;
+        expect_co(co, Status::Ok)?;
+        expect_reg(backend, REG_IN_RO, Some(fe(WITNESS_COMMITMENT)))?;
+
+        let context = VmContext { witness_commitment: None, ..context.clone() };
+        let mut co = Status::Ok;
+        expect_next(backend.exec(ldw, &context, &mut co), ldw)?;
+        expect_co(co, Status::Fail)?;
+        expect_reg(backend, REG_IN_RO, None)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FIELD_ORDER_SECP;
+
+    #[test]
+    fn reference_core() {
+        let mut core = UsonicCore::with(FIELD_ORDER_SECP);
+        run_all(&mut core).unwrap();
+    }
+}
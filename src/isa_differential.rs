@@ -0,0 +1,371 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Differential testing of the USONIC instruction set implementation.
+//!
+//! The module provides a slow, but obviously correct [`ReferenceInterpreter`] of the USONIC
+//! instructions, and [`run_differential`] runner executing pseudo-random programs against two
+//! [`ConformanceBackend`]s, reporting the first divergence in their behavior. Together with
+//! [`VmBackend`], which executes instructions through the zk-AluVM integration, this allows to
+//! catch semantic drift in the ISA implementation.
+
+use std::collections::BTreeMap;
+
+use aluvm::isa::Instruction;
+use aluvm::regs::Status;
+use aluvm::{CoreConfig, CoreExt, ExecStep, LibId, RegE, Site, Vm};
+use amplify::num::u256;
+
+use crate::isa::{REG_IN_IM, REG_IN_RO, REG_OUT_IM, REG_OUT_RO};
+use crate::isa_conformance::ConformanceBackend;
+use crate::{
+    fe256, hash_elements, AuthToken, CellCategory, CellLock, Instr, StateCell, StateData,
+    StateValue, UsonicCore, UsonicInstr, VmContext, FIELD_ORDER_SECP,
+};
+
+/// Straightforward interpreter of the USONIC instructions, used as a reference for the
+/// differential testing.
+///
+/// Instead of counters, the interpreter tracks the index of the current cell in each of the
+/// categories, and keeps registers in a map.
+///
+/// The default interpreter uses [`FIELD_ORDER_SECP`] field order.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReferenceInterpreter {
+    current: [Option<usize>; 4],
+    element: [u8; 4],
+    regs: BTreeMap<RegE, fe256>,
+    order: u256,
+}
+
+impl Default for ReferenceInterpreter {
+    fn default() -> Self { Self::with_order(FIELD_ORDER_SECP) }
+}
+
+impl ReferenceInterpreter {
+    pub fn new() -> Self { Self::default() }
+
+    /// Constructs the interpreter using the provided field order.
+    pub fn with_order(order: u256) -> Self {
+        Self { current: none!(), element: none!(), regs: none!(), order }
+    }
+
+    fn cells(context: &VmContext, reg: usize) -> Vec<StateValue> {
+        match reg {
+            REG_IN_RO => context.read_once_input.to_vec(),
+            REG_IN_IM => context.immutable_input.to_vec(),
+            REG_OUT_RO => context
+                .read_once_output
+                .iter()
+                .map(|cell| cell.data)
+                .collect(),
+            REG_OUT_IM => context
+                .immutable_output
+                .iter()
+                .map(|data| data.value)
+                .collect(),
+            _ => unreachable!("unknown iterator register"),
+        }
+    }
+
+    fn current_cell(&self, context: &VmContext, reg: usize) -> Option<StateValue> {
+        let pos = self.current[reg]?;
+        Self::cells(context, reg).get(pos).copied()
+    }
+
+    fn next(&mut self, reg: usize, jmp: Site<LibId>, context: &VmContext) -> ExecStep<Site<LibId>> {
+        let next = match self.current[reg] {
+            None => 0,
+            Some(pos) => pos + 1,
+        };
+        if next >= Self::cells(context, reg).len() {
+            return ExecStep::Next;
+        }
+        self.current[reg] = Some(next);
+        self.element[reg] = 0;
+        ExecStep::Call(jmp)
+    }
+
+    fn load(&mut self, reg: usize, context: &VmContext) -> ExecStep<Site<LibId>> {
+        let Some(cell) = self.current_cell(context, reg) else {
+            return ExecStep::FailHalt;
+        };
+        let dst = UsonicCore::load_dst(reg);
+        match cell.get(self.element[reg]) {
+            Some(el) => {
+                self.regs.insert(dst, el);
+                self.element[reg] += 1;
+            }
+            None => {
+                self.regs.remove(&dst);
+            }
+        }
+        ExecStep::Next
+    }
+}
+
+impl ConformanceBackend for ReferenceInterpreter {
+    fn reset(&mut self) { *self = Self::with_order(self.order); }
+
+    fn exec(
+        &mut self,
+        instr: UsonicInstr<LibId>,
+        context: &VmContext,
+        co: &mut Status,
+    ) -> ExecStep<Site<LibId>> {
+        match instr {
+            UsonicInstr::NxIRo(jmp) => self.next(REG_IN_RO, jmp, context),
+            UsonicInstr::NxIIm(jmp) => self.next(REG_IN_IM, jmp, context),
+            UsonicInstr::NxORo(jmp) => self.next(REG_OUT_RO, jmp, context),
+            UsonicInstr::NxOIm(jmp) => self.next(REG_OUT_IM, jmp, context),
+            UsonicInstr::LdIRo => self.load(REG_IN_RO, context),
+            UsonicInstr::LdIIm => self.load(REG_IN_IM, context),
+            UsonicInstr::LdORo => self.load(REG_OUT_RO, context),
+            UsonicInstr::LdOIm => self.load(REG_OUT_IM, context),
+            UsonicInstr::EqSt(cat1, cat2) => {
+                let first = self.current_cell(context, cat1.to_reg());
+                let second = self.current_cell(context, cat2.to_reg());
+                let (Some(first), Some(second)) = (first, second) else {
+                    return ExecStep::FailHalt;
+                };
+                *co = if first == second { Status::Ok } else { Status::Fail };
+                ExecStep::Next
+            }
+            UsonicInstr::Hash => {
+                let input = [REG_IN_RO, REG_IN_IM, REG_OUT_RO, REG_OUT_IM].map(|reg| {
+                    self.reg(UsonicCore::load_dst(reg))
+                        .map(|el| el.to_u256())
+                        .unwrap_or(u256::ZERO)
+                });
+                let hash = fe256::from(hash_elements(input, self.order));
+                self.regs.insert(UsonicCore::load_dst(REG_IN_RO), hash);
+                ExecStep::Next
+            }
+            UsonicInstr::LdW => {
+                let dst = UsonicCore::load_dst(REG_IN_RO);
+                match context.witness_commitment {
+                    Some(commitment) => {
+                        self.regs.insert(dst, commitment);
+                        *co = Status::Ok;
+                    }
+                    None => {
+                        self.regs.remove(&dst);
+                        *co = Status::Fail;
+                    }
+                }
+                ExecStep::Next
+            }
+        }
+    }
+
+    fn reg(&self, reg: RegE) -> Option<fe256> { self.regs.get(&reg).copied() }
+
+    fn field_order(&self) -> u256 { self.order }
+}
+
+/// Backend executing USONIC instructions through the zk-AluVM core.
+pub struct VmBackend {
+    vm: Vm<Instr<LibId>>,
+}
+
+impl VmBackend {
+    pub fn new(field_order: u256) -> Self {
+        Self {
+            vm: Vm::<Instr<LibId>>::with(CoreConfig::default(), field_order),
+        }
+    }
+}
+
+impl ConformanceBackend for VmBackend {
+    fn reset(&mut self) { self.vm.reset() }
+
+    fn exec(
+        &mut self,
+        instr: UsonicInstr<LibId>,
+        context: &VmContext,
+        co: &mut Status,
+    ) -> ExecStep<Site<LibId>> {
+        self.vm.core.set_co(*co);
+        let step = Instruction::exec(&Instr::Usonic(instr), site(), &mut self.vm.core, context);
+        *co = self.vm.core.co();
+        step
+    }
+
+    fn reg(&self, reg: RegE) -> Option<fe256> { self.vm.core.cx.get(reg) }
+
+    fn field_order(&self) -> u256 { self.vm.core.cx.field_order() }
+}
+
+/// Divergence in the behavior of two backends found by [`run_differential`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("backends diverge at step {step} of program {program} executing `{instr}`: {details}.")]
+pub struct Divergence {
+    /// Number of the program (starting from zero).
+    pub program: usize,
+    /// Number of the instruction in the program (starting from zero).
+    pub step: usize,
+    /// Diverging instruction.
+    pub instr: UsonicInstr<LibId>,
+    /// Details of the divergence.
+    pub details: String,
+}
+
+/// Executes `programs` pseudo-random programs of `len` instructions each against both backends,
+/// each program over a pseudo-random context; after each instruction compares the execution
+/// step, `CO` register and load destination registers of both backends.
+///
+/// The programs are fully defined by the `seed`, such that a divergence can be reproduced.
+pub fn run_differential<A: ConformanceBackend, B: ConformanceBackend>(
+    first: &mut A,
+    second: &mut B,
+    seed: u64,
+    programs: usize,
+    len: usize,
+) -> Result<(), Divergence> {
+    let mut rng = Rng(seed | 1);
+    for program in 0..programs {
+        first.reset();
+        second.reset();
+
+        let read_once_input = rng.values();
+        let immutable_input = rng.values();
+        let read_once_output = rng
+            .values()
+            .into_iter()
+            .map(|data| StateCell { data, auth: AuthToken::from([0u8; 30]), lock: CellLock::None })
+            .collect::<Vec<_>>();
+        let immutable_output = rng
+            .values()
+            .into_iter()
+            .map(StateData::new)
+            .collect::<Vec<_>>();
+        let witness_commitment = match rng.below(2) {
+            0 => None,
+            _ => Some(fe256::from(u256::from(rng.below(3)))),
+        };
+        let context = VmContext {
+            read_once_input: &read_once_input,
+            immutable_input: &immutable_input,
+            read_once_output: &read_once_output,
+            immutable_output: &immutable_output,
+            witness_commitment,
+        };
+
+        let mut co1 = Status::Ok;
+        let mut co2 = Status::Ok;
+        for step in 0..len {
+            let instr = rng.instr();
+            let divergence = |details: String| Divergence { program, step, instr, details };
+
+            let step1 = first.exec(instr, &context, &mut co1);
+            let step2 = second.exec(instr, &context, &mut co2);
+            if format!("{step1:?}") != format!("{step2:?}") {
+                return Err(divergence(format!("execution steps {step1:?} and {step2:?}")));
+            }
+            if co1 != co2 {
+                return Err(divergence(format!("CO register values {co1:?} and {co2:?}")));
+            }
+            for reg in [REG_IN_RO, REG_IN_IM, REG_OUT_RO, REG_OUT_IM] {
+                let dst = UsonicCore::load_dst(reg);
+                let (val1, val2) = (first.reg(dst), second.reg(dst));
+                if val1 != val2 {
+                    return Err(divergence(format!("{dst} register values {val1:?} and {val2:?}")));
+                }
+            }
+            if matches!(step1, ExecStep::FailHalt) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn site() -> Site<LibId> { Site::new(LibId::from([0x5Au8; 32]), 0) }
+
+/// Xorshift pseudo-random number generator; not suitable for anything but test data generation.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: u64) -> u64 { self.next() % max }
+
+    /// Generates up to three state values with up to four small elements, such that equal
+    /// values are frequent.
+    fn values(&mut self) -> Vec<StateValue> {
+        (0..self.below(4))
+            .map(|_| {
+                let len = self.below(5) as usize;
+                StateValue::from(
+                    (0..len)
+                        .map(|_| u256::from(self.below(3)))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect()
+    }
+
+    fn category(&mut self) -> CellCategory { CellCategory::from_bits(self.below(4) as u8) }
+
+    fn instr(&mut self) -> UsonicInstr<LibId> {
+        match self.below(11) {
+            0 => UsonicInstr::NxIRo(site()),
+            1 => UsonicInstr::NxIIm(site()),
+            2 => UsonicInstr::NxORo(site()),
+            3 => UsonicInstr::NxOIm(site()),
+            4 => UsonicInstr::LdIRo,
+            5 => UsonicInstr::LdIIm,
+            6 => UsonicInstr::LdORo,
+            7 => UsonicInstr::LdOIm,
+            8 => UsonicInstr::Hash,
+            9 => UsonicInstr::LdW,
+            _ => UsonicInstr::EqSt(self.category(), self.category()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::isa_conformance::run_all;
+
+    #[test]
+    fn reference_conformance() { run_all(&mut ReferenceInterpreter::new()).unwrap(); }
+
+    #[test]
+    fn microcode_vs_reference() {
+        let mut core = UsonicCore::with(FIELD_ORDER_SECP);
+        run_differential(&mut ReferenceInterpreter::new(), &mut core, 0x5EED, 1000, 64).unwrap();
+    }
+
+    #[test]
+    fn vm_vs_reference() {
+        let mut vm = VmBackend::new(FIELD_ORDER_SECP);
+        run_differential(&mut ReferenceInterpreter::new(), &mut vm, 0xC0FFEE, 1000, 64).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Write-ahead journal of the state changes made by verified operations.
+//!
+//! Before applying a verified operation, a node can persist a [`VerifyJournalEntry`], which
+//! records the operation id, the memory cells destroyed and created by the operation, and digests
+//! of the live contract state before and after the application. After a crash, the node compares
+//! the digest of its state with the ones in the last entry (see [`VerifyJournalEntry::status`]) to
+//! find out whether the operation has to be re-applied; the journal also serves as an audit log
+//! for forensic analysis.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::{LargeVec, SmallVec, U32};
+use amplify::Bytes32;
+use commit_verify::{DigestExt, ReservedBytes};
+use strict_encoding::{StrictDeserialize, StrictEncode, StrictSerialize, StrictWriter};
+
+use crate::{
+    ApplyError, ApplyState, CellAddr, ContractId, DumpCell, DumpValue, Height, IdHash, LiveState,
+    Opid, StateCell, StateValue, VerifiedOperation, LIB_NAME_ULTRASONIC,
+};
+
+/// Tag of the hash committing to the live contract state, see [`live_state_digest`].
+pub const JOURNAL_STATE_TAG: &str = "urn:ubideco:ultrasonic:journal-state#2025-06-01";
+
+/// Computes digest committing to all live read-once and immutable memory cells of the contract
+/// state.
+pub fn live_state_digest(state: &impl LiveState) -> Bytes32 {
+    cells_digest(state.live_read_once(), state.live_immutable())
+}
+
+fn cells_digest(
+    read_once: impl Iterator<Item = (CellAddr, StateCell)>,
+    immutable: impl Iterator<Item = (CellAddr, StateValue)>,
+) -> Bytes32 {
+    // Sorting guarantees the same digest irrespective of the iteration order of the state
+    let read_once = read_once.collect::<BTreeMap<_, _>>();
+    let immutable = immutable.collect::<BTreeMap<_, _>>();
+    let read_once = LargeVec::from_iter_checked(
+        read_once
+            .into_iter()
+            .map(|(addr, cell)| DumpCell { addr, cell }),
+    );
+    let immutable = LargeVec::from_iter_checked(
+        immutable
+            .into_iter()
+            .map(|(addr, value)| DumpValue { addr, value }),
+    );
+    let writer = StrictWriter::in_memory::<U32>();
+    let writer = read_once
+        .strict_encode(writer)
+        .and_then(|w| immutable.strict_encode(w))
+        .expect("state size is limited by its confinements");
+    let data = writer.unbox().unconfine();
+    Bytes32::from(IdHash::digest_tagged(JOURNAL_STATE_TAG, &data))
+}
+
+/// Journal entry recording application of a verified operation to the contract state.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct VerifyJournalEntry {
+    /// Version of the journal entry format; must be zero.
+    pub version: ReservedBytes<1>,
+    pub contract_id: ContractId,
+    pub opid: Opid,
+    pub height: Height,
+    /// Read-once memory cells destroyed by the operation.
+    pub destroyed: SmallVec<CellAddr>,
+    /// Read-once memory cells created by the operation.
+    pub created: SmallVec<DumpCell>,
+    /// Immutable memory cells created by the operation.
+    pub written: SmallVec<DumpValue>,
+    /// Digest of the live state before applying the operation, see [`live_state_digest`].
+    pub state_before: Bytes32,
+    /// Digest of the live state after applying the operation, see [`live_state_digest`].
+    pub state_after: Bytes32,
+}
+
+impl StrictSerialize for VerifyJournalEntry {}
+impl StrictDeserialize for VerifyJournalEntry {}
+
+impl VerifyJournalEntry {
+    /// Constructs the journal entry for the operation before it gets applied to the state.
+    ///
+    /// The method checks that the operation can be applied (see [`ApplyState::check_apply`]) and
+    /// computes the state digest after the application without modifying the state.
+    pub fn new(
+        state: &(impl ApplyState + LiveState),
+        op: &VerifiedOperation,
+    ) -> Result<Self, ApplyError> {
+        state.check_apply(op)?;
+        let operation = op.as_operation();
+
+        let destroyed = SmallVec::from_iter_checked(operation.destroying.iter().map(|i| i.addr));
+        let created = SmallVec::from_iter_checked(
+            op.destructible_cells()
+                .map(|(addr, cell)| DumpCell { addr, cell: *cell }),
+        );
+        let written = SmallVec::from_iter_checked(
+            op.immutable_cells()
+                .map(|(addr, data)| DumpValue { addr, value: data.value }),
+        );
+
+        let mut read_once = state.live_read_once().collect::<BTreeMap<_, _>>();
+        for addr in &destroyed {
+            read_once.remove(addr);
+        }
+        read_once.extend(created.iter().map(|item| (item.addr, item.cell)));
+        let immutable = state
+            .live_immutable()
+            .chain(written.iter().map(|item| (item.addr, item.value)));
+
+        Ok(Self {
+            version: default!(),
+            contract_id: operation.contract_id,
+            opid: op.opid(),
+            height: op.height(),
+            destroyed,
+            created,
+            written,
+            state_before: live_state_digest(state),
+            state_after: cells_digest(read_once.into_iter(), immutable),
+        })
+    }
+
+    /// Detects whether the journaled operation is applied to the state.
+    ///
+    /// Used during crash recovery to decide whether the operation must be (re-)applied.
+    pub fn status(&self, state: &impl LiveState) -> JournalStatus {
+        match live_state_digest(state) {
+            digest if digest == self.state_after => JournalStatus::Applied,
+            digest if digest == self.state_before => JournalStatus::Pending,
+            _ => JournalStatus::Diverged,
+        }
+    }
+}
+
+/// Status of a [`VerifyJournalEntry`] with respect to the contract state.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum JournalStatus {
+    /// The state is the one before applying the operation.
+    Pending,
+    /// The state is the one after applying the operation.
+    Applied,
+    /// The state matches neither the state before nor after applying the operation.
+    Diverged,
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::num::u256;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{Input, Operation, ReplayMemory, StateData};
+
+    fn state() -> (ReplayMemory, CellAddr) {
+        let genesis = Opid::from([1u8; 32]);
+        let addr = CellAddr::new(genesis, 0);
+        let mut memory = ReplayMemory::default();
+        memory.heights.insert(genesis, Height::GENESIS);
+        let cell = StateCell {
+            data: StateValue::from([u256::from(10u8)]),
+            ..StateCell::strict_dumb()
+        };
+        memory.read_once.insert(addr, cell);
+        (memory, addr)
+    }
+
+    fn operation(spent: CellAddr) -> VerifiedOperation {
+        let mut op = Operation::strict_dumb();
+        op.destroying
+            .push(Input { addr: spent, witness: none!() })
+            .unwrap();
+        op.destructible
+            .push(StateCell {
+                data: StateValue::from([u256::from(7u8)]),
+                ..StateCell::strict_dumb()
+            })
+            .unwrap();
+        op.immutable
+            .push(StateData {
+                value: StateValue::from([u256::from(3u8)]),
+                hint: None,
+                raw: None,
+            })
+            .unwrap();
+        VerifiedOperation::new_unchecked(op.opid(), op, Height::new(1))
+    }
+
+    #[test]
+    fn write_ahead() {
+        let (mut memory, spent) = state();
+        let op = operation(spent);
+        let entry = VerifyJournalEntry::new(&memory, &op).unwrap();
+        assert_eq!(entry.opid, op.opid());
+        assert_eq!(entry.destroyed.as_slice(), &[spent]);
+        assert_eq!(entry.created.len(), 1);
+        assert_eq!(entry.written.len(), 1);
+        assert_eq!(entry.status(&memory), JournalStatus::Pending);
+
+        let data = entry.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(VerifyJournalEntry::from_strict_serialized::<U32>(data).unwrap(), entry);
+
+        let journaled = memory.clone().apply_journaled(op.clone()).unwrap();
+        assert_eq!(journaled, entry);
+
+        memory.apply(op.clone()).unwrap();
+        assert_eq!(entry.status(&memory), JournalStatus::Applied);
+        assert_eq!(live_state_digest(&memory), entry.state_after);
+        assert_eq!(VerifyJournalEntry::new(&memory, &op), Err(ApplyError::NotLive(spent)));
+
+        memory.read_once.clear();
+        assert_eq!(entry.status(&memory), JournalStatus::Diverged);
+    }
+}
@@ -0,0 +1,123 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Pinned strict-encoded sizes of the fixed-layout consensus types.
+//!
+//! Any change to these sizes changes the consensus encoding of operations and contracts. Downstream
+//! projects may call [`check_layout`] from their own test suites to detect a layout drift when
+//! updating this crate.
+
+use commit_verify::ReservedBytes;
+use strict_encoding::{StrictDumb, StrictEncode, StrictWriter};
+
+use crate::{
+    AuthToken, CellAddr, CodexId, ConstU32, ContractId, ContractMeta, ContractName, Identity, Opid,
+    Timestamp,
+};
+
+/// Size of [`Opid`].
+pub const OPID_LEN: usize = 32;
+/// Size of [`CodexId`].
+pub const CODEX_ID_LEN: usize = 32;
+/// Size of [`ContractId`].
+pub const CONTRACT_ID_LEN: usize = 32;
+/// Size of [`AuthToken`].
+pub const AUTH_TOKEN_LEN: usize = 32;
+/// Size of [`CellAddr`]: operation id followed by a 16-bit output number.
+pub const CELL_ADDR_LEN: usize = 34;
+/// Size of the fixed-length header of [`crate::ContractMeta`], preceding the contract name and
+/// issuer.
+pub const CONTRACT_HEADER_LEN: usize = 23;
+
+const _: () = assert!(CELL_ADDR_LEN == OPID_LEN + 2);
+const _: () = assert!(CONTRACT_HEADER_LEN == 4 + 1 + 10 + 8);
+
+/// Returns the length of the strict-encoded value.
+pub fn strict_len(val: &impl StrictEncode) -> usize {
+    let writer = StrictWriter::in_memory::<{ u16::MAX as usize }>();
+    let writer = val
+        .strict_encode(writer)
+        .expect("fixed-layout types fit 64kB");
+    writer.unbox().unconfine().len()
+}
+
+/// Asserts that the strict-encoded size of a value matches the expected one.
+#[macro_export]
+macro_rules! assert_strict_len {
+    ($val:expr, $len:expr) => {
+        assert_eq!(
+            $crate::layout::strict_len(&$val),
+            $len,
+            "strict-encoded size of `{}` has changed",
+            stringify!($val)
+        )
+    };
+}
+
+/// Checks strict-encoded sizes of all fixed-layout consensus types against the pinned values.
+pub fn check_layout() -> Result<(), LayoutError> {
+    fn check(name: &'static str, found: usize, expected: usize) -> Result<(), LayoutError> {
+        if found != expected {
+            return Err(LayoutError { name, expected, found });
+        }
+        Ok(())
+    }
+
+    check("Opid", strict_len(&Opid::strict_dumb()), OPID_LEN)?;
+    check("CodexId", strict_len(&CodexId::strict_dumb()), CODEX_ID_LEN)?;
+    check("ContractId", strict_len(&ContractId::strict_dumb()), CONTRACT_ID_LEN)?;
+    check("AuthToken", strict_len(&AuthToken::strict_dumb()), AUTH_TOKEN_LEN)?;
+    check("CellAddr", strict_len(&CellAddr::strict_dumb()), CELL_ADDR_LEN)?;
+    let meta = ContractMeta::<0> {
+        capabilities: ConstU32::new(),
+        testnet: false,
+        reserved: ReservedBytes::default(),
+        timestamp: Timestamp::default(),
+        name: ContractName::Unnamed,
+        issuer: Identity::default(),
+    };
+    let header = strict_len(&meta) - strict_len(&meta.name) - strict_len(&meta.issuer);
+    check("ContractMeta header", header, CONTRACT_HEADER_LEN)
+}
+
+/// strict-encoded size of {name} is {found} bytes instead of {expected} bytes.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct LayoutError {
+    pub name: &'static str,
+    pub expected: usize,
+    pub found: usize,
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+
+    #[test]
+    fn pinned() { check_layout().unwrap(); }
+
+    #[test]
+    fn cell_addr() {
+        assert_strict_len!(CellAddr::new(Opid::strict_dumb(), 0xFFFF), CELL_ADDR_LEN);
+    }
+}
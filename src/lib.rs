@@ -44,22 +44,136 @@ mod codex;
 mod state;
 mod operation;
 mod isa;
+#[cfg(feature = "isa-conformance")]
+pub mod isa_conformance;
+#[cfg(feature = "isa-conformance")]
+pub mod isa_differential;
 mod contract;
+mod bound;
+#[cfg(feature = "baid64")]
+mod display;
+mod anchor;
+mod apply;
+mod builder;
+mod replay;
+mod store;
+mod stream;
+#[cfg(feature = "serde")]
+mod serde_id;
+mod schema;
+mod sign;
+mod prune;
+mod report;
+mod dump;
+mod invariants;
+mod journal;
+mod untrusted;
+mod conceal;
+mod cache;
+mod persist;
+mod policy;
+#[cfg(feature = "prover-hooks")]
+mod prover;
+mod limit;
+mod control;
+mod stdlib;
+pub mod analysis;
+pub mod reg;
+pub mod types;
+pub mod prelude;
+pub mod seals;
+pub mod lint;
+mod graph;
 #[cfg(feature = "stl")]
 pub mod stl;
 mod util;
+mod vanity;
+mod witness;
+mod info;
+mod reserved;
+pub mod compat;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "rayon")]
+pub mod workers;
+#[cfg(any(test, feature = "layout-tests"))]
+pub mod layout;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
+#[cfg(feature = "determinism")]
+pub mod determinism;
+#[cfg(all(test, feature = "heap-profiling"))]
+mod heap;
 
 use amplify::num::u256;
-pub use codex::{AccessId, CallError, CallId, Codex, CodexId, LibRepo, Memory, VmContext};
+pub use anchor::{AnchorError, AnchorPolicy, AnchorProof};
+pub use apply::{ApplyError, ApplyState};
+pub use bound::{Bound, BoundAddr, BoundAuth, BoundError, BoundOpid};
+pub use builder::{BuildError, OperationBuilder};
+pub use cache::{CacheStats, CachedMemory};
+pub use codex::{
+    verify_pure, AccessId, AccessKind, AuditedMemory, CallError, CallId, CheckedRepo, Codex,
+    CodexFlags, CodexId, LibRepo, Memory, MemoryAccess, MemoryAudit, OperationInputs, ResolvedRepo,
+    VerifierSite, VerifierSiteError, VmContext,
+};
+pub use conceal::{
+    ConcealableCell, ConcealableData, PartialOperation, RevealError, RevealedMemory,
+};
 pub use contract::{
-    ConstU32, Contract, ContractId, ContractMeta, ContractName, ContractPrivate, ContractRef,
+    ConstU32, Contract, ContractDraft, ContractId, ContractMeta, ContractName, ContractPrivate,
+    ContractRef,
+};
+pub use control::{ControlError, ControlProof};
+#[cfg(feature = "baid64")]
+pub use display::{IdDisplay, MnemonicDisplay};
+pub use dump::{DumpCell, DumpError, DumpHeight, DumpValue, StateDump, STATE_DUMP_TAG};
+pub use graph::{EdgeKind, OpEdge, OpGraph};
+pub use info::{
+    build_info, consensus_params, BuildInfo, ConsensusParams, CONSENSUS_PARAMS_VERSION,
+};
+pub use invariants::{InvariantsError, LiveState};
+pub use isa::{
+    hash_elements, CellCategory, Instr, UnknownGfaInstr, UsonicCore, UsonicInstr,
+    HASH_CONSTANTS_TAG, HASH_ROUNDS, HASH_WIDTH, ISA_ULTRASONIC, USONIC_DEPRECATED_OPCODES,
+    USONIC_OPCODES,
 };
-pub use isa::{Instr, UsonicCore, UsonicInstr, ISA_ULTRASONIC};
+pub use journal::{live_state_digest, JournalStatus, VerifyJournalEntry, JOURNAL_STATE_TAG};
+pub use limit::{LimitError, RateLimit};
 #[cfg(feature = "baid64")]
 pub use operation::ParseAddrError;
-pub use operation::{CellAddr, Genesis, GenesisId, Input, Operation, Opid};
-pub use state::{AuthToken, RawData, StateCell, StateData, StateValue};
-pub use util::Identity;
+pub use operation::{
+    AnnotationValue, Annotations, CellAddr, CellPosError, Genesis, GenesisId, Height, IdMismatch,
+    Input, Operation, OperationWitness, Opid, ReadIntent, VerifiedOperation, VerifiedRef,
+    VerifyStats, WitnessMismatch,
+};
+pub use persist::{verify_context_digest, PersistError, PersistedOperation, VERIFY_CONTEXT_TAG};
+pub use policy::{Consensus, SizeError, SizePolicy, VerifyPolicy};
+#[cfg(feature = "prover-hooks")]
+pub use prover::VerifyTrace;
+pub use prune::{Prunable, PruneProof};
+pub use replay::ReplayMemory;
+pub use report::VerificationReport;
+pub use reserved::ReservedError;
+pub use schema::{StateDescriptor, StateSchema};
+#[cfg(feature = "serde")]
+pub use serde_id::ID_SERDE_VERSION;
+pub use sign::{
+    OutputSummary, SignDoc, SignDocError, WriteSummary, SIGN_DOC_OPERATION_TAG, SIGN_DOC_TAG,
+};
+pub use state::{
+    AuthToken, AuthTokenDisplay, AuthTokenParseError, CellLock, DisplayPolicy, EncryptedRawData,
+    PubkeyLenError, RawCipher, RawData, RawEncoding, RawStrictError, StateCell, StateData,
+    StateValue,
+};
+pub use stdlib::{StdLib, StdRoutine};
+pub use store::MemoryStore;
+pub use stream::{OperationStream, VerifiedStream};
+pub use untrusted::{DecodeLimits, DecodeUntrusted, UntrustedError};
+pub use util::{IdHash, IdHasher, Identity, Timestamp, TimestampError};
+pub use vanity::{VanityError, VanityProgress, VanitySearch};
+pub use witness::{StdWitness, WitnessError, WitnessLayout, WitnessRegistry};
 pub use zkaluvm::fe256;
 
 pub const LIB_NAME_ULTRASONIC: &str = "UltraSONIC";
@@ -77,3 +191,42 @@ pub const FIELD_ORDER_SECP: u256 = u256::from_inner([
     0xFFFF_FFFF_FFFF_FFFF,
     0xFFFF_FFFF_FFFF_FFFF,
 ]);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    fn sources(dir: &Path, found: &mut Vec<(String, String)>) {
+        for entry in fs::read_dir(dir).expect("source directory is readable") {
+            let path = entry.expect("source directory is readable").path();
+            if path.is_dir() {
+                sources(&path, found);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                let source = fs::read_to_string(&path).expect("source file is readable");
+                found.push((path.display().to_string(), source));
+            }
+        }
+    }
+
+    /// Crate modules must use only ordered containers, such that iteration order (and thus
+    /// verification results and reports) is the same across platforms.
+    ///
+    /// All the source files are scanned, such that newly added modules are covered as well.
+    #[test]
+    fn deterministic_containers() {
+        // Type names are assembled here so this file does not match itself.
+        let types = ["Map", "Set"]
+            .into_iter()
+            .flat_map(|kind| [format!("Hash{kind}"), format!("hash_{}", kind.to_lowercase())])
+            .collect::<Vec<_>>();
+        let mut found = vec![];
+        sources(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut found);
+        assert!(found.len() > 1);
+        for (file, source) in found {
+            for ty in &types {
+                assert!(!source.contains(ty.as_str()), "module {file} uses unordered `{ty}`");
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+use aluvm::CoreConfig;
+
+use crate::{
+    CallError, Codex, ContractId, Input, LibRepo, Memory, Operation, StateCell, VerifiedOperation,
+    VerifyPolicy, VmContext,
+};
+
+/// Per-call resource ceilings for verifying operations received from untrusted peers.
+///
+/// The ceilings are not a part of the contract consensus rules: they can only tighten the limits
+/// declared by the codex, never relax them. An operation rejected under the ceilings may still be
+/// valid under the codex limits, so network-facing services should treat [`LimitError`] as a
+/// reason to drop the peer request rather than to consider the operation invalid.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct RateLimit {
+    /// Maximal total complexity of all the scripts run to verify a single operation: the lock
+    /// scripts of all the inputs and the operation verifier.
+    ///
+    /// Each of the scripts runs under the ceiling, and the verification is aborted once the
+    /// complexity accumulated over the scripts exceeds it.
+    pub max_complexity: Option<u64>,
+    /// Maximal wall-clock time spent on verifying a single operation.
+    ///
+    /// The time is checked between the verification phases, such that a single script run is
+    /// bounded only by [`Self::max_complexity`].
+    pub max_duration: Option<Duration>,
+}
+
+impl RateLimit {
+    /// Ceilings which don't restrict the verification beyond the codex limits.
+    pub const fn unlimited() -> Self { Self { max_complexity: None, max_duration: None } }
+
+    /// Ceilings with the given complexity and wall-clock limits.
+    pub const fn new(max_complexity: u64, max_duration: Duration) -> Self {
+        Self {
+            max_complexity: Some(max_complexity),
+            max_duration: Some(max_duration),
+        }
+    }
+
+    /// Returns the VM configuration with the complexity limit tightened to the ceiling, or `None`
+    /// if the ceiling doesn't tighten the limit of the `config`.
+    pub fn clamp(&self, config: CoreConfig) -> Option<CoreConfig> {
+        let max = self.max_complexity?;
+        match config.complexity_lim {
+            Some(lim) if lim <= max => None,
+            _ => Some(CoreConfig { complexity_lim: Some(max), ..config }),
+        }
+    }
+}
+
+/// Policy aborting the verification once the wall-clock limit or the total complexity ceiling is
+/// reached, and detecting scripts which have exhausted the tightened complexity limits.
+struct Deadline {
+    start: Instant,
+    limit: Option<Duration>,
+    elapsed: Option<Duration>,
+    /// Ceiling on the total complexity of all the scripts.
+    max_complexity: Option<u64>,
+    /// Complexity consumed by all the scripts run so far.
+    complexity: u64,
+    /// Tightened complexity limit of the lock scripts, if any.
+    lock_limit: Option<u64>,
+    /// Tightened complexity limit of the operation verifier, if any.
+    verifier_limit: Option<u64>,
+    /// Whether the verification has failed because a script reached a tightened complexity limit
+    /// or the total complexity ceiling.
+    exhausted: bool,
+}
+
+impl Deadline {
+    fn start(limit: RateLimit) -> Self {
+        Self {
+            start: Instant::now(),
+            limit: limit.max_duration,
+            elapsed: None,
+            max_complexity: limit.max_complexity,
+            complexity: 0,
+            lock_limit: None,
+            verifier_limit: None,
+            exhausted: false,
+        }
+    }
+
+    fn check(&mut self) -> Result<(), CallError> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+        let elapsed = self.start.elapsed();
+        if elapsed >= limit {
+            self.elapsed = Some(elapsed);
+            return Err(CallError::Policy(LimitError::TIMEOUT_CODE));
+        }
+        Ok(())
+    }
+}
+
+impl VerifyPolicy for Deadline {
+    fn check_operation(&mut self, _: &Operation) -> Result<(), CallError> { self.check() }
+
+    fn before_lock(&mut self, _: &Input, _: &StateCell) -> Result<(), CallError> { self.check() }
+
+    fn after_lock(&mut self, _: &Input, _: &StateCell) -> Result<(), CallError> { self.check() }
+
+    fn before_exec(&mut self, _: &Operation, _: &VmContext) -> Result<(), CallError> {
+        self.check()
+    }
+
+    fn after_exec(&mut self, _: &Operation, _: &VmContext) -> Result<(), CallError> { self.check() }
+
+    fn after_script(&mut self, complexity: u64) -> Result<(), CallError> {
+        self.complexity = self.complexity.saturating_add(complexity);
+        if self.max_complexity.is_some_and(|max| self.complexity > max) {
+            self.exhausted = true;
+            return Err(CallError::Policy(LimitError::COMPLEXITY_CODE));
+        }
+        Ok(())
+    }
+
+    fn after_script_failure(&mut self, err: &CallError, complexity: u64) {
+        let limit = match err {
+            CallError::Lock(_) => self.lock_limit,
+            _ => self.verifier_limit,
+        };
+        self.complexity = self.complexity.saturating_add(complexity);
+        self.exhausted = limit.is_some_and(|limit| complexity >= limit)
+            || self
+                .max_complexity
+                .is_some_and(|max| self.complexity >= max);
+    }
+}
+
+impl Codex {
+    /// Verifies the operation like [`Self::verify`], enforcing the per-call resource ceilings of
+    /// the `limit` on top of the codex limits.
+    ///
+    /// The verification is aborted as soon as the wall-clock limit is reached or the complexity
+    /// consumed by the scripts exceeds the ceiling, and the abort is reported with a
+    /// [`LimitError`] distinct from the operation verification errors. A script failure is
+    /// reported as [`LimitError::Complexity`] only if the script has consumed the complexity up to
+    /// the ceiling; all other failures are reported as [`LimitError::Call`].
+    pub fn verify_limited(
+        &self,
+        contract_id: ContractId,
+        operation: Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+        limit: RateLimit,
+    ) -> Result<VerifiedOperation, LimitError> {
+        let mut deadline = Deadline::start(limit);
+        let input_config = limit.clamp(self.input_config);
+        let verification_config = limit.clamp(self.verification_config);
+        deadline.lock_limit = input_config.and_then(|config| config.complexity_lim);
+        deadline.verifier_limit = verification_config.and_then(|config| config.complexity_lim);
+        let codex = if input_config.is_some() || verification_config.is_some() {
+            Cow::Owned(Codex {
+                input_config: input_config.unwrap_or(self.input_config),
+                verification_config: verification_config.unwrap_or(self.verification_config),
+                ..self.clone()
+            })
+        } else {
+            Cow::Borrowed(self)
+        };
+
+        let res = codex.verify_with_policy(contract_id, operation, memory, repo, &mut deadline);
+        res.map_err(|err| match (err, deadline.elapsed, limit.max_complexity) {
+            (_, Some(elapsed), _) => {
+                LimitError::Timeout { elapsed, limit: limit.max_duration.unwrap_or_default() }
+            }
+            (cause, None, Some(limit)) if deadline.exhausted => {
+                LimitError::Complexity { limit, cause }
+            }
+            (err, ..) => LimitError::Call(err),
+        })
+    }
+}
+
+/// Error returned by [`Codex::verify_limited`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum LimitError {
+    /// verification was aborted after {elapsed:?}, reaching the per-call time limit of {limit:?}.
+    Timeout { elapsed: Duration, limit: Duration },
+
+    /// verification has reached the per-call complexity limit of {limit}; the operation may still
+    /// be valid under the codex limits ({cause}).
+    Complexity { limit: u64, cause: CallError },
+
+    /// {0}
+    #[from]
+    Call(CallError),
+}
+
+impl LimitError {
+    /// Code of the [`CallError::Policy`] error used internally to abort the verification on
+    /// timeout.
+    pub const TIMEOUT_CODE: u32 = 0x7453_4C52;
+
+    /// Code of the [`CallError::Policy`] error used internally to abort the verification once the
+    /// total complexity of the scripts exceeds the ceiling.
+    pub const COMPLEXITY_CODE: u32 = 0x6353_4C52;
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::gfa::FieldInstr;
+    use aluvm::isa::CtrlInstr;
+    use aluvm::{Lib, LibId, LibSite, RegE};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        CallId, CellAddr, CellLock, Height, Instr, MemoryStore, Opid, ReplayMemory,
+        FIELD_ORDER_SECP,
+    };
+
+    struct NoLibs;
+    impl LibRepo for NoLibs {
+        fn get_lib(&self, _: LibId) -> Option<&Lib> { None }
+    }
+
+    /// Script which consumes some complexity and then either fails or succeeds.
+    struct ScriptLib(Lib);
+    impl LibRepo for ScriptLib {
+        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> {
+            (lib_id == self.0.lib_id()).then_some(&self.0)
+        }
+    }
+
+    fn script_lib(fail: bool) -> ScriptLib {
+        let mut code =
+            vec![Instr::<LibId>::from(FieldInstr::Eq { src1: RegE::E1, src2: RegE::E1 }); 16];
+        if fail {
+            code.push(CtrlInstr::FailCk.into());
+        }
+        code.push(CtrlInstr::Stop.into());
+        ScriptLib(Lib::assemble(&code).unwrap())
+    }
+
+    fn codex() -> Codex {
+        let mut codex = Codex { field_order: FIELD_ORDER_SECP, ..Codex::strict_dumb() };
+        let site = LibSite::new(LibId::from([0xAB; 32]), 0);
+        codex.verifiers.insert(CallId::new(0), site).unwrap();
+        codex
+    }
+
+    #[test]
+    fn clamp() {
+        let config = CoreConfig { halt: true, complexity_lim: Some(1000) };
+        let limit = RateLimit { max_complexity: Some(100), max_duration: None };
+        assert_eq!(limit.clamp(config), Some(CoreConfig { halt: true, complexity_lim: Some(100) }));
+        assert_eq!(RateLimit { max_complexity: Some(1000), ..limit }.clamp(config), None);
+        assert_eq!(RateLimit::unlimited().clamp(config), None);
+    }
+
+    #[test]
+    fn timeout() {
+        let op = Operation::strict_dumb();
+        let limit = RateLimit { max_complexity: None, max_duration: Some(Duration::ZERO) };
+        let err = codex()
+            .verify_limited(op.contract_id, op, &ReplayMemory::default(), &NoLibs, limit)
+            .unwrap_err();
+        assert!(matches!(err, LimitError::Timeout { limit: Duration::ZERO, .. }));
+    }
+
+    #[test]
+    fn distinct_errors() {
+        let op = Operation::strict_dumb();
+        let memory = ReplayMemory::default();
+        let err = codex()
+            .verify_limited(op.contract_id, op.clone(), &memory, &NoLibs, RateLimit::unlimited())
+            .unwrap_err();
+        assert_eq!(err, LimitError::Call(CallError::ScriptUnspecified));
+
+        // A script failing without reaching the ceiling is reported as is
+        let limit = RateLimit { max_complexity: Some(10), max_duration: None };
+        let err = codex()
+            .verify_limited(op.contract_id, op, &memory, &NoLibs, limit)
+            .unwrap_err();
+        assert_eq!(err, LimitError::Call(CallError::ScriptUnspecified));
+    }
+
+    #[test]
+    fn complexity() {
+        let repo = script_lib(true);
+        let mut codex = codex();
+        let site = LibSite::new(repo.0.lib_id(), 0);
+        codex.verifiers.insert(CallId::new(0), site).unwrap();
+        let op = Operation::strict_dumb();
+        let memory = ReplayMemory::default();
+
+        let err = codex
+            .verify_limited(op.contract_id, op.clone(), &memory, &repo, RateLimit::unlimited())
+            .unwrap_err();
+        assert_eq!(err, LimitError::Call(CallError::ScriptUnspecified));
+
+        let limit = RateLimit { max_complexity: Some(1), max_duration: None };
+        let err = codex
+            .verify_limited(op.contract_id, op, &memory, &repo, limit)
+            .unwrap_err();
+        assert_eq!(err, LimitError::Complexity { limit: 1, cause: CallError::ScriptUnspecified });
+    }
+
+    #[test]
+    fn total_complexity() {
+        let repo = script_lib(false);
+        let site = LibSite::new(repo.0.lib_id(), 0);
+        let mut codex = codex();
+        codex.verifiers.insert(CallId::new(0), site).unwrap();
+
+        let spent = CellAddr::new(Opid::from([1u8; 32]), 0);
+        let mut memory = MemoryStore::new();
+        memory.insert_height(spent.opid, Height::GENESIS);
+        let cell = StateCell { lock: CellLock::Single(site), ..StateCell::strict_dumb() };
+        memory.insert_read_once(spent, cell);
+        let mut op = Operation::strict_dumb();
+        op.destroying
+            .push(Input { addr: spent, witness: none!() })
+            .unwrap();
+        let verify = |max_complexity| {
+            let limit = RateLimit { max_complexity, max_duration: None };
+            codex.verify_limited(op.contract_id, op.clone(), &memory, &repo, limit)
+        };
+
+        let stats = verify(None).unwrap().stats();
+        assert!(stats.lock_complexity > 0 && stats.main_complexity > 0);
+        let total = stats.lock_complexity + stats.main_complexity;
+        assert!(verify(Some(total)).is_ok());
+
+        // Each of the scripts fits the ceiling, but their total doesn't
+        assert_eq!(verify(Some(total - 1)).unwrap_err(), LimitError::Complexity {
+            limit: total - 1,
+            cause: CallError::Policy(LimitError::COMPLEXITY_CODE)
+        });
+    }
+}
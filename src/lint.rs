@@ -0,0 +1,165 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Linter detecting operation patterns which may lead to accidental malleability or to operations
+//! conflicting with themselves.
+//!
+//! Lints are warnings and not consensus rules: an operation with lints may still be valid.
+//! Wallets are expected to check operations before broadcasting them.
+
+use std::collections::BTreeMap;
+
+use amplify::num::u256;
+
+use crate::{CallId, Codex, Memory, Operation};
+
+/// Warning about a potentially problematic operation pattern.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum Lint {
+    /// codex has no verifier for the operation call id {0}.
+    NoVerifier(CallId),
+
+    /// operation uses zero nonce, which is likely to be reused by other operations with the same
+    /// inputs and outputs.
+    ZeroNonce,
+
+    /// witness of input #{0} ends with a zero field element, which may be indistinguishable from a
+    /// shorter witness to the lock script.
+    WitnessZeroTail(u16),
+
+    /// input #{0} provides a witness, but the memory cell it destroys is not locked.
+    UnusedWitness(u16),
+
+    /// read-once outputs #{0} and #{1} have identical contents and authority tokens.
+    DuplicateOutput(u16, u16),
+
+    /// immutable outputs #{0} and #{1} carry the same raw data.
+    DuplicateRaw(u16, u16),
+}
+
+/// Checks the operation for patterns which may cause malleability or id instability.
+///
+/// The lints are returned in a deterministic order.
+pub fn check_operation(op: &Operation, codex: &Codex) -> Vec<Lint> {
+    let mut lints = vec![];
+
+    if !codex.has_call(op.call_id) {
+        lints.push(Lint::NoVerifier(op.call_id));
+    }
+    if op.nonce.to_u256() == u256::ZERO {
+        lints.push(Lint::ZeroNonce);
+    }
+
+    for (no, input) in op.destroying.iter().enumerate() {
+        if input
+            .witness
+            .elements()
+            .last()
+            .is_some_and(|el| el.to_u256() == u256::ZERO)
+        {
+            lints.push(Lint::WitnessZeroTail(no as u16));
+        }
+    }
+
+    let mut seen = BTreeMap::new();
+    for (no, cell) in op.destructible.iter().enumerate() {
+        let elements = cell
+            .data
+            .elements()
+            .map(|el| el.to_u256())
+            .collect::<Vec<_>>();
+        let key = (elements, cell.auth.to_byte_array());
+        if let Some(first) = seen.insert(key, no as u16) {
+            lints.push(Lint::DuplicateOutput(first, no as u16));
+        }
+    }
+
+    let mut seen = BTreeMap::new();
+    for (no, data) in op.immutable.iter().enumerate() {
+        let Some(raw) = &data.raw else {
+            continue;
+        };
+        if let Some(first) = seen.insert(raw.as_slice(), no as u16) {
+            lints.push(Lint::DuplicateRaw(first, no as u16));
+        }
+    }
+
+    lints
+}
+
+/// Checks the operation inputs against the memory cells they destroy, flagging witnesses which
+/// are not used by any lock script.
+///
+/// Inputs missing in the memory are skipped.
+pub fn check_inputs(op: &Operation, memory: &impl Memory) -> Vec<Lint> {
+    op.destroying
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| input.witness.elements().next().is_some())
+        .filter(|(_, input)| {
+            memory
+                .read_once(input.addr)
+                .is_some_and(|cell| !cell.lock.is_locked())
+        })
+        .map(|(no, _)| Lint::UnusedWitness(no as u16))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use core::str::FromStr;
+
+    use aluvm::fe256;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AuthToken, CellLock, ContractId, RawData, StateCell, StateData, StateValue};
+
+    #[test]
+    fn duplicates() {
+        let cell = StateCell {
+            data: StateValue::from([u256::from(1u64)]),
+            auth: AuthToken::from([1u8; 30]),
+            lock: CellLock::None,
+        };
+        let data = StateData::with_raw(StateValue::None, RawData::from_str("0xabcd").unwrap());
+        let op = Operation {
+            contract_id: ContractId::from([0u8; 32]),
+            call_id: CallId::new(0),
+            nonce: fe256::from(u256::from(1u64)),
+            destroying: none!(),
+            reading: none!(),
+            destructible: small_vec![cell, cell],
+            immutable: small_vec![data.clone(), data],
+            witness_commitment: None,
+            read_intents: none!(),
+            reserved: default!(),
+        };
+        assert_eq!(check_operation(&op, &Codex::strict_dumb()), vec![
+            Lint::NoVerifier(CallId::new(0)),
+            Lint::DuplicateOutput(0, 1),
+            Lint::DuplicateRaw(0, 1),
+        ]);
+    }
+}
@@ -22,18 +22,28 @@
 // the License.
 
 use core::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io;
 
 #[cfg(feature = "baid64")]
 pub use _baid64::ParseAddrError;
 use aluvm::fe256;
-use amplify::confinement::SmallVec;
+use amplify::confinement::{SmallVec, U16, U24};
+use amplify::num::u256;
 use amplify::Bytes32;
 use commit_verify::{
-    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, MerkleHash, ReservedBytes,
-    Sha256,
+    CommitEncode, CommitEngine, CommitId, CommitmentId, DigestExt, MerkleHash, MerkleLeaves,
+    ReservedBytes, StrictHash,
+};
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictDeserialize, StrictEncode, StrictSerialize, TypedRead,
+    TypedWrite,
 };
 
-use crate::{CallId, CodexId, ContractId, StateCell, StateData, StateValue, LIB_NAME_ULTRASONIC};
+use crate::{
+    check_reserved, CallId, CodexId, ContractId, IdHash, ReservedError, StateCell, StateData,
+    StateValue, LIB_NAME_ULTRASONIC,
+};
 
 /// Unique operation (genesis, extensions & state transition) identifier
 /// equivalent to the commitment hash
@@ -41,11 +51,6 @@ use crate::{CallId, CodexId, ContractId, StateCell, StateData, StateValue, LIB_N
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
-#[cfg_attr(
-    all(feature = "serde", not(feature = "baid64")),
-    derive(Serialize, Deserialize),
-    serde(transparent)
-)]
 pub struct Opid(
     #[from]
     #[from([u8; 32])]
@@ -56,8 +61,8 @@ impl From<Opid> for [u8; 32] {
     fn from(opid: Opid) -> Self { opid.to_byte_array() }
 }
 
-impl From<Sha256> for Opid {
-    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+impl From<IdHash> for Opid {
+    fn from(hasher: IdHash) -> Self { hasher.finish().into() }
 }
 
 impl CommitmentId for Opid {
@@ -78,8 +83,8 @@ impl From<GenesisId> for [u8; 32] {
     fn from(id: GenesisId) -> Self { id.to_byte_array() }
 }
 
-impl From<Sha256> for GenesisId {
-    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+impl From<IdHash> for GenesisId {
+    fn from(hasher: IdHash) -> Self { hasher.finish().into() }
 }
 
 impl CommitmentId for GenesisId {
@@ -91,7 +96,6 @@ impl CommitmentId for GenesisId {
 #[commit_encode(strategy = strict, id = MerkleHash)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
-#[cfg_attr(all(feature = "serde", not(feature = "baid64")), derive(Serialize, Deserialize))]
 pub struct CellAddr {
     pub opid: Opid,
     pub pos: u16,
@@ -99,6 +103,94 @@ pub struct CellAddr {
 
 impl CellAddr {
     pub fn new(opid: Opid, pos: u16) -> Self { Self { opid, pos } }
+
+    /// Constructs address of a destructible (read-once) memory cell, checking that `pos` is
+    /// within the number `max` of the destructible cells created by the operation.
+    pub fn destructible(opid: Opid, pos: u16, max: u16) -> Result<Self, CellPosError> {
+        Self::checked(opid, pos, max)
+    }
+
+    /// Constructs address of an immutable memory cell, checking that `pos` is within the number
+    /// `max` of the immutable cells created by the operation.
+    pub fn immutable(opid: Opid, pos: u16, max: u16) -> Result<Self, CellPosError> {
+        Self::checked(opid, pos, max)
+    }
+
+    /// Constructs address of a destructible (read-once) memory cell created by the operation,
+    /// checking that the operation has a cell at `pos`.
+    pub fn destructible_of(operation: &Operation, pos: u16) -> Result<Self, CellPosError> {
+        Self::checked(operation.opid(), pos, operation.destructible.len() as u16)
+    }
+
+    /// Constructs address of an immutable memory cell created by the operation, checking that
+    /// the operation has a cell at `pos`.
+    pub fn immutable_of(operation: &Operation, pos: u16) -> Result<Self, CellPosError> {
+        Self::checked(operation.opid(), pos, operation.immutable.len() as u16)
+    }
+
+    fn checked(opid: Opid, pos: u16, max: u16) -> Result<Self, CellPosError> {
+        if pos >= max {
+            return Err(CellPosError { pos, max });
+        }
+        Ok(Self { opid, pos })
+    }
+
+    /// Detects whether the cell is created by the contract genesis with the given `genesis_opid`
+    /// (see [`crate::Contract::genesis_opid`]).
+    pub fn is_genesis(&self, genesis_opid: Opid) -> bool { self.opid == genesis_opid }
+
+    /// Encodes the address into a pair of field elements, which can be used by verifier scripts
+    /// to reference memory cells.
+    ///
+    /// The canonical encoding is the following:
+    /// - the first element holds the first 16 bytes of the operation id;
+    /// - the second element holds the last 16 bytes of the operation id followed by the two bytes
+    ///   of the cell position.
+    ///
+    /// Both elements are little-endian numbers below `2^144`, such that they fit any field order
+    /// supported by zk-AluVM.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ultrasonic::{CellAddr, Opid};
+    ///
+    /// let addr = CellAddr::new(Opid::from([0xA5; 32]), 1);
+    /// assert_eq!(CellAddr::from_fe256_pair(addr.to_fe256_pair()), Some(addr));
+    /// ```
+    pub fn to_fe256_pair(&self) -> [fe256; 2] {
+        let opid = self.opid.to_byte_array();
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        first[..16].copy_from_slice(&opid[..16]);
+        second[..16].copy_from_slice(&opid[16..]);
+        second[16..18].copy_from_slice(&self.pos.to_le_bytes());
+        [fe256::from(first), fe256::from(second)]
+    }
+
+    /// Decodes the address from a pair of field elements produced by [`Self::to_fe256_pair`].
+    ///
+    /// Returns `None` if the elements are not a canonical encoding of an address.
+    pub fn from_fe256_pair(pair: [fe256; 2]) -> Option<Self> {
+        let first = pair[0].to_u256().to_le_bytes();
+        let second = pair[1].to_u256().to_le_bytes();
+        if first[16..].iter().chain(&second[18..]).any(|b| *b != 0) {
+            return None;
+        }
+        let mut opid = [0u8; 32];
+        opid[..16].copy_from_slice(&first[..16]);
+        opid[16..].copy_from_slice(&second[..16]);
+        let pos = u16::from_le_bytes([second[16], second[17]]);
+        Some(Self::new(Opid::from(opid), pos))
+    }
+}
+
+/// memory cell position {pos} exceeds the number of the operation outputs ({max}).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct CellPosError {
+    pub pos: u16,
+    pub max: u16,
 }
 
 #[cfg(feature = "baid64")]
@@ -111,6 +203,7 @@ mod _baid64 {
     use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
 
     use super::*;
+    use crate::display::parse_id;
 
     impl Display for CellAddr {
         fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -129,7 +222,7 @@ mod _baid64 {
     impl FromBaid64Str for Opid {}
     impl FromStr for Opid {
         type Err = Baid64ParseError;
-        fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+        fn from_str(s: &str) -> Result<Self, Self::Err> { parse_id(s) }
     }
     impl Display for Opid {
         fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
@@ -170,47 +263,24 @@ mod _baid64 {
     }
 }
 
-#[cfg(all(feature = "serde", feature = "baid64"))]
-mod _serde {
-    use core::str::FromStr;
+#[cfg(feature = "serde")]
+crate::serde_id::impl_serde_id!(Opid);
 
-    use amplify::ByteArray;
+#[cfg(feature = "serde")]
+mod _serde {
     use serde::de::Error;
     use serde::ser::SerializeTuple;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     use super::*;
-
-    // TODO: Use Base64 macro
-    impl Serialize for Opid {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where S: Serializer {
-            if serializer.is_human_readable() {
-                self.to_string().serialize(serializer)
-            } else {
-                self.to_byte_array().serialize(serializer)
-            }
-        }
-    }
-
-    impl<'de> Deserialize<'de> for Opid {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer<'de> {
-            if deserializer.is_human_readable() {
-                let s = String::deserialize(deserializer)?;
-                Self::from_str(&s).map_err(D::Error::custom)
-            } else {
-                let bytes = <[u8; 32]>::deserialize(deserializer)?;
-                Ok(Self::from_byte_array(bytes))
-            }
-        }
-    }
+    use crate::serde_id;
 
     impl Serialize for CellAddr {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer {
             if serializer.is_human_readable() {
-                self.to_string().serialize(serializer)
+                let opid = serde_id::to_string(self.opid.to_byte_array());
+                serializer.serialize_str(&format!("{opid}:{}", self.pos))
             } else {
                 let mut ser = serializer.serialize_tuple(1)?;
                 ser.serialize_element(&self.opid)?;
@@ -223,9 +293,27 @@ mod _serde {
     impl<'de> Deserialize<'de> for CellAddr {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where D: Deserializer<'de> {
+            /// Cell addresses were serialized as structures by the versions which didn't use Baid64
+            /// for serde.
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum Repr {
+                Str(String),
+                Legacy { opid: Opid, pos: u16 },
+            }
+
             if deserializer.is_human_readable() {
-                let s = String::deserialize(deserializer)?;
-                CellAddr::from_str(&s).map_err(D::Error::custom)
+                match Repr::deserialize(deserializer)? {
+                    Repr::Str(s) => {
+                        let (opid, pos) = s.rsplit_once(':').ok_or_else(|| {
+                            D::Error::custom(format!("cell address '{s}' lacks separator ':'"))
+                        })?;
+                        let opid = serde_id::from_str::<D, 32>(opid, serde_id::legacy::<Opid, 32>)?;
+                        let pos = pos.parse().map_err(D::Error::custom)?;
+                        Ok(CellAddr::new(Opid::from(opid), pos))
+                    }
+                    Repr::Legacy { opid, pos } => Ok(CellAddr::new(opid, pos)),
+                }
             } else {
                 <(Opid, u16)>::deserialize(deserializer).map(|(opid, pos)| CellAddr::new(opid, pos))
             }
@@ -244,6 +332,33 @@ pub struct Input {
     pub witness: StateValue,
 }
 
+/// Content of an immutable memory cell which the operation creator expects the operation to read.
+///
+/// The expected content is checked against the memory during the verification of access
+/// conditions, protecting from verifying the operation against a different immutable value than
+/// the creator has intended (e.g. after a reorg or a rollback of the contract state).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = MerkleHash)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct ReadIntent {
+    /// Position of the memory cell address in [`Operation::reading`].
+    pub pos: u16,
+    /// Commitment id of the expected state value of the memory cell.
+    pub value_hash: StrictHash,
+}
+
+impl ReadIntent {
+    /// Constructs the read intent expecting the memory cell at position `pos` of
+    /// [`Operation::reading`] to contain the `value`.
+    pub fn new(pos: u16, value: &StateValue) -> Self { Self { pos, value_hash: value.commit_id() } }
+
+    /// Checks whether the state value matches the intent.
+    pub fn matches(&self, value: &StateValue) -> bool { self.value_hash == value.commit_id() }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
@@ -291,6 +406,8 @@ impl Genesis {
             reading: none!(),
             destructible: self.destructible.clone(),
             immutable: self.immutable.clone(),
+            witness_commitment: None,
+            read_intents: none!(),
             reserved: self.reserved,
         }
     }
@@ -298,10 +415,23 @@ impl Genesis {
 
 impl Genesis {
     pub fn opid(&self, contract_id: ContractId) -> Opid { self.to_operation(contract_id).opid() }
+
+    /// Checks that all reserved fields of the genesis, including the blanks, are zero.
+    pub fn check_reserved(&self) -> Result<(), ReservedError> {
+        check_reserved!(self, "Genesis", blank1, blank2, reserved);
+        Ok(())
+    }
 }
 
+/// Operation over the contract memory.
+///
+/// The strict encoding is versioned, using the first of the previously reserved bytes as the
+/// version tag. Operations which don't use [`Self::witness_commitment`] and
+/// [`Self::read_intents`] are encoded exactly as in version 0.12.0-beta.4 (version 0), where the
+/// reserved bytes are zero; other operations are encoded with the version 1 tag followed by the
+/// new fields and the reserved bytes.
 #[derive(Clone, PartialEq, Eq, Debug)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictDumb)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub struct Operation {
@@ -315,9 +445,134 @@ pub struct Operation {
     pub destructible: SmallVec<StateCell>,
     /// Immutable memory data which were created (write-once, readable by all).
     pub immutable: SmallVec<StateData>,
+    /// Commitment to the witness data, which must be bound to the operation at its creation time.
+    ///
+    /// The commitment is a part of the operation id, while the witness itself is not; scripts
+    /// access the commitment with [`crate::UsonicInstr::LdW`] and check it against the provided
+    /// witness. Operations without the commitment keep the ids they had before the field was
+    /// introduced.
+    pub witness_commitment: Option<fe256>,
+    /// Expected content of some of the immutable memory cells read by the operation, in the order
+    /// of their positions in [`Self::reading`].
+    ///
+    /// Operations without read intents keep the ids they had before the field was introduced.
+    pub read_intents: SmallVec<ReadIntent>,
     pub reserved: ReservedBytes<8>,
 }
 
+impl StrictSerialize for Operation {}
+impl StrictDeserialize for Operation {}
+
+/// Wire format of [`Operation`].
+mod wire {
+    use super::*;
+
+    /// Operation fields following the immutable outputs, prefixed with the version tag.
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+    pub(super) enum Extensions {
+        /// Version 0: the rest of the reserved bytes, which must be zero.
+        #[strict_type(tag = 0x00, dumb)]
+        V0(ReservedBytes<7>),
+        #[strict_type(tag = 0x01)]
+        V1(ExtensionsV1),
+    }
+
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub(super) struct ExtensionsV1 {
+        pub witness_commitment: Option<fe256>,
+        pub read_intents: SmallVec<ReadIntent>,
+        pub reserved: ReservedBytes<8>,
+    }
+
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub(super) struct Operation {
+        pub contract_id: ContractId,
+        pub call_id: CallId,
+        pub nonce: fe256,
+        pub destroying: SmallVec<Input>,
+        pub reading: SmallVec<CellAddr>,
+        pub destructible: SmallVec<StateCell>,
+        pub immutable: SmallVec<StateData>,
+        pub extensions: Extensions,
+    }
+}
+
+impl Operation {
+    /// Detects whether the operation can be encoded with the version 0 layout.
+    fn is_v0(&self) -> bool {
+        self.witness_commitment.is_none()
+            && self.read_intents.is_empty()
+            && self.reserved == ReservedBytes::default()
+    }
+}
+
+impl StrictEncode for Operation {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        let extensions = if self.is_v0() {
+            wire::Extensions::V0(default!())
+        } else {
+            wire::Extensions::V1(wire::ExtensionsV1 {
+                witness_commitment: self.witness_commitment,
+                read_intents: self.read_intents.clone(),
+                reserved: self.reserved,
+            })
+        };
+        wire::Operation {
+            contract_id: self.contract_id,
+            call_id: self.call_id,
+            nonce: self.nonce,
+            destroying: self.destroying.clone(),
+            reading: self.reading.clone(),
+            destructible: self.destructible.clone(),
+            immutable: self.immutable.clone(),
+            extensions,
+        }
+        .strict_encode(writer)
+    }
+}
+
+impl StrictDecode for Operation {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let op = wire::Operation::strict_decode(reader)?;
+        let (witness_commitment, read_intents, reserved, v1) = match op.extensions {
+            wire::Extensions::V0(reserved) if reserved != default!() => {
+                return Err(DecodeError::DataIntegrityError(s!(
+                    "operation has non-zero reserved bytes"
+                )));
+            }
+            wire::Extensions::V0(_) => (None, none!(), default!(), false),
+            wire::Extensions::V1(ext) => {
+                (ext.witness_commitment, ext.read_intents, ext.reserved, true)
+            }
+        };
+        let op = Operation {
+            contract_id: op.contract_id,
+            call_id: op.call_id,
+            nonce: op.nonce,
+            destroying: op.destroying,
+            reading: op.reading,
+            destructible: op.destructible,
+            immutable: op.immutable,
+            witness_commitment,
+            read_intents,
+            reserved,
+        };
+        // Each operation has a single encoding
+        if v1 && op.is_v0() {
+            return Err(DecodeError::DataIntegrityError(s!(
+                "operation without extensions is encoded with a version 1 layout"
+            )));
+        }
+        Ok(op)
+    }
+}
+
+impl StrictSerialize for Genesis {}
+impl StrictDeserialize for Genesis {}
+
 impl PartialOrd for Operation {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
 }
@@ -328,18 +583,628 @@ impl Ord for Operation {
 impl CommitEncode for Operation {
     type CommitmentId = Opid;
 
-    fn commit_encode(&self, e: &mut CommitEngine) {
+    fn commit_encode(&self, e: &mut CommitEngine) { self.commit_encode_with(&self.immutable, e) }
+}
+
+impl Operation {
+    /// Commits to the operation, taking the immutable outputs from `immutable` instead of
+    /// [`Self::immutable`], which allows computing ids of operations with streamed outputs.
+    pub(crate) fn commit_encode_with<L>(&self, immutable: &L, e: &mut CommitEngine)
+    where L: MerkleLeaves<Leaf = StateData> {
+        self.commit_encode_outputs(
+            e,
+            |e| e.commit_to_merkle(&self.destructible),
+            |e| e.commit_to_merkle(immutable),
+        )
+    }
+
+    /// Commits to the operation, using `destructible` and `immutable` to commit to the outputs,
+    /// which allows computing ids of operations with concealed outputs.
+    pub(crate) fn commit_encode_outputs(
+        &self,
+        e: &mut CommitEngine,
+        destructible: impl FnOnce(&mut CommitEngine),
+        immutable: impl FnOnce(&mut CommitEngine),
+    ) {
+        // Any change to the committed fields must be reflected in
+        // `Operation::explain_id_mismatch`.
         e.commit_to_serialized(&self.contract_id);
         e.commit_to_serialized(&self.call_id);
         e.commit_to_serialized(&self.nonce);
         e.commit_to_merkle(&self.destroying);
         e.commit_to_merkle(&self.reading);
-        e.commit_to_merkle(&self.destructible);
-        e.commit_to_merkle(&self.immutable);
+        destructible(e);
+        immutable(e);
         e.commit_to_serialized(&self.reserved);
+        // Optional fields are committed to only when present, keeping ids of the operations which
+        // don't use them. The fields take 32 and 2 + 34 * n bytes, so no combination of them can
+        // be serialized into the same bytes as another one.
+        if let Some(commitment) = &self.witness_commitment {
+            e.commit_to_serialized(commitment);
+        }
+        if !self.read_intents.is_empty() {
+            e.commit_to_serialized(&self.read_intents);
+        }
     }
 }
 
+// Output positions are addressed with `u16`, thus the number of outputs must not exceed the
+// number of values it can represent.
+const _: () = assert!(U16 <= u16::MAX as usize + 1);
+
 impl Operation {
+    /// Maximal number of read-once and, separately, immutable memory cells created by an
+    /// operation.
+    ///
+    /// The limit is implied by `u16` positions in [`CellAddr`] and is enforced by the
+    /// confinement of the output vectors both at construction and at decoding.
+    pub const MAX_OUTPUTS: usize = U16;
+
     pub fn opid(&self) -> Opid { self.commit_id() }
+
+    /// Checks that all reserved fields of the operation are zero.
+    pub fn check_reserved(&self) -> Result<(), ReservedError> {
+        check_reserved!(self, "Operation", reserved);
+        Ok(())
+    }
+
+    /// Returns read-once memory cell created by the operation at position `pos`.
+    pub fn destructible_at(&self, pos: u16) -> Option<&StateCell> {
+        self.destructible.get(pos as usize)
+    }
+
+    /// Returns immutable memory cell created by the operation at position `pos`.
+    pub fn immutable_at(&self, pos: u16) -> Option<&StateData> { self.immutable.get(pos as usize) }
+
+    /// Checks that all field elements in the operation (nonce, witnesses, witness commitment and
+    /// outputs) are canonical, i.e. less than the field `order`.
+    pub fn is_canonical(&self, order: u256) -> bool {
+        self.nonce.to_u256() < order
+            && self
+                .destroying
+                .iter()
+                .all(|input| input.witness.is_canonical(order))
+            && self
+                .destructible
+                .iter()
+                .all(|cell| cell.is_canonical(order))
+            && self.immutable.iter().all(|data| data.is_canonical(order))
+            && self
+                .witness_commitment
+                .map_or(true, |commitment| commitment.to_u256() < order)
+    }
+
+    /// Returns witness data of the operation.
+    ///
+    /// NB: input witnesses are a part of [`Input`] and thus are committed to by the operation id.
+    pub fn witness(&self) -> OperationWitness {
+        OperationWitness {
+            inputs: SmallVec::from_iter_checked(self.destroying.iter().map(|input| input.witness)),
+        }
+    }
+
+    /// Returns strict-encoded [`OperationWitness`], allowing to store and transport witnesses
+    /// detached from the committed operation data.
+    pub fn witness_bytes(&self) -> Vec<u8> {
+        self.witness()
+            .to_strict_serialized::<U24>()
+            .expect("operation witness size is always less than 2^24 bytes")
+            .release()
+    }
+
+    /// Replaces all witnesses of the operation inputs with the provided ones.
+    ///
+    /// Since input witnesses are committed to, this changes the operation id unless the provided
+    /// witnesses are the same as the existing ones.
+    pub fn set_witness(&mut self, witness: OperationWitness) -> Result<(), WitnessMismatch> {
+        if witness.inputs.len() != self.destroying.len() {
+            return Err(WitnessMismatch {
+                expected: self.destroying.len(),
+                found: witness.inputs.len(),
+            });
+        }
+        for (input, witness) in self.destroying.iter_mut().zip(witness.inputs) {
+            input.witness = witness;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the operation with all input witnesses replaced by [`StateValue::None`].
+    ///
+    /// The redacted operation allows sharing the operation data while withholding the data
+    /// satisfying the input locks, which can be re-attached later with [`Self::set_witness`].
+    /// Since input witnesses are committed to, the redacted operation has a different operation
+    /// id, unless all the witnesses are already empty.
+    pub fn redact_witnesses(&self) -> Self {
+        let mut redacted = self.clone();
+        for input in &mut redacted.destroying {
+            input.witness = StateValue::None;
+        }
+        redacted
+    }
+}
+
+/// Witness data of an operation, detached from the rest of the operation data.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct OperationWitness {
+    /// Witnesses of the destroyed memory cells, in the order of [`Operation::destroying`].
+    pub inputs: SmallVec<StateValue>,
+}
+
+impl StrictSerialize for OperationWitness {}
+impl StrictDeserialize for OperationWitness {}
+
+/// First difference in the commitment streams of two operations, explaining why their ids differ.
+///
+/// Returned by [`Operation::explain_id_mismatch`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display)]
+#[display(doc_comments)]
+pub enum IdMismatch {
+    /// operations commit to different values of `{0}` field.
+    Field(&'static str),
+
+    /// operations commit to `{field}` lists of different length ({first} and {second}).
+    Len {
+        field: &'static str,
+        first: usize,
+        second: usize,
+    },
+
+    /// operations commit to different items at position {pos} of `{field}` list.
+    Item { field: &'static str, pos: usize },
+}
+
+/// Compares commitments of the list items, returning the first difference.
+fn list_mismatch<T: CommitEncode<CommitmentId = MerkleHash>>(
+    field: &'static str,
+    first: &[T],
+    second: &[T],
+) -> Option<IdMismatch> {
+    if first.len() != second.len() {
+        return Some(IdMismatch::Len { field, first: first.len(), second: second.len() });
+    }
+    first
+        .iter()
+        .zip(second)
+        .position(|(a, b)| a.commit_id() != b.commit_id())
+        .map(|pos| IdMismatch::Item { field, pos })
+}
+
+impl Operation {
+    /// Explains why two operations have different ids, returning the first committed field
+    /// (in the commitment order) where their commitment streams diverge.
+    ///
+    /// Fields committed as merkle trees are compared by their length and the commitments of
+    /// their items, such that the exact diverging item is reported. Returns `None` if the
+    /// operations have the same id.
+    pub fn explain_id_mismatch(&self, other: &Operation) -> Option<IdMismatch> {
+        if self.contract_id != other.contract_id {
+            return Some(IdMismatch::Field("contract_id"));
+        }
+        if self.call_id != other.call_id {
+            return Some(IdMismatch::Field("call_id"));
+        }
+        if self.nonce != other.nonce {
+            return Some(IdMismatch::Field("nonce"));
+        }
+        list_mismatch("destroying", &self.destroying, &other.destroying)
+            .or_else(|| list_mismatch("reading", &self.reading, &other.reading))
+            .or_else(|| list_mismatch("destructible", &self.destructible, &other.destructible))
+            .or_else(|| list_mismatch("immutable", &self.immutable, &other.immutable))
+            .or_else(|| (self.reserved != other.reserved).then_some(IdMismatch::Field("reserved")))
+            .or_else(|| {
+                (self.witness_commitment != other.witness_commitment)
+                    .then_some(IdMismatch::Field("witness_commitment"))
+            })
+            .or_else(|| list_mismatch("read_intents", &self.read_intents, &other.read_intents))
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(
+    "number of witnesses ({found}) doesn't match the number of operation inputs ({expected})."
+)]
+pub struct WitnessMismatch {
+    pub expected: usize,
+    pub found: usize,
+}
+
+/// Height of an operation in the contract history.
+///
+/// Genesis has zero height; any other operation is higher by one than the highest of the
+/// operations whose memory cells it destroys or reads. The height is assigned to an operation
+/// during its verification (see [`VerifiedOperation::height`]), providing the ordering semantic
+/// for the upper layers (like maturity rules).
+///
+/// The maximum depth of the contract history is limited by [`Height::MAX`]; operations which
+/// would exceed it are invalid.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
+#[display(inner)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Height(u32);
+
+impl Height {
+    /// Height of the contract genesis.
+    pub const GENESIS: Self = Self(0);
+    /// Maximal height of an operation in the contract history.
+    pub const MAX: Self = Self(u32::MAX);
+
+    pub const fn new(height: u32) -> Self { Self(height) }
+
+    pub const fn to_u32(&self) -> u32 { self.0 }
+
+    pub const fn is_genesis(&self) -> bool { self.0 == 0 }
+
+    /// Returns height of a child operation, unless it exceeds [`Height::MAX`].
+    pub const fn checked_next(self) -> Option<Self> {
+        match self.0.checked_add(1) {
+            Some(height) => Some(Self(height)),
+            None => None,
+        }
+    }
+
+    /// Returns height which is higher by `depth`, unless it exceeds [`Height::MAX`].
+    pub const fn checked_add(self, depth: u32) -> Option<Self> {
+        match self.0.checked_add(depth) {
+            Some(height) => Some(Self(height)),
+            None => None,
+        }
+    }
+
+    /// Returns the number of operations between the `base` height and this one, unless the
+    /// `base` is higher.
+    pub const fn checked_depth(self, base: Height) -> Option<u32> { self.0.checked_sub(base.0) }
+}
+
+impl From<u32> for Height {
+    fn from(height: u32) -> Self { Self(height) }
+}
+
+impl From<Height> for u32 {
+    fn from(height: Height) -> Self { height.0 }
+}
+
+/// Resources consumed by a successful operation verification.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct VerifyStats {
+    /// Complexity consumed by the lock scripts of all the operation inputs.
+    pub lock_complexity: u64,
+    /// Complexity consumed by the verification script.
+    pub main_complexity: u64,
+    /// Number of instructions executed by the verification script.
+    pub instructions: u64,
+}
+
+/// Operation which was successfully verified against a codex, borrowed from the caller.
+///
+/// Produced by [`crate::Codex::verify_ref`]; see [`VerifiedOperation`] for the owned version.
+///
+/// Equality ignores the verification [stats](Self::stats), which describe a specific verification
+/// run and not the operation.
+#[derive(Copy, Clone, Debug)]
+pub struct VerifiedRef<'op> {
+    pub(crate) opid: Opid,
+    pub(crate) operation: &'op Operation,
+    pub(crate) height: Height,
+    pub(crate) stats: VerifyStats,
+}
+
+impl PartialEq for VerifiedRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.opid == other.opid && self.operation == other.operation && self.height == other.height
+    }
+}
+impl Eq for VerifiedRef<'_> {}
+
+impl<'op> VerifiedRef<'op> {
+    pub fn opid(&self) -> Opid { self.opid }
+
+    pub fn height(&self) -> Height { self.height }
+
+    /// Returns the resources consumed by the operation verification.
+    pub fn stats(&self) -> VerifyStats { self.stats }
+
+    pub fn as_operation(&self) -> &'op Operation { self.operation }
+
+    /// Clones the operation into an owned [`VerifiedOperation`].
+    pub fn to_owned(&self) -> VerifiedOperation {
+        VerifiedOperation::new_unchecked(self.opid, self.operation.clone(), self.height)
+            .with_stats(self.stats)
+    }
+}
+
+/// Value of an operation annotation.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, From)]
+pub enum AnnotationValue {
+    #[from]
+    Text(String),
+    #[from]
+    Number(u64),
+    #[from]
+    Bytes(Vec<u8>),
+}
+
+impl From<&str> for AnnotationValue {
+    fn from(s: &str) -> Self { AnnotationValue::Text(s.to_owned()) }
+}
+
+/// Transient annotations of a verified operation, like the source peer, the time the operation was
+/// received or application-specific labels.
+///
+/// Annotations are never committed to or serialized as a part of the consensus data.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Annotations(BTreeMap<String, AnnotationValue>);
+
+impl Annotations {
+    pub fn get(&self, key: &str) -> Option<&AnnotationValue> { self.0.get(key) }
+
+    /// Adds an annotation, returning the previous value under the same key, if any.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<AnnotationValue>,
+    ) -> Option<AnnotationValue> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<AnnotationValue> { self.0.remove(key) }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AnnotationValue)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize { self.0.len() }
+
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+/// Operation which was successfully verified against a codex.
+///
+/// The type is produced by [`crate::Codex::verify`] and guarantees that the operation has passed
+/// all the codex checks; it also caches the operation id and its [`Height`].
+///
+/// The operation may carry non-committed [`Annotations`], which are passed along with it through
+/// the APIs. Equality ignores both the annotations and the verification [stats](Self::stats),
+/// comparing only the operation and its height.
+#[derive(Clone, Debug)]
+pub struct VerifiedOperation(Opid, Operation, Height, VerifyStats, Annotations);
+
+impl PartialEq for VerifiedOperation {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1 && self.2 == other.2
+    }
+}
+impl Eq for VerifiedOperation {}
+
+impl VerifiedOperation {
+    /// Constructs verified operation without performing verification.
+    ///
+    /// The caller must ensure that the operation has already been verified, and that the `opid`
+    /// and the `height` match the operation.
+    pub fn new_unchecked(opid: Opid, operation: Operation, height: Height) -> Self {
+        Self(opid, operation, height, VerifyStats::default(), Annotations::default())
+    }
+
+    pub(crate) fn with_stats(mut self, stats: VerifyStats) -> Self {
+        self.3 = stats;
+        self
+    }
+
+    pub fn opid(&self) -> Opid { self.0 }
+
+    pub fn height(&self) -> Height { self.2 }
+
+    /// Returns the resources consumed by the operation verification.
+    ///
+    /// Operations constructed with [`Self::new_unchecked`] report zero consumption.
+    pub fn stats(&self) -> VerifyStats { self.3 }
+
+    pub fn annotations(&self) -> &Annotations { &self.4 }
+
+    pub fn annotations_mut(&mut self) -> &mut Annotations { &mut self.4 }
+
+    /// Adds an annotation to the operation.
+    pub fn with_annotation(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<AnnotationValue>,
+    ) -> Self {
+        self.4.insert(key, value);
+        self
+    }
+
+    /// Iterates over the read-once memory cells created by the operation together with their
+    /// addresses.
+    pub fn destructible_cells(&self) -> impl Iterator<Item = (CellAddr, &StateCell)> {
+        let opid = self.0;
+        (0..=u16::MAX)
+            .zip(&self.1.destructible)
+            .map(move |(pos, cell)| (CellAddr::new(opid, pos), cell))
+    }
+
+    /// Iterates over the immutable memory cells created by the operation together with their
+    /// addresses.
+    pub fn immutable_cells(&self) -> impl Iterator<Item = (CellAddr, &StateData)> {
+        let opid = self.0;
+        (0..=u16::MAX)
+            .zip(&self.1.immutable)
+            .map(move |(pos, data)| (CellAddr::new(opid, pos), data))
+    }
+
+    pub fn as_operation(&self) -> &Operation { &self.1 }
+
+    pub fn into_operation(self) -> Operation { self.1 }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use amplify::hex::FromHex;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn verified_eq() {
+        let op = Operation::strict_dumb();
+        let verified = VerifiedOperation::new_unchecked(op.opid(), op.clone(), Height::new(1));
+        let other = verified
+            .clone()
+            .with_stats(VerifyStats { lock_complexity: 1, main_complexity: 2, instructions: 3 })
+            .with_annotation("note", "value");
+        assert_eq!(verified, other);
+
+        let later = VerifiedOperation::new_unchecked(op.opid(), op, Height::new(2));
+        assert_ne!(verified, later);
+    }
+
+    #[test]
+    fn id_mismatch() {
+        let op = Operation::strict_dumb();
+        assert_eq!(op.explain_id_mismatch(&op), None);
+
+        let mut other = op.clone();
+        other.call_id = CallId::new(1);
+        assert_eq!(op.explain_id_mismatch(&other), Some(IdMismatch::Field("call_id")));
+
+        let mut other = op.clone();
+        other.destructible.push(StateCell::strict_dumb()).unwrap();
+        assert_eq!(
+            op.explain_id_mismatch(&other),
+            Some(IdMismatch::Len { field: "destructible", first: 0, second: 1 })
+        );
+        let mut third = op.clone();
+        let cell = StateCell {
+            data: StateValue::from([u256::ONE]),
+            ..StateCell::strict_dumb()
+        };
+        third.destructible.push(cell).unwrap();
+        assert_eq!(
+            other.explain_id_mismatch(&third),
+            Some(IdMismatch::Item { field: "destructible", pos: 0 })
+        );
+    }
+
+    #[test]
+    fn witness_commitment() {
+        let op = Operation::strict_dumb();
+        let mut committed = op.clone();
+        committed.witness_commitment = Some(fe256::from(u256::ONE));
+        assert_ne!(committed.opid(), op.opid());
+        assert_eq!(
+            op.explain_id_mismatch(&committed),
+            Some(IdMismatch::Field("witness_commitment"))
+        );
+
+        committed.witness_commitment = Some(fe256::from(u256::MAX));
+        assert!(op.is_canonical(crate::FIELD_ORDER_SECP));
+        assert!(!committed.is_canonical(crate::FIELD_ORDER_SECP));
+    }
+
+    #[test]
+    fn versioned_encoding() {
+        // Operation with a single immutable output having raw data 0xDEAD, serialized by
+        // 0.12.0-beta.4
+        let legacy = Vec::<u8>::from_hex(
+            "1111111111111111111111111111111111111111111111111111111111111111\
+             0500\
+             0000000000000000000000000000000000000000000000000000000000000000\
+             0000000000000100000102\
+             00dead\
+             0000000000000000",
+        )
+        .unwrap();
+        let decode = |data: &[u8]| {
+            Operation::from_strict_serialized::<U24>(Confined::from_checked(data.to_vec()))
+        };
+        let encode = |op: &Operation| op.to_strict_serialized::<U24>().unwrap().release();
+
+        let op = decode(&legacy).unwrap();
+        assert_eq!(op.contract_id, ContractId::from([0x11; 32]));
+        assert_eq!(op.immutable.len(), 1);
+        assert_eq!(encode(&op), legacy);
+        assert_eq!(op, crate::compat::decode_operation_v0_12_beta4(&legacy).unwrap());
+
+        let mut extended = op.clone();
+        extended.witness_commitment = Some(fe256::from(u256::ONE));
+        let data = encode(&extended);
+        assert_eq!(data[..legacy.len() - 8], legacy[..legacy.len() - 8]);
+        assert_eq!(data[legacy.len() - 8], 0x01);
+        assert_eq!(decode(&data).unwrap(), extended);
+
+        // Version 1 layout without the extensions
+        let mut data = legacy[..legacy.len() - 8].to_vec();
+        data.extend([0x01, 0x00, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(decode(&data).is_err());
+
+        // Non-zero reserved bytes of version 0
+        let mut data = legacy.clone();
+        *data.last_mut().unwrap() = 1;
+        assert!(decode(&data).is_err());
+    }
+
+    #[test]
+    fn cell_addr_fe256_pair() {
+        let addr = CellAddr::new(Opid::from([0xFFu8; 32]), 0xABCD);
+        let pair = addr.to_fe256_pair();
+        assert_eq!(CellAddr::from_fe256_pair(pair), Some(addr));
+
+        let mut noise = [0u8; 32];
+        noise[25] = 1;
+        let noise = fe256::from(noise);
+        assert_eq!(CellAddr::from_fe256_pair([noise, pair[1]]), None);
+        assert_eq!(CellAddr::from_fe256_pair([pair[0], noise]), None);
+    }
+
+    #[test]
+    fn redact_witnesses() {
+        let witness = StateValue::from([u256::from(42u64)]);
+        let op = Operation {
+            contract_id: ContractId::from([0xCA; 32]),
+            call_id: CallId::new(0),
+            nonce: fe256::from(u256::ONE),
+            destroying: small_vec![
+                Input { addr: CellAddr::new(Opid::from([1; 32]), 0), witness },
+                Input {
+                    addr: CellAddr::new(Opid::from([2; 32]), 1),
+                    witness: StateValue::None
+                },
+            ],
+            reading: none!(),
+            destructible: none!(),
+            immutable: none!(),
+            witness_commitment: None,
+            read_intents: none!(),
+            reserved: default!(),
+        };
+
+        let mut redacted = op.redact_witnesses();
+        assert!(redacted
+            .destroying
+            .iter()
+            .all(|input| input.witness == StateValue::None));
+        assert_eq!(
+            redacted
+                .destroying
+                .iter()
+                .map(|input| input.addr)
+                .collect::<Vec<_>>(),
+            op.destroying
+                .iter()
+                .map(|input| input.addr)
+                .collect::<Vec<_>>()
+        );
+        // Witnesses are committed to, so the redaction is visible in the operation id
+        assert_ne!(redacted.opid(), op.opid());
+
+        redacted.set_witness(op.witness()).unwrap();
+        assert_eq!(redacted, op);
+        assert_eq!(redacted.opid(), op.opid());
+    }
 }
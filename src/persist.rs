@@ -0,0 +1,134 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use amplify::{ByteArray, Bytes32};
+use commit_verify::ReservedBytes;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{
+    CallError, Codex, CodexId, ContractId, Height, IdHash, IdHasher, LibRepo, Memory, Operation,
+    Opid, VerifiedOperation, LIB_NAME_ULTRASONIC,
+};
+
+/// Tag of the hash committing to the verification context of a [`PersistedOperation`].
+pub const VERIFY_CONTEXT_TAG: &str = "urn:ubideco:ultrasonic:verify-context#2025-01-24";
+
+/// Computes digest of the context an operation was verified in: the contract and its codex.
+pub fn verify_context_digest(contract_id: ContractId, codex_id: CodexId) -> Bytes32 {
+    let mut data = contract_id.to_byte_array().to_vec();
+    data.extend(codex_id.to_byte_array());
+    Bytes32::from(IdHash::digest_tagged(VERIFY_CONTEXT_TAG, &data))
+}
+
+/// Persisted form of a [`VerifiedOperation`].
+///
+/// The form allows stores to keep verified operations without re-verifying them on load. Stores
+/// which are trusted to keep the data intact can load operations with
+/// [`Self::assume_verified_unchecked`]; others should use [`Self::reverify`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct PersistedOperation {
+    /// Version of the persisted form; must be zero.
+    pub version: ReservedBytes<2>,
+    pub opid: Opid,
+    pub height: Height,
+    /// Digest of the verification context, see [`verify_context_digest`].
+    pub context: Bytes32,
+    pub operation: Operation,
+}
+
+impl StrictSerialize for PersistedOperation {}
+impl StrictDeserialize for PersistedOperation {}
+
+impl VerifiedOperation {
+    /// Converts the operation into its persisted form, recording that it was verified with the
+    /// codex having `codex_id`.
+    pub fn to_persisted(&self, codex_id: CodexId) -> PersistedOperation {
+        let operation = self.as_operation().clone();
+        PersistedOperation {
+            version: default!(),
+            opid: self.opid(),
+            height: self.height(),
+            context: verify_context_digest(operation.contract_id, codex_id),
+            operation,
+        }
+    }
+}
+
+impl PersistedOperation {
+    /// Restores the verified operation without running the verification.
+    ///
+    /// The method checks only that the operation was verified with the codex having `codex_id`,
+    /// and that the persisted operation id matches the operation. It must be used only with the
+    /// data coming from stores trusted to keep verified operations intact.
+    pub fn assume_verified_unchecked(
+        self,
+        codex_id: CodexId,
+    ) -> Result<VerifiedOperation, PersistError> {
+        if self.context != verify_context_digest(self.operation.contract_id, codex_id) {
+            return Err(PersistError::ContextMismatch(self.opid));
+        }
+        if self.operation.opid() != self.opid {
+            return Err(PersistError::OpidMismatch(self.opid));
+        }
+        Ok(VerifiedOperation::new_unchecked(self.opid, self.operation, self.height))
+    }
+
+    /// Verifies the persisted operation anew against the codex.
+    pub fn reverify(
+        self,
+        codex: &Codex,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedOperation, CallError> {
+        codex.verify(self.operation.contract_id, self.operation, memory, repo)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PersistError {
+    #[cfg_attr(
+        feature = "baid64",
+        display = "persisted operation {0} was verified in a different contract or with a \
+                   different codex."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "persisted operation {0:?} was verified in a different contract or with a \
+                   different codex."
+    )]
+    ContextMismatch(Opid),
+
+    #[cfg_attr(
+        feature = "baid64",
+        display = "persisted operation id {0} doesn't match the operation data."
+    )]
+    #[cfg_attr(
+        not(feature = "baid64"),
+        display = "persisted operation id {0:?} doesn't match the operation data."
+    )]
+    OpidMismatch(Opid),
+}
@@ -0,0 +1,252 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+#[cfg(feature = "prover-hooks")]
+use aluvm::{LibId, Vm};
+use amplify::confinement::U24;
+use strict_encoding::StrictSerialize;
+
+use crate::{CallError, Input, Operation, StateCell, VmContext};
+#[cfg(feature = "prover-hooks")]
+use crate::{Instr, OperationInputs};
+
+/// Hooks called by [`crate::Codex::verify_with_policy`] around each of the verification phases.
+///
+/// Overlay protocols can use the policy to enforce additional application-specific rules (like
+/// limits on the operation size) without re-implementing the verification procedure. All the hooks
+/// do nothing by default; an error returned by any of them aborts the verification. Policies
+/// should use [`CallError::Policy`] to report their own errors.
+///
+/// With the `prover-hooks` feature, the policy additionally receives the intermediate values of
+/// the verification phases, which zk prover pipelines must re-prove (see `VerifyTrace`).
+pub trait VerifyPolicy {
+    /// Called once the operation is known to belong to the verified contract, before any other
+    /// verification is done.
+    fn check_operation(&mut self, operation: &Operation) -> Result<(), CallError> {
+        let _ = operation;
+        Ok(())
+    }
+
+    /// Called once the operation inputs are resolved from the memory and checked to match the
+    /// operation, before running any of the scripts.
+    #[cfg(feature = "prover-hooks")]
+    fn after_inputs(
+        &mut self,
+        operation: &Operation,
+        inputs: &OperationInputs,
+    ) -> Result<(), CallError> {
+        let _ = (operation, inputs);
+        Ok(())
+    }
+
+    /// Called for each of the destroyed memory cells after its lock scripts were satisfied,
+    /// providing the complexity consumed by the scripts.
+    ///
+    /// Unlike [`Self::after_lock`], the hook is not called for cells without a lock.
+    #[cfg(feature = "prover-hooks")]
+    fn after_lock_script(
+        &mut self,
+        input: &Input,
+        cell: &StateCell,
+        complexity: u64,
+    ) -> Result<(), CallError> {
+        let _ = (input, cell, complexity);
+        Ok(())
+    }
+
+    /// Called for each of the destroyed memory cells before checking its lock conditions.
+    fn before_lock(&mut self, input: &Input, cell: &StateCell) -> Result<(), CallError> {
+        let _ = (input, cell);
+        Ok(())
+    }
+
+    /// Called for each of the destroyed memory cells after its lock conditions were satisfied.
+    fn after_lock(&mut self, input: &Input, cell: &StateCell) -> Result<(), CallError> {
+        let _ = (input, cell);
+        Ok(())
+    }
+
+    /// Called before running the operation verifier.
+    fn before_exec(&mut self, operation: &Operation, context: &VmContext) -> Result<(), CallError> {
+        let _ = (operation, context);
+        Ok(())
+    }
+
+    /// Called after the operation verifier has succeeded, providing the VM in the final state of
+    /// the verifier execution.
+    #[cfg(feature = "prover-hooks")]
+    fn after_verifier(
+        &mut self,
+        operation: &Operation,
+        context: &VmContext,
+        vm: &Vm<Instr<LibId>>,
+    ) -> Result<(), CallError> {
+        let _ = (operation, context, vm);
+        Ok(())
+    }
+
+    /// Called after the operation verifier has succeeded.
+    fn after_exec(&mut self, operation: &Operation, context: &VmContext) -> Result<(), CallError> {
+        let _ = (operation, context);
+        Ok(())
+    }
+
+    /// Called when the lock scripts of a destroyed memory cell or the operation verifier succeed,
+    /// providing the complexity consumed by the scripts.
+    ///
+    /// For the lock scripts, the complexity is the total of all the scripts run for the cell; the
+    /// hook is not called for cells without a lock.
+    fn after_script(&mut self, complexity: u64) -> Result<(), CallError> {
+        let _ = complexity;
+        Ok(())
+    }
+
+    /// Called when the lock scripts of a destroyed memory cell or the operation verifier fail,
+    /// providing the verification error and the complexity consumed by the failed scripts.
+    ///
+    /// For the lock scripts, the complexity is the total of all the scripts run for the cell. The
+    /// verification fails with the provided error after the hook returns.
+    fn after_script_failure(&mut self, err: &CallError, complexity: u64) {
+        let _ = (err, complexity);
+    }
+}
+
+/// Policy which doesn't add any verification rules.
+impl VerifyPolicy for () {}
+
+/// Consensus layer to which contract operations are anchored.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum Consensus {
+    Bitcoin,
+    Liquid,
+    Prime,
+}
+
+/// Limits on operation sizes, which are safe to relay and anchor for a specific consensus layer.
+///
+/// The limits are not a part of the contract consensus rules; they represent a common policy for
+/// the software broadcasting operations, such that integrators don't have to guess safe limits.
+/// The policy can be passed to [`crate::Codex::verify_with_policy`], rejecting operations which
+/// don't fit the limits with a [`CallError::Policy`] error carrying [`SizeError::code`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SizePolicy {
+    /// Maximal size of the strict-serialized operation, in bytes.
+    pub max_operation_size: usize,
+    /// Maximal size of the serialized operation witness (see [`Operation::witness_bytes`]), in
+    /// bytes.
+    pub max_witness_size: usize,
+    /// Maximal size of raw data in each of the immutable memory cells, in bytes.
+    pub max_raw_size: usize,
+}
+
+impl SizePolicy {
+    /// Returns the preset for the given consensus layer.
+    pub const fn for_consensus(consensus: Consensus) -> Self {
+        match consensus {
+            Consensus::Bitcoin => Self::bitcoin_anchor(),
+            Consensus::Liquid => Self::liquid_anchor(),
+            Consensus::Prime => Self::prime_anchor(),
+        }
+    }
+
+    /// Preset for operations anchored to Bitcoin.
+    pub const fn bitcoin_anchor() -> Self {
+        Self {
+            max_operation_size: 0x10000,
+            max_witness_size: 0x4000,
+            max_raw_size: 0x1000,
+        }
+    }
+
+    /// Preset for operations anchored to Liquid.
+    pub const fn liquid_anchor() -> Self {
+        Self {
+            max_operation_size: 0x20000,
+            max_witness_size: 0x8000,
+            max_raw_size: 0x4000,
+        }
+    }
+
+    /// Preset for operations anchored to Prime.
+    pub const fn prime_anchor() -> Self {
+        Self {
+            max_operation_size: 0x100000,
+            max_witness_size: 0x40000,
+            max_raw_size: 0xFFFF,
+        }
+    }
+
+    /// Checks that the operation fits the limits of the policy.
+    pub fn check(&self, operation: &Operation) -> Result<(), SizeError> {
+        let size = operation
+            .to_strict_serialized::<U24>()
+            .map(|data| data.len())
+            .unwrap_or(usize::MAX);
+        if size > self.max_operation_size {
+            return Err(SizeError::Operation { size, max: self.max_operation_size });
+        }
+        let size = operation.witness_bytes().len();
+        if size > self.max_witness_size {
+            return Err(SizeError::Witness { size, max: self.max_witness_size });
+        }
+        for data in &operation.immutable {
+            let size = data.raw.as_ref().map(|raw| raw.len()).unwrap_or_default();
+            if size > self.max_raw_size {
+                return Err(SizeError::Raw { size, max: self.max_raw_size });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl VerifyPolicy for SizePolicy {
+    fn check_operation(&mut self, operation: &Operation) -> Result<(), CallError> {
+        self.check(operation)
+            .map_err(|err| CallError::Policy(err.code()))
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SizeError {
+    /// operation size {size} exceeds the policy limit of {max} bytes.
+    Operation { size: usize, max: usize },
+
+    /// operation witness size {size} exceeds the policy limit of {max} bytes.
+    Witness { size: usize, max: usize },
+
+    /// raw data size {size} exceeds the policy limit of {max} bytes.
+    Raw { size: usize, max: usize },
+}
+
+impl SizeError {
+    /// Error code reported inside [`CallError::Policy`].
+    pub const fn code(&self) -> u32 {
+        match self {
+            SizeError::Operation { .. } => 1,
+            SizeError::Witness { .. } => 2,
+            SizeError::Raw { .. } => 3,
+        }
+    }
+}
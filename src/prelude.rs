@@ -0,0 +1,36 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Commonly used traits and types, allowing downstream code to start with a single
+//! `use ultrasonic::prelude::*;` import.
+
+pub use aluvm::{fe256, Lib, LibId, LibSite};
+pub use commit_verify::{CommitId, ReservedBytes};
+
+pub use crate::types::*;
+pub use crate::{
+    ApplyState, AuthToken, CallError, CallId, CellAddr, CellCategory, CellLock, Codex, CodexId,
+    Contract, ContractId, ContractMeta, Genesis, Height, Input, Instr, LibRepo, LiveState, Memory,
+    Operation, Opid, RawData, ReplayMemory, StateCell, StateData, StateValue, UsonicInstr,
+    VerifiedOperation, VerifyPolicy, ISA_ULTRASONIC,
+};
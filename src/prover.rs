@@ -0,0 +1,100 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Recording of the intermediate verification values for zk prover pipelines.
+
+use aluvm::{Core, LibId, Vm};
+
+use crate::{
+    CallError, CellAddr, Input, Instr, Operation, OperationInputs, StateCell, UsonicCore,
+    VerifyPolicy, VmContext,
+};
+
+/// Verification policy recording the intermediate values of the verification phases.
+///
+/// The trace contains the exact values a zk prover must re-prove for the verified operation:
+/// the inputs resolved from the memory, the complexity consumed by the lock scripts of each of
+/// the destroyed cells, and the final state of the verifier VM.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "fixtures")] {
+/// use ultrasonic::fixtures::Fixture;
+/// use ultrasonic::VerifyTrace;
+///
+/// let fixture = Fixture::counter();
+/// let contract_id = fixture.contract.contract_id();
+/// let op = fixture.operations[0].clone();
+///
+/// let mut trace = VerifyTrace::default();
+/// let verified = fixture
+///     .codex()
+///     .verify_with_policy(contract_id, op.clone(), &fixture, &fixture, &mut trace)
+///     .unwrap();
+/// let inputs = trace.inputs.unwrap();
+/// assert_eq!(inputs.read_once.len(), op.destroying.len());
+/// assert_eq!(inputs.height, verified.height());
+/// assert_eq!(trace.verifier.unwrap().ca(), verified.stats().main_complexity);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VerifyTrace {
+    /// Inputs of the operation resolved from the memory.
+    pub inputs: Option<OperationInputs>,
+    /// Complexity consumed by the lock scripts of each of the locked destroyed cells.
+    pub locks: Vec<(CellAddr, u64)>,
+    /// Core of the verifier VM after the successful execution.
+    pub verifier: Option<Core<LibId, UsonicCore>>,
+}
+
+impl VerifyPolicy for VerifyTrace {
+    fn after_inputs(
+        &mut self,
+        _operation: &Operation,
+        inputs: &OperationInputs,
+    ) -> Result<(), CallError> {
+        self.inputs = Some(inputs.clone());
+        Ok(())
+    }
+
+    fn after_lock_script(
+        &mut self,
+        input: &Input,
+        _cell: &StateCell,
+        complexity: u64,
+    ) -> Result<(), CallError> {
+        self.locks.push((input.addr, complexity));
+        Ok(())
+    }
+
+    fn after_verifier(
+        &mut self,
+        _operation: &Operation,
+        _context: &VmContext,
+        vm: &Vm<Instr<LibId>>,
+    ) -> Result<(), CallError> {
+        self.verifier = Some(vm.core.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeSet;
+
+use crate::{ApplyState, CellAddr, Codex, CodexFlags, LiveState, Opid, StateValue};
+
+/// Contract state which can drop immutable memory cells which can't be read by any future
+/// operation under the codex retention policy (see [`CodexFlags::BOUND_READS`]).
+///
+/// Without pruning, the append-only immutable memory of a long-running contract grows unbounded.
+pub trait Prunable: ApplyState + LiveState {
+    /// Removes the immutable memory cell from the state, returning its value.
+    fn remove_immutable(&mut self, addr: CellAddr) -> Option<StateValue>;
+
+    /// Returns addresses of the immutable memory cells which are provably unreferencable by the
+    /// future operations, in ascending order.
+    ///
+    /// Under the [`CodexFlags::BOUND_READS`] policy these are the immutable memory cells of the
+    /// operations which have no live read-once memory cells. Codices without a retention policy
+    /// have no unreferencable cells.
+    fn unreferencable(&self, codex: &Codex) -> Vec<CellAddr> {
+        if !codex.flags.contains(CodexFlags::BOUND_READS) {
+            return vec![];
+        }
+        let live = self
+            .live_read_once()
+            .map(|(addr, _)| addr.opid)
+            .collect::<BTreeSet<_>>();
+        self.live_immutable()
+            .map(|(addr, _)| addr)
+            .filter(|addr| !live.contains(&addr.opid))
+            .collect()
+    }
+
+    /// Prunes all [unreferencable](Self::unreferencable) immutable memory cells from the state,
+    /// returning a proof of the pruning validity.
+    fn prune(&mut self, codex: &Codex) -> PruneProof {
+        let mut pruned = vec![];
+        for addr in self.unreferencable(codex) {
+            if let Some(value) = self.remove_immutable(addr) {
+                pruned.push((addr, value));
+            }
+        }
+        PruneProof { pruned }
+    }
+}
+
+/// Proof of validity of the state pruning produced by [`Prunable::prune`].
+///
+/// Lists all the pruned immutable memory cells with their values, such that the pruned state can
+/// be audited and, if needed, restored.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PruneProof {
+    pub pruned: Vec<(CellAddr, StateValue)>,
+}
+
+impl PruneProof {
+    /// Iterates over the operations whose immutable memory cells were pruned.
+    pub fn operations(&self) -> impl Iterator<Item = Opid> + '_ {
+        self.pruned
+            .iter()
+            .map(|(addr, _)| addr.opid)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Checks the proof against the reduced state: the codex must have a retention policy, none
+    /// of the pruned cells may be present in the state, and none of the operations whose cells
+    /// were pruned may have live read-once memory cells.
+    pub fn verify(&self, codex: &Codex, state: &impl LiveState) -> bool {
+        if self.pruned.is_empty() {
+            return true;
+        }
+        if !codex.flags.contains(CodexFlags::BOUND_READS) {
+            return false;
+        }
+        let opids = self.operations().collect::<BTreeSet<_>>();
+        self.pruned
+            .iter()
+            .all(|(addr, _)| state.immutable(*addr).is_none())
+            && state
+                .live_read_once()
+                .all(|(addr, _)| !opids.contains(&addr.opid))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{Memory, ReplayMemory, StateCell};
+
+    #[test]
+    fn prune_bound_reads() {
+        let spent = Opid::from([1; 32]);
+        let live = Opid::from([2; 32]);
+        let mut memory = ReplayMemory::default();
+        memory
+            .read_once
+            .insert(CellAddr::new(live, 0), StateCell::strict_dumb());
+        for opid in [spent, live] {
+            memory
+                .immutable
+                .insert(CellAddr::new(opid, 0), StateValue::None);
+        }
+
+        let mut codex = Codex::strict_dumb();
+        assert!(memory.unreferencable(&codex).is_empty());
+
+        codex.flags = CodexFlags::BOUND_READS;
+        assert_eq!(memory.unreferencable(&codex), vec![CellAddr::new(spent, 0)]);
+        let proof = memory.prune(&codex);
+        assert_eq!(proof.pruned, vec![(CellAddr::new(spent, 0), StateValue::None)]);
+        assert!(proof.verify(&codex, &memory));
+        assert_eq!(memory.immutable(CellAddr::new(spent, 0)), None);
+        assert_eq!(memory.immutable(CellAddr::new(live, 0)), Some(StateValue::None));
+
+        // The proof doesn't hold for a state where the pruned operation has live cells
+        memory
+            .read_once
+            .insert(CellAddr::new(spent, 1), StateCell::strict_dumb());
+        assert!(!proof.verify(&codex, &memory));
+    }
+}
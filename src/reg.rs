@@ -0,0 +1,60 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Register conventions (ABI) used by [`crate::Codex::verify`] to pass data to lock scripts and
+//! operation verifiers, and to get error codes from them.
+//!
+//! Lock scripts receive the token of authority of the memory cell in [`AUTH`], the input witness
+//! in [`WITNESS0`]-[`WITNESS3`] and, when checked as a part of a control proof, the proof
+//! challenge in [`CHALLENGE`]. A failing lock script may put an error code into [`LOCK_ERR_CODE`].
+//! Lock scripts run under the USONIC ISA and may iterate over the state of the spending operation,
+//! for instance to constrain its outputs.
+//!
+//! Operation verifiers access the operation state with the USONIC iterator instructions; a failing
+//! verifier may put an error code into [`ERR_CODE`].
+
+use aluvm::RegE;
+
+/// Register with the token of authority of the memory cell, provided to a lock script.
+pub const AUTH: RegE = RegE::E1;
+
+/// Register with the first field element of the input witness, provided to a lock script.
+pub const WITNESS0: RegE = RegE::E2;
+/// Register with the second field element of the input witness, provided to a lock script.
+pub const WITNESS1: RegE = RegE::E3;
+/// Register with the third field element of the input witness, provided to a lock script.
+pub const WITNESS2: RegE = RegE::E4;
+/// Register with the fourth field element of the input witness, provided to a lock script.
+pub const WITNESS3: RegE = RegE::E5;
+/// Registers with the input witness elements, in order, provided to a lock script.
+pub const WITNESS: [RegE; 4] = [WITNESS0, WITNESS1, WITNESS2, WITNESS3];
+
+/// Register with the control proof challenge, provided to a lock script checked as a part of a
+/// [`crate::ControlProof`].
+pub const CHALLENGE: RegE = RegE::E6;
+
+/// Register with the error code set by a failed lock script.
+pub const LOCK_ERR_CODE: RegE = RegE::E8;
+
+/// Register with the error code set by a failed operation verifier.
+pub const ERR_CODE: RegE = RegE::E1;
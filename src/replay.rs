@@ -0,0 +1,145 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    ApplyError, ApplyState, CellAddr, ContractId, Genesis, Height, LiveState, Memory, Opid,
+    Prunable, StateCell, StateValue, VerifiedOperation,
+};
+
+/// In-memory contract state materialized from an ordered list of operations.
+///
+/// The memory is intended for verifying new operations against an in-flight batch of operations
+/// without a full state database: it can be passed directly to [`crate::Codex::verify`], and the
+/// resulting [`VerifiedOperation`] can be [applied](ApplyState::apply) to it to extend the history.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "fixtures")] {
+/// use ultrasonic::fixtures::Fixture;
+/// use ultrasonic::{ApplyError, ApplyState, ReplayMemory};
+///
+/// let fixture = Fixture::fungible_token();
+/// let contract_id = fixture.contract.contract_id();
+/// let mut memory =
+///     ReplayMemory::from_history(contract_id, &fixture.contract.genesis, []).unwrap();
+/// for op in fixture.operations.clone() {
+///     let verified = fixture
+///         .codex()
+///         .verify(contract_id, op, &memory, &fixture)
+///         .unwrap();
+///     memory.apply(verified.clone()).unwrap();
+///     // The same operation can't be applied twice
+///     assert!(matches!(memory.apply(verified), Err(ApplyError::NotLive(_))));
+/// }
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ReplayMemory {
+    pub(crate) read_once: BTreeMap<CellAddr, StateCell>,
+    pub(crate) immutable: BTreeMap<CellAddr, StateValue>,
+    pub(crate) heights: BTreeMap<Opid, Height>,
+    pub(crate) generation: u64,
+}
+
+impl ReplayMemory {
+    /// Materializes the state implied by the contract genesis and the history of operations
+    /// following it.
+    ///
+    /// The operations must be ordered such that each of them comes after all the operations it
+    /// depends on. Errors if some of the operations can't be applied to the state produced by the
+    /// preceding ones.
+    pub fn from_history(
+        contract_id: ContractId,
+        genesis: &Genesis,
+        ops: impl IntoIterator<Item = VerifiedOperation>,
+    ) -> Result<Self, ApplyError> {
+        let mut memory = Self::default();
+        let genesis = VerifiedOperation::new_unchecked(
+            genesis.opid(contract_id),
+            genesis.to_operation(contract_id),
+            Height::GENESIS,
+        );
+        memory.apply_unchecked(genesis);
+        for op in ops {
+            memory.apply(op)?;
+        }
+        Ok(memory)
+    }
+
+    /// Iterates over all live read-once memory cells.
+    pub fn read_once_cells(&self) -> impl Iterator<Item = (CellAddr, &StateCell)> {
+        self.read_once.iter().map(|(addr, cell)| (*addr, cell))
+    }
+
+    /// Iterates over all immutable memory cells.
+    pub fn immutable_cells(&self) -> impl Iterator<Item = (CellAddr, &StateValue)> {
+        self.immutable.iter().map(|(addr, value)| (*addr, value))
+    }
+}
+
+impl Memory for ReplayMemory {
+    fn read_once(&self, addr: CellAddr) -> Option<StateCell> { self.read_once.get(&addr).copied() }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.immutable.get(&addr).copied() }
+
+    fn height(&self, opid: Opid) -> Option<Height> { self.heights.get(&opid).copied() }
+
+    fn tip(&self) -> Option<u64> { Some(self.generation) }
+}
+
+impl LiveState for ReplayMemory {
+    fn live_read_once(&self) -> impl Iterator<Item = (CellAddr, StateCell)> + '_ {
+        self.read_once.iter().map(|(addr, cell)| (*addr, *cell))
+    }
+
+    fn live_immutable(&self) -> impl Iterator<Item = (CellAddr, StateValue)> + '_ {
+        self.immutable.iter().map(|(addr, value)| (*addr, *value))
+    }
+}
+
+impl Prunable for ReplayMemory {
+    fn remove_immutable(&mut self, addr: CellAddr) -> Option<StateValue> {
+        let value = self.immutable.remove(&addr)?;
+        self.generation += 1;
+        Some(value)
+    }
+}
+
+impl ApplyState for ReplayMemory {
+    fn apply_unchecked(&mut self, op: VerifiedOperation) {
+        self.generation += 1;
+        self.heights.insert(op.opid(), op.height());
+        for input in &op.as_operation().destroying {
+            self.read_once.remove(&input.addr);
+        }
+        for (addr, cell) in op.destructible_cells() {
+            self.read_once.insert(addr, *cell);
+        }
+        for (addr, data) in op.immutable_cells() {
+            self.immutable.insert(addr, data.value);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::BTreeSet;
+
+use crate::{CallError, CallId, Codex, ContractId, LibRepo, Memory, Operation, Opid, VmContext};
+
+/// List of all problems found in an operation by [`Codex::verify_report`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VerificationReport {
+    /// Id of the verified operation.
+    pub opid: Opid,
+    /// Detected errors, in the order of the verification phases which detected them.
+    pub errors: Vec<CallError>,
+}
+
+impl VerificationReport {
+    /// Detects whether the operation has passed all the checks.
+    pub fn is_valid(&self) -> bool { self.errors.is_empty() }
+
+    /// Converts the report into a result, returning the first detected error, if any.
+    ///
+    /// The returned error may differ from the one returned by [`Codex::verify`], which may run
+    /// the checks in a different order.
+    pub fn into_result(self) -> Result<Opid, CallError> {
+        match self.errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(self.opid),
+        }
+    }
+}
+
+impl Codex {
+    /// Verifies the operation like [`Self::verify`], but instead of stopping at the first error
+    /// continues past the failures where it is safe, reporting all the detected problems.
+    ///
+    /// All structural checks are performed, presence of all the inputs is checked, and lock
+    /// scripts of all the inputs are run. The lock scripts and the operation verifier run only if
+    /// all the inputs are present, since otherwise the VM context is incomplete; the verifier
+    /// runs even if some of the locks are not satisfied.
+    ///
+    /// The report is intended for wallets to show users everything wrong with a drafted
+    /// operation at once; a valid report doesn't replace [`Self::verify`].
+    pub fn verify_report(
+        &self,
+        contract_id: ContractId,
+        operation: &Operation,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> VerificationReport {
+        let mut errors = vec![];
+
+        if operation.contract_id != contract_id {
+            errors.push(CallError::WrongContract {
+                expected: contract_id,
+                found: operation.contract_id,
+            });
+        }
+        let _ = self.check_operation_with(operation, &mut |err| {
+            errors.push(err);
+            Ok(())
+        });
+
+        let mut read_once = Vec::with_capacity(operation.destroying.len());
+        for input in &operation.destroying {
+            match memory.read_once(input.addr) {
+                Some(cell) => read_once.push(cell),
+                None => errors.push(CallError::NoReadOnceInput(input.addr)),
+            }
+        }
+        let mut immutable = Vec::with_capacity(operation.reading.len());
+        for addr in &operation.reading {
+            match memory.immutable(*addr) {
+                Some(value) => immutable.push(value),
+                None => errors.push(CallError::NoImmutableInput(*addr)),
+            }
+        }
+        // Invalid positions are already reported by the structural checks
+        for intent in &operation.read_intents {
+            let Some(addr) = operation.reading.get(intent.pos as usize) else {
+                continue;
+            };
+            if matches!(memory.immutable(*addr), Some(value) if !intent.matches(&value)) {
+                errors.push(CallError::ReadIntentMismatch(*addr));
+            }
+        }
+        let parents = operation
+            .destroying
+            .iter()
+            .map(|input| input.addr.opid)
+            .chain(operation.reading.iter().map(|addr| addr.opid))
+            .collect::<BTreeSet<_>>();
+        for opid in parents {
+            match memory.height(opid) {
+                Some(height) if height.checked_next().is_none() => {
+                    errors.push(CallError::HeightOverflow)
+                }
+                Some(_) => {}
+                None => errors.push(CallError::NoHeight(opid)),
+            }
+        }
+
+        let opid = operation.opid();
+        if read_once.len() < operation.destroying.len() || immutable.len() < operation.reading.len()
+        {
+            return VerificationReport { opid, errors };
+        }
+
+        let read_once_input = read_once.iter().map(|cell| cell.data).collect::<Vec<_>>();
+        let context = VmContext {
+            read_once_input: read_once_input.as_slice(),
+            immutable_input: immutable.as_slice(),
+            read_once_output: operation.destructible.as_slice(),
+            immutable_output: operation.immutable.as_slice(),
+            witness_commitment: operation.witness_commitment,
+        };
+
+        let mut vm_inputs = None;
+        let mut complexity = 0u64;
+        for (input, cell) in operation.destroying.iter().zip(&read_once) {
+            if !cell.lock.is_locked() {
+                continue;
+            }
+            let vm = vm_inputs.get_or_insert_with(|| self.lock_vm());
+            if let Err(err) = self.check_lock(
+                vm,
+                cell.lock,
+                cell.auth,
+                &input.witness,
+                None,
+                &context,
+                repo,
+                &mut complexity,
+            ) {
+                errors.push(CallError::Lock(err));
+            }
+        }
+
+        match self.verifiers.get(&operation.call_id) {
+            // Already reported by the structural checks
+            _ if operation.call_id == CallId::INVARIANTS => {}
+            None => errors.push(CallError::NotFound(operation.call_id)),
+            Some(entry_point) => {
+                if let Err(err) = self.exec_verifier(*entry_point, &context, repo) {
+                    errors.push(err);
+                }
+            }
+        }
+
+        VerificationReport { opid, errors }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        CellAddr, Genesis, Input, ReadIntent, ReplayMemory, StateData, StateValue, FIELD_ORDER_SECP,
+    };
+
+    struct NoLibs;
+    impl LibRepo for NoLibs {
+        fn get_lib(&self, _: aluvm::LibId) -> Option<&aluvm::Lib> { None }
+    }
+
+    #[test]
+    fn reports_all() {
+        let codex = Codex { field_order: FIELD_ORDER_SECP, ..Codex::strict_dumb() };
+        let contract_id = ContractId::from([0xAB; 32]);
+        let mut op = Operation::strict_dumb();
+        let spent = CellAddr::new(Opid::from([1; 32]), 0);
+        let read = CellAddr::new(Opid::from([2; 32]), 0);
+        op.destroying
+            .push(Input { addr: spent, witness: none!() })
+            .unwrap();
+        op.reading.push(read).unwrap();
+
+        let report = codex.verify_report(contract_id, &op, &ReplayMemory::default(), &NoLibs);
+        assert!(!report.is_valid());
+        assert_eq!(report.opid, op.opid());
+        assert_eq!(report.errors, vec![
+            CallError::WrongContract { expected: contract_id, found: op.contract_id },
+            CallError::NoReadOnceInput(spent),
+            CallError::NoImmutableInput(read),
+            CallError::NoHeight(spent.opid),
+            CallError::NoHeight(read.opid),
+        ]);
+        assert_eq!(
+            report.into_result(),
+            Err(CallError::WrongContract { expected: contract_id, found: op.contract_id })
+        );
+    }
+
+    #[test]
+    fn read_intent_mismatch() {
+        let codex = Codex { field_order: FIELD_ORDER_SECP, ..Codex::strict_dumb() };
+        let contract_id = ContractId::from([0xAB; 32]);
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .immutable
+            .push(StateData::new(StateValue::None))
+            .unwrap();
+        let memory = ReplayMemory::from_history(contract_id, &genesis, []).unwrap();
+
+        let read = CellAddr::new(genesis.opid(contract_id), 0);
+        let mut op = Operation { contract_id, ..Operation::strict_dumb() };
+        op.reading.push(read).unwrap();
+        op.read_intents
+            .push(ReadIntent::new(0, &StateValue::from([amplify::num::u256::ONE])))
+            .unwrap();
+
+        let report = codex.verify_report(contract_id, &op, &memory, &NoLibs);
+        assert!(report.errors.contains(&CallError::ReadIntentMismatch(read)));
+
+        op.read_intents = small_vec![ReadIntent::new(0, &StateValue::None)];
+        let report = codex.verify_report(contract_id, &op, &memory, &NoLibs);
+        assert!(!report.errors.contains(&CallError::ReadIntentMismatch(read)));
+    }
+}
@@ -0,0 +1,55 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+/// Error indicating that a reserved field has a non-zero value.
+///
+/// Non-zero reserved fields usually mean the data were produced by a newer version of the
+/// software, which assigned a meaning to the field.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(
+    "reserved field `{0}` has a non-zero value; the data may come from a newer software version."
+)]
+pub struct ReservedError(pub &'static str);
+
+/// Checks that the reserved fields of a value are zero, returning [`ReservedError`] naming the
+/// first of the fields which is not.
+///
+/// # Example
+///
+/// ```
+/// # use ultrasonic::{check_reserved, Codex, ReservedError};
+/// fn check(codex: &Codex) -> Result<(), ReservedError> {
+///     check_reserved!(codex, "Codex", version, reserved);
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! check_reserved {
+    ($obj:expr, $name:literal, $($field:ident),+ $(,)?) => {
+        $(
+            if $obj.$field != ::core::default::Default::default() {
+                return Err($crate::ReservedError(concat!($name, ".", stringify!($field))));
+            }
+        )+
+    };
+}
@@ -0,0 +1,138 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! State schema descriptors committed to by a codex.
+//!
+//! By convention, the first field element of a state value is its type tag. A codex may describe
+//! the layout of state values for each of the type tags it uses, such that generic wallets and
+//! explorers can decode and render the state of contracts they don't know.
+
+use amplify::confinement::{TinyOrdMap, TinyString, TinyVec};
+use amplify::num::u256;
+
+use crate::{Codex, RawEncoding, StateData, StateValue, LIB_NAME_ULTRASONIC};
+
+/// State layout descriptors of a codex, indexed by the state type tag.
+pub type StateSchema = TinyOrdMap<u256, StateDescriptor>;
+
+/// Descriptor of the layout of state values with a specific type tag.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct StateDescriptor {
+    /// Human-readable name of the state type.
+    pub name: TinyString,
+    /// Names of the field elements following the type tag, in order.
+    ///
+    /// A state value matches the descriptor if it has exactly one field element more than the
+    /// number of names here (for the type tag).
+    pub elements: TinyVec<TinyString>,
+    /// Encoding of the raw data attached to the immutable memory cells with this state type, if
+    /// they are expected to have raw data.
+    pub raw: Option<RawEncoding>,
+}
+
+impl StateDescriptor {
+    /// Checks whether the state value has the number of field elements described.
+    pub fn matches(&self, value: &StateValue) -> bool {
+        value.get(0).is_some() && value.elements().count() == self.elements.len() + 1
+    }
+
+    /// Iterates over the names of the field elements of the state value, excluding the type tag,
+    /// paired with their values.
+    pub fn decode<'a>(
+        &'a self,
+        value: &'a StateValue,
+    ) -> impl Iterator<Item = (&'a str, u256)> + 'a {
+        self.elements
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, name)| Some((name.as_str(), value.get(pos as u8 + 1)?.to_u256())))
+    }
+}
+
+impl Codex {
+    /// Returns descriptors of the state layout committed to by the codex.
+    pub fn state_schema(&self) -> &StateSchema { &self.schema }
+
+    /// Returns descriptor of the state type with the given type tag, if the codex has one.
+    pub fn state_descriptor(&self, tag: u256) -> Option<&StateDescriptor> { self.schema.get(&tag) }
+
+    /// Returns descriptor of the state value by its type tag (the first field element), if the
+    /// codex has one and the value matches it.
+    pub fn describe(&self, value: &StateValue) -> Option<&StateDescriptor> {
+        let tag = value.get(0)?.to_u256();
+        self.state_descriptor(tag)
+            .filter(|descriptor| descriptor.matches(value))
+    }
+
+    /// Returns descriptor of the immutable state data, like [`Self::describe`], additionally
+    /// checking that the raw data presence and its encoding hint match the descriptor.
+    pub fn describe_data(&self, data: &StateData) -> Option<&StateDescriptor> {
+        self.describe(&data.value)
+            .filter(|descriptor| match (descriptor.raw, &data.raw) {
+                (None, None) => true,
+                (Some(encoding), Some(_)) => data.hint.unwrap_or_default() == encoding,
+                _ => false,
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallBlob;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn describe() {
+        let mut codex = Codex::strict_dumb();
+        let descriptor = StateDescriptor {
+            name: TinyString::from_checked(s!("Amount")),
+            elements: TinyVec::from_checked(vec![TinyString::from_checked(s!("value"))]),
+            raw: None,
+        };
+        codex
+            .schema
+            .insert(u256::from(7u8), descriptor.clone())
+            .unwrap();
+
+        let value = StateValue::from([u256::from(7u8), u256::from(100u8)]);
+        assert_eq!(codex.describe(&value), Some(&descriptor));
+        assert_eq!(descriptor.decode(&value).collect::<Vec<_>>(), vec![(
+            "value",
+            u256::from(100u8)
+        )]);
+
+        let wrong_len = StateValue::from([u256::from(7u8)]);
+        assert_eq!(codex.describe(&wrong_len), None);
+        let unknown = StateValue::from([u256::from(8u8), u256::from(100u8)]);
+        assert_eq!(codex.describe(&unknown), None);
+
+        let data = StateData::with_raw(value, SmallBlob::from_checked(vec![1, 2, 3]));
+        assert_eq!(codex.describe_data(&data), None);
+        assert_eq!(codex.describe_data(&StateData::new(value)), Some(&descriptor));
+    }
+}
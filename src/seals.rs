@@ -0,0 +1,156 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Helpers for binding read-once memory cells to single-use seals.
+//!
+//! A protocol using single-use seals puts a commitment to the seal definition into the token of
+//! authority of a read-once memory cell. The cell is destroyed by an operation only together with
+//! a witness proving the closing of the seals of all the destroyed cells over the operation id.
+//! The seal closing is verified outside of the VM, complementing [`crate::Codex::verify`].
+
+use core::fmt::{self, Debug, Display, Formatter};
+
+use crate::{
+    AuthToken, CallError, CellAddr, Codex, ContractId, IdHash, IdHasher, LibRepo, Memory,
+    Operation, Opid, VerifiedOperation,
+};
+
+/// Single-use seal definition, which can be committed to in a token of authority.
+pub trait SingleUseSeal {
+    /// Returns token of authority committing to the seal definition.
+    ///
+    /// Implementations are advised to use [`seal_auth_token`].
+    fn auth_token(&self) -> AuthToken;
+}
+
+/// Witness proving that a set of single-use seals is closed over a message.
+pub trait SealWitness<Seal: SingleUseSeal> {
+    type Error;
+
+    /// Verifies that all the `seals` are closed over the `message`.
+    fn verify_seals_closed(&self, seals: &[Seal], message: Opid) -> Result<(), Self::Error>;
+}
+
+/// Computes token of authority committing to the serialized seal definition `seal_data`, using a
+/// protocol-specific hash `tag`.
+///
+/// The hash is truncated to 30 bytes, such that it always fits a field element.
+pub fn seal_auth_token(tag: &str, seal_data: &[u8]) -> AuthToken {
+    let hash = IdHash::digest_tagged(tag, seal_data);
+    let mut bytes = [0u8; 30];
+    bytes.copy_from_slice(&hash[..30]);
+    AuthToken::from_byte_array(bytes)
+}
+
+/// Checks that the operation destroys only memory cells bound to the provided `seals`, and that
+/// the `witness` proves closing of the seals over the operation id.
+pub fn check_seals<S: SingleUseSeal, W: SealWitness<S>>(
+    operation: &Operation,
+    memory: &impl Memory,
+    seals: &[S],
+    witness: &W,
+) -> Result<(), SealError<W::Error>> {
+    for input in &operation.destroying {
+        let cell = memory
+            .read_once(input.addr)
+            .ok_or(SealError::UnknownInput(input.addr))?;
+        if !seals.iter().any(|seal| seal.auth_token() == cell.auth) {
+            return Err(SealError::NoSeal(input.addr));
+        }
+    }
+    witness
+        .verify_seals_closed(seals, operation.opid())
+        .map_err(SealError::Witness)
+}
+
+/// Single-use seal protocol, providing the canonical integration point for the client-side
+/// validation layers.
+///
+/// The seals are identified by the tokens of authority of the read-once memory cells they are
+/// bound to.
+pub trait SealProtocol {
+    type Error;
+
+    /// Closes the seal bound to the token of authority `auth` over the operation `opid`.
+    ///
+    /// Called by the party creating the operation for each of the memory cells it destroys.
+    fn close(&mut self, auth: AuthToken, opid: Opid) -> Result<(), Self::Error>;
+
+    /// Verifies that the seal bound to the token of authority `auth` is closed over the operation
+    /// `opid`.
+    fn verify(&self, auth: AuthToken, opid: Opid) -> Result<(), Self::Error>;
+}
+
+/// Verifies the operation with the `codex` and checks that the seals of all the memory cells the
+/// operation destroys are closed over it according to the seal `protocol`.
+pub fn verify_with_seals<P: SealProtocol>(
+    codex: &Codex,
+    contract_id: ContractId,
+    operation: Operation,
+    memory: &impl Memory,
+    repo: &impl LibRepo,
+    protocol: &P,
+) -> Result<VerifiedOperation, SealError<P::Error>> {
+    let verified = codex
+        .verify(contract_id, operation, memory, repo)
+        .map_err(SealError::Verify)?;
+    for input in &verified.as_operation().destroying {
+        let cell = memory
+            .read_once(input.addr)
+            .ok_or(SealError::UnknownInput(input.addr))?;
+        protocol
+            .verify(cell.auth, verified.opid())
+            .map_err(SealError::Witness)?;
+    }
+    Ok(verified)
+}
+
+/// Errors of the single-use seal checks.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SealError<E> {
+    /// The operation destroys a memory cell unknown to the memory.
+    UnknownInput(CellAddr),
+    /// The operation destroys a memory cell not bound to any of the provided seals.
+    NoSeal(CellAddr),
+    /// The witness doesn't prove closing of the seals.
+    Witness(E),
+    /// The operation is not valid according to the codex.
+    Verify(CallError),
+}
+
+impl<E: Display> Display for SealError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SealError::UnknownInput(addr) => {
+                write!(f, "operation destroys unknown memory cell {addr:?}.")
+            }
+            SealError::NoSeal(addr) => {
+                write!(f, "memory cell {addr:?} is not bound to any of the provided seals.")
+            }
+            SealError::Witness(err) => write!(f, "invalid seal closing witness: {err}"),
+            SealError::Verify(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: Debug + Display> core::error::Error for SealError<E> {}
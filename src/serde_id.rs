@@ -0,0 +1,156 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Serde representation of the identifiers, which doesn't depend on the enabled crate features.
+//!
+//! In human-readable formats, each identifier is written with a version discriminator, such that
+//! the readers can tell the representation apart from the other ones. Version 1 of the
+//! representation, produced by all builds, is `v1:` followed by the lowercase hex of the
+//! identifier bytes, and cell addresses are written as `<opid>:<pos>`. Binary formats use the plain
+//! bytes. Builds with the `baid64` feature additionally accept the baid64 strings produced by the
+//! versions preceding the versioned representation (version 0).
+//!
+//! The versions preceding the versioned representation can't read version 1.
+
+use amplify::hex::{FromHex, ToHex};
+use serde::de::Error;
+use serde::Deserializer;
+
+/// Version of the human-readable serde representation of the identifiers.
+pub const ID_SERDE_VERSION: u8 = 1;
+
+/// Version discriminator prefixing the human-readable representation of the identifiers.
+const VERSION_PREFIX: &str = "v1:";
+
+/// Returns the human-readable representation of the identifier bytes.
+pub(crate) fn to_string<const LEN: usize>(bytes: [u8; LEN]) -> String {
+    format!("{VERSION_PREFIX}{}", bytes.to_hex())
+}
+
+/// Parses the human-readable representation of the identifier bytes, falling back to the
+/// `legacy` parser for the strings which are not in the current version of the representation.
+pub(crate) fn from_str<'de, D: Deserializer<'de>, const LEN: usize>(
+    s: &str,
+    legacy: impl FnOnce(&str) -> Option<[u8; LEN]>,
+) -> Result<[u8; LEN], D::Error> {
+    s.strip_prefix(VERSION_PREFIX)
+        .and_then(|hex| Vec::<u8>::from_hex(hex).ok())
+        .and_then(|vec| <[u8; LEN]>::try_from(vec).ok())
+        .or_else(|| legacy(s))
+        .ok_or_else(|| D::Error::custom(format!("invalid identifier '{s}'")))
+}
+
+/// Parses a legacy (version 0) baid64 string, if the `baid64` feature is enabled.
+#[cfg(feature = "baid64")]
+pub(crate) fn legacy<T, const LEN: usize>(s: &str) -> Option<[u8; LEN]>
+where T: baid64::FromBaid64Str<LEN> + baid64::DisplayBaid64<LEN> {
+    T::from_baid64_str(s).ok().map(|id| id.to_baid64_payload())
+}
+
+/// Parses a legacy (version 0) baid64 string, if the `baid64` feature is enabled.
+#[cfg(not(feature = "baid64"))]
+pub(crate) fn legacy<T, const LEN: usize>(_: &str) -> Option<[u8; LEN]> { None }
+
+/// Implements serde for an identifier type wrapping [`amplify::Bytes32`].
+macro_rules! impl_serde_id {
+    ($ty:ty) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: serde::Serializer {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&$crate::serde_id::to_string(self.to_byte_array()))
+                } else {
+                    serde::Serialize::serialize(&self.to_byte_array(), serializer)
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where D: serde::Deserializer<'de> {
+                if deserializer.is_human_readable() {
+                    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+                    $crate::serde_id::from_str::<D, 32>(&s, $crate::serde_id::legacy::<Self, 32>)
+                        .map(Self::from)
+                } else {
+                    <[u8; 32] as serde::Deserialize>::deserialize(deserializer).map(Self::from)
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_serde_id;
+
+#[cfg(test)]
+mod test {
+    use serde::de::value::{Error, StrDeserializer};
+    use serde::de::IntoDeserializer;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{AuthToken, CellAddr, CodexId, ContractId, Opid};
+
+    fn de<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, Error> {
+        let deserializer: StrDeserializer<Error> = s.into_deserializer();
+        T::deserialize(deserializer)
+    }
+
+    fn v1(hex: String) -> String { format!("v1:{hex}") }
+
+    #[test]
+    fn hex() {
+        let hex = v1("17".repeat(32));
+        assert_eq!(to_string([0x17u8; 32]), hex);
+        assert_eq!(de::<Opid>(&hex).unwrap(), Opid::from([0x17; 32]));
+        assert_eq!(de::<CodexId>(&v1("a5".repeat(32))).unwrap(), CodexId::from([0xA5; 32]));
+        assert_eq!(de::<ContractId>(&v1("5A".repeat(32))).unwrap(), ContractId::from([0x5A; 32]));
+        assert_eq!(de::<AuthToken>(&v1("71".repeat(30))).unwrap(), AuthToken::from([0x71; 30]));
+        assert_eq!(
+            de::<CellAddr>(&format!("{hex}:5")).unwrap(),
+            CellAddr::new(Opid::from([0x17; 32]), 5)
+        );
+        assert!(de::<Opid>(&v1("17".repeat(31))).is_err());
+        assert!(de::<AuthToken>(&v1("71".repeat(32))).is_err());
+    }
+
+    #[test]
+    fn version_required() {
+        assert!(de::<Opid>(&"17".repeat(32)).is_err());
+        assert!(de::<Opid>(&format!("v2:{}", "17".repeat(32))).is_err());
+    }
+
+    #[cfg(feature = "baid64")]
+    #[test]
+    fn legacy_baid64() {
+        let opid = Opid::from([0x17; 32]);
+        assert_eq!(de::<Opid>(&opid.to_string()).unwrap(), opid);
+        let auth = AuthToken::from([0x71; 30]);
+        assert_eq!(de::<AuthToken>(&auth.to_string()).unwrap(), auth);
+    }
+
+    #[cfg(not(feature = "baid64"))]
+    #[test]
+    fn no_legacy() {
+        assert!(de::<Opid>("usop:invalid").is_err());
+    }
+}
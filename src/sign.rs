@@ -0,0 +1,268 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Signing payloads for air-gapped signers.
+//!
+//! A [`SignDoc`] summarizes an operation for a signer which doesn't have access to the contract
+//! state: it contains the ids of the operation and the contract, the verifier call id, the
+//! destroyed memory cells and human-relevant summaries of the created ones, and a hash binding the
+//! document to the full operation data. The signer displays the document and signs its
+//! [`SignDoc::sighash`]; the coordinator checks the document against the operation with
+//! [`SignDoc::verify`] before attaching the signatures as the input witnesses.
+
+use amplify::confinement::{SmallVec, U24, U32};
+use amplify::Bytes32;
+use commit_verify::{DigestExt, ReservedBytes};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{
+    AuthToken, CallId, CellAddr, ContractId, IdHash, Operation, Opid, RawEncoding, StateValue,
+    LIB_NAME_ULTRASONIC,
+};
+
+/// Tag of the hash binding a [`SignDoc`] to the full operation data.
+pub const SIGN_DOC_OPERATION_TAG: &str = "urn:ubideco:ultrasonic:sign-doc-operation#2025-06-01";
+
+/// Tag of the hash signed by the signers of a [`SignDoc`].
+pub const SIGN_DOC_TAG: &str = "urn:ubideco:ultrasonic:sign-doc#2025-06-01";
+
+/// Summary of a read-once memory cell created by the operation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct OutputSummary {
+    pub value: StateValue,
+    /// Token of authority controlling the created cell.
+    pub auth: AuthToken,
+    /// Whether destroying the cell requires satisfying a lock script.
+    pub locked: bool,
+}
+
+/// Summary of an immutable memory cell created by the operation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct WriteSummary {
+    pub value: StateValue,
+    /// Encoding hint of the raw data, if the raw data are present.
+    pub raw_encoding: Option<RawEncoding>,
+    /// Length of the raw data, in bytes.
+    pub raw_len: u16,
+}
+
+/// Compact signing payload for air-gapped signers.
+///
+/// Since the input witnesses are committed to by the operation id, the document describes the
+/// operation with the witnesses redacted (see [`Operation::redact_witnesses`]); thus, the same
+/// document is produced for the operation before and after the signatures are attached.
+///
+/// # Example
+///
+/// ```
+/// use amplify::num::u256;
+/// use ultrasonic::{CallId, CellAddr, ContractId, OperationBuilder, SignDoc, StateValue};
+///
+/// let spent = CellAddr::new([0xCD; 32].into(), 0);
+/// let builder = OperationBuilder::new(ContractId::from([0xAB; 32]), CallId::from(1u16))
+///     .destroy(spent, StateValue::None);
+/// let doc = SignDoc::new(&builder.clone().finish().unwrap());
+/// // the document is transferred to the signer, which signs `doc.sighash()`
+/// let _sighash = doc.sighash();
+///
+/// let signed = builder
+///     .witness(spent, StateValue::from([u256::ONE]))
+///     .finish()
+///     .unwrap();
+/// assert_eq!(doc.verify(&signed), Ok(()));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct SignDoc {
+    /// Version of the document format; must be zero.
+    pub version: ReservedBytes<1>,
+    pub contract_id: ContractId,
+    /// Id of the operation with the redacted witnesses.
+    pub opid: Opid,
+    pub call_id: CallId,
+    /// Memory cells destroyed by the operation.
+    pub destroying: SmallVec<CellAddr>,
+    /// Memory cells read by the operation.
+    pub reading: SmallVec<CellAddr>,
+    pub outputs: SmallVec<OutputSummary>,
+    pub writes: SmallVec<WriteSummary>,
+    /// Hash of the strict-serialized operation with the redacted witnesses.
+    pub operation_hash: Bytes32,
+}
+
+impl StrictSerialize for SignDoc {}
+impl StrictDeserialize for SignDoc {}
+
+impl SignDoc {
+    /// Constructs the signing document for the operation.
+    pub fn new(operation: &Operation) -> Self {
+        let redacted = operation.redact_witnesses();
+        SignDoc {
+            version: default!(),
+            contract_id: redacted.contract_id,
+            opid: redacted.opid(),
+            call_id: redacted.call_id,
+            destroying: SmallVec::from_iter_checked(redacted.destroying.iter().map(|i| i.addr)),
+            reading: redacted.reading.clone(),
+            outputs: SmallVec::from_iter_checked(redacted.destructible.iter().map(|cell| {
+                OutputSummary {
+                    value: cell.data,
+                    auth: cell.auth,
+                    locked: cell.lock.is_locked(),
+                }
+            })),
+            writes: SmallVec::from_iter_checked(redacted.immutable.iter().map(|data| {
+                WriteSummary {
+                    value: data.value,
+                    raw_encoding: data.raw_encoding(),
+                    raw_len: data
+                        .raw
+                        .as_ref()
+                        .map(|raw| raw.len() as u16)
+                        .unwrap_or_default(),
+                }
+            })),
+            operation_hash: operation_hash(&redacted),
+        }
+    }
+
+    /// Returns the hash signed by the signers of the document.
+    pub fn sighash(&self) -> Bytes32 {
+        let data = self
+            .to_strict_serialized::<U32>()
+            .expect("sign document size is limited by its confinements");
+        Bytes32::from(IdHash::digest_tagged(SIGN_DOC_TAG, data.as_slice()))
+    }
+
+    /// Checks that the document describes the operation.
+    ///
+    /// The operation may have its input witnesses set or redacted.
+    pub fn verify(&self, operation: &Operation) -> Result<(), SignDocError> {
+        if self.version != default!() {
+            return Err(SignDocError::Version);
+        }
+        let expected = SignDoc::new(operation);
+        let field = if self.contract_id != expected.contract_id {
+            "contract_id"
+        } else if self.opid != expected.opid {
+            "opid"
+        } else if self.call_id != expected.call_id {
+            "call_id"
+        } else if self.destroying != expected.destroying {
+            "destroying"
+        } else if self.reading != expected.reading {
+            "reading"
+        } else if self.outputs != expected.outputs {
+            "outputs"
+        } else if self.writes != expected.writes {
+            "writes"
+        } else if self.operation_hash != expected.operation_hash {
+            "operation_hash"
+        } else {
+            return Ok(());
+        };
+        Err(SignDocError::Mismatch(field))
+    }
+}
+
+fn operation_hash(operation: &Operation) -> Bytes32 {
+    let data = operation
+        .to_strict_serialized::<U24>()
+        .expect("operation size is always less than 2^24 bytes");
+    Bytes32::from(IdHash::digest_tagged(SIGN_DOC_OPERATION_TAG, data.as_slice()))
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SignDocError {
+    /// signing document has an unknown version.
+    Version,
+
+    /// signing document doesn't match the operation in `{0}`.
+    Mismatch(&'static str),
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::num::u256;
+
+    use super::*;
+    use crate::{OperationBuilder, RawData, StateCell, StateData};
+
+    fn operation() -> Operation {
+        let spent = CellAddr::new(Opid::from([1u8; 32]), 0);
+        OperationBuilder::new(ContractId::from([0xAB; 32]), CallId::from(2u16))
+            .destroy(spent, StateValue::None)
+            .create(StateCell {
+                data: StateValue::from([u256::from(100u8)]),
+                auth: AuthToken::from([0x11; 30]),
+                lock: none!(),
+            })
+            .write(StateData::with_encoded_raw(
+                StateValue::None,
+                RawData::from(amplify::confinement::SmallBlob::from_checked(b"memo".to_vec())),
+                RawEncoding::Utf8,
+            ))
+            .finish()
+            .unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let op = operation();
+        let doc = SignDoc::new(&op);
+        assert_eq!(doc.opid, op.opid());
+        assert_eq!(doc.writes[0].raw_len, 4);
+        assert_eq!(doc.writes[0].raw_encoding, Some(RawEncoding::Utf8));
+
+        let data = doc.to_strict_serialized::<U32>().unwrap();
+        let decoded = SignDoc::from_strict_serialized::<U32>(data).unwrap();
+        assert_eq!(decoded, doc);
+        assert_eq!(decoded.sighash(), doc.sighash());
+        assert_eq!(decoded.verify(&op), Ok(()));
+    }
+
+    #[test]
+    fn mismatch() {
+        let op = operation();
+        let mut doc = SignDoc::new(&op);
+        for output in &mut doc.outputs {
+            output.value = StateValue::from([u256::from(200u8)]);
+        }
+        assert_eq!(doc.verify(&op), Err(SignDocError::Mismatch("outputs")));
+
+        let mut other = op.clone();
+        for data in &mut other.immutable {
+            data.raw = None;
+        }
+        assert_eq!(SignDoc::new(&op).verify(&other), Err(SignDocError::Mismatch("opid")));
+    }
+}
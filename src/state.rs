@@ -21,25 +21,26 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use core::fmt::{self, Display, Formatter};
 use core::str::FromStr;
+use std::io;
 
 use aluvm::{fe256, LibSite};
-use amplify::confinement::SmallBlob;
+use amplify::confinement::{self, SmallBlob, U16};
 use amplify::hex::FromHex;
 use amplify::num::u256;
-use amplify::{hex, Bytes};
+use amplify::{hex, Bytes, Bytes32};
 use commit_verify::{CommitEncode, CommitEngine, MerkleHash, StrictHash};
+use strict_encoding::{
+    DecodeError, StreamReader, StrictDecode, StrictDeserialize, StrictEncode, StrictReader,
+    StrictSerialize, StrictWriter, TypedRead, TypedWrite,
+};
 
 use crate::LIB_NAME_ULTRASONIC;
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, From)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
-#[cfg_attr(
-    all(feature = "serde", not(feature = "baid64")),
-    derive(Serialize, Deserialize),
-    serde(transparent)
-)]
 pub struct AuthToken(#[from] fe256);
 
 impl From<[u8; 30]> for AuthToken {
@@ -72,6 +73,173 @@ impl AuthToken {
         let bytes = self.to_byte_array();
         Bytes::from(bytes)
     }
+
+    /// Hash tag used to derive tokens of authority from public keys with [`Self::from_pubkey`].
+    pub const PUBKEY_TAG: &'static str = "urn:ubideco:ultrasonic:pubkey#2025-06-01";
+
+    /// Derives token of authority from a serialized public key, which must be either a 32-byte
+    /// x-only key or a 33-byte compressed key.
+    ///
+    /// The token is a tagged hash (see [`Self::PUBKEY_TAG`]) of a single byte with the key length
+    /// followed by the key itself, truncated to 30 bytes. Thus, the x-only and compressed forms of
+    /// the same key produce different tokens.
+    ///
+    /// No lock script template is provided for the derived tokens. The zk-AluVM GFA instruction
+    /// set has no elliptic curve operations, and a Schnorr-like check over the field elements
+    /// alone would not be sound, since discrete logarithms in the additive group of a prime field
+    /// are trivial to compute. Ownership must be proven by the layer checking operation witnesses,
+    /// for instance by a [`crate::seals::SealWitness`].
+    pub fn from_pubkey(pubkey: &[u8]) -> Result<Self, PubkeyLenError> {
+        let len = pubkey.len();
+        if len != 32 && len != 33 {
+            return Err(PubkeyLenError(len));
+        }
+        let mut data = Vec::with_capacity(len + 1);
+        data.push(len as u8);
+        data.extend_from_slice(pubkey);
+        Ok(crate::seals::seal_auth_token(Self::PUBKEY_TAG, &data))
+    }
+}
+
+/// public key must be either 32 or 33 bytes long, while {0} bytes were provided.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct PubkeyLenError(pub usize);
+
+/// Encoding used to render a token of authority with [`AuthToken::display`] and to parse it with
+/// [`AuthToken::parse_with`].
+///
+/// Allows user interfaces to keep tokens consistent with their existing address formats. The
+/// default is the baid64 encoding used by the [`Display`] implementation (or hex if the `baid64`
+/// feature is not enabled).
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub enum DisplayPolicy {
+    /// Chunked baid64 with an embedded checksum, as produced by [`Display`].
+    #[cfg(feature = "baid64")]
+    #[default]
+    Baid64,
+
+    /// Lowercase hex of the 30 token bytes.
+    #[cfg_attr(not(feature = "baid64"), default)]
+    Hex,
+
+    /// Bech32m with `auth` human-readable part.
+    #[cfg(feature = "bech32")]
+    Bech32m,
+
+    /// Hex truncated to `head` leading and `tail` trailing digits, separated with an ellipsis.
+    ///
+    /// The truncated form is for display purposes only and can't be parsed.
+    Truncated { head: u8, tail: u8 },
+}
+
+/// Token of authority rendered with a specific [`DisplayPolicy`], returned by
+/// [`AuthToken::display`].
+#[derive(Copy, Clone, Debug)]
+pub struct AuthTokenDisplay {
+    token: AuthToken,
+    policy: DisplayPolicy,
+}
+
+impl Display for AuthTokenDisplay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bytes = self.token.to_byte_array();
+        match self.policy {
+            #[cfg(feature = "baid64")]
+            DisplayPolicy::Baid64 => Display::fmt(&self.token, f),
+            DisplayPolicy::Hex => f.write_str(&hex::ToHex::to_hex(&bytes[..])),
+            #[cfg(feature = "bech32")]
+            DisplayPolicy::Bech32m => {
+                let hrp = bech32::Hrp::parse_unchecked(AuthToken::BECH32_HRP);
+                bech32::encode_to_fmt::<bech32::Bech32m, _>(f, hrp, &bytes).map_err(|_| fmt::Error)
+            }
+            DisplayPolicy::Truncated { head, tail } => {
+                let hex = hex::ToHex::to_hex(&bytes[..]);
+                let (head, tail) = (head as usize, tail as usize);
+                if head + tail >= hex.len() {
+                    return f.write_str(&hex);
+                }
+                write!(f, "{}…{}", &hex[..head], &hex[hex.len() - tail..])
+            }
+        }
+    }
+}
+
+impl AuthToken {
+    /// Human-readable part of the bech32m encoding of tokens of authority.
+    pub const BECH32_HRP: &'static str = "auth";
+
+    /// Returns an object rendering the token with the provided [`DisplayPolicy`].
+    pub fn display(&self, policy: DisplayPolicy) -> AuthTokenDisplay {
+        AuthTokenDisplay { token: *self, policy }
+    }
+
+    /// Parses a token rendered with the provided [`DisplayPolicy`].
+    ///
+    /// Parsing is strict: the string must exactly match the form produced by [`Self::display`],
+    /// without any whitespace or case changes.
+    ///
+    /// # Errors
+    ///
+    /// Errors for [`DisplayPolicy::Truncated`] policy, and if the string is not a valid token
+    /// encoding under the policy.
+    pub fn parse_with(s: &str, policy: DisplayPolicy) -> Result<Self, AuthTokenParseError> {
+        match policy {
+            #[cfg(feature = "baid64")]
+            DisplayPolicy::Baid64 => s.parse().map_err(|err: baid64::Baid64ParseError| {
+                AuthTokenParseError::Baid64(err.to_string())
+            }),
+            DisplayPolicy::Hex => {
+                if s.len() != 60 || !s.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f')) {
+                    return Err(AuthTokenParseError::Hex);
+                }
+                let data = Vec::<u8>::from_hex(s).map_err(|_| AuthTokenParseError::Hex)?;
+                let bytes = <[u8; 30]>::try_from(data.as_slice())
+                    .map_err(|_| AuthTokenParseError::Len(data.len()))?;
+                Ok(Self::from_byte_array(bytes))
+            }
+            #[cfg(feature = "bech32")]
+            DisplayPolicy::Bech32m => {
+                use bech32::primitives::decode::CheckedHrpstring;
+
+                let checked = CheckedHrpstring::new::<bech32::Bech32m>(s)
+                    .map_err(|err| AuthTokenParseError::Bech32(err.to_string()))?;
+                if checked.hrp().as_str() != Self::BECH32_HRP || s.to_lowercase() != s {
+                    return Err(AuthTokenParseError::Bech32Hrp);
+                }
+                let data = checked.byte_iter().collect::<Vec<_>>();
+                let bytes = <[u8; 30]>::try_from(data.as_slice())
+                    .map_err(|_| AuthTokenParseError::Len(data.len()))?;
+                Ok(Self::from_byte_array(bytes))
+            }
+            DisplayPolicy::Truncated { .. } => Err(AuthTokenParseError::Truncated),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AuthTokenParseError {
+    /// truncated token of authority can't be parsed.
+    Truncated,
+
+    /// token of authority must be exactly 60 lowercase hex digits.
+    Hex,
+
+    /// invalid baid64 encoding of a token of authority: {0}
+    #[cfg(feature = "baid64")]
+    Baid64(String),
+
+    /// invalid bech32m encoding of a token of authority: {0}
+    #[cfg(feature = "bech32")]
+    Bech32(String),
+
+    /// bech32m token of authority must be lowercase and have `auth` human-readable part.
+    #[cfg(feature = "bech32")]
+    Bech32Hrp,
+
+    /// token of authority must be 30 bytes long, while {0} bytes were provided.
+    Len(usize),
 }
 
 #[cfg(feature = "baid64")]
@@ -82,6 +250,7 @@ mod _baid64 {
     use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
 
     use super::*;
+    use crate::display::parse_id;
 
     impl DisplayBaid64<30> for AuthToken {
         const HRI: &'static str = "auth";
@@ -96,14 +265,20 @@ mod _baid64 {
     impl FromBaid64Str<30> for AuthToken {}
     impl FromStr for AuthToken {
         type Err = Baid64ParseError;
-        fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+        fn from_str(s: &str) -> Result<Self, Self::Err> { parse_id(s) }
     }
     impl Display for AuthToken {
         fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
     }
 }
 
+/// Tuple of up to four field elements kept in a memory cell.
+///
+/// The commitment id of the value (a [`StrictHash`]) is used by operations to specify the
+/// immutable memory cell content they expect to read (see [`crate::ReadIntent`]).
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = StrictHash)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
 #[cfg_attr(
@@ -162,6 +337,22 @@ impl StateValue {
         }
     }
 
+    /// Iterates over the field elements of the value.
+    pub fn elements(&self) -> impl Iterator<Item = fe256> {
+        let value = *self;
+        (0u8..4).map_while(move |pos| value.get(pos))
+    }
+
+    /// Checks that all field elements of the value are canonical, i.e. less than the field
+    /// `order`.
+    ///
+    /// Field elements are serialized as 256-bit little-endian integers; a value which is not less
+    /// than the field order represents the same element as its reduction, making such encodings
+    /// malleable.
+    pub fn is_canonical(&self, order: u256) -> bool {
+        self.elements().all(|el| el.to_u256() < order)
+    }
+
     pub fn get(&self, pos: u8) -> Option<fe256> {
         match (*self, pos) {
             (Self::Single { first }, 0)
@@ -182,6 +373,126 @@ impl StateValue {
     }
 }
 
+/// Formats a field element as a short hex string, abbreviating long values to their first and
+/// last four hex digits.
+fn fmt_short(el: fe256, f: &mut Formatter) -> fmt::Result {
+    let hex = format!("{:02x}", el.to_u256());
+    if hex.len() > 16 {
+        write!(f, "0x{}…{}", &hex[..4], &hex[hex.len() - 4..])
+    } else {
+        write!(f, "0x{hex}")
+    }
+}
+
+/// Displays the value as its lowercase variant name followed by the field elements in short hex,
+/// for instance `double(0x01, 0xdead…beef)`.
+impl Display for StateValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StateValue::None => return f.write_str("none"),
+            StateValue::Single { .. } => "single",
+            StateValue::Double { .. } => "double",
+            StateValue::Three { .. } => "three",
+            StateValue::Four { .. } => "four",
+        };
+        write!(f, "{name}(")?;
+        for (pos, el) in self.elements().enumerate() {
+            if pos > 0 {
+                f.write_str(", ")?;
+            }
+            fmt_short(el, f)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Lock of a read-once memory cell, defining conditions under which the cell can be destroyed.
+///
+/// The strict encoding of [`CellLock::None`] and [`CellLock::Single`] variants matches the one of
+/// `Option<LibSite>`, which was used for the cell locks before threshold locks were introduced;
+/// thus, ids of the existing operations are not affected.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub enum CellLock {
+    /// The cell can be destroyed by anyone.
+    #[default]
+    #[strict_type(tag = 0x00, dumb)]
+    None,
+
+    /// The cell can be destroyed by satisfying the lock script.
+    #[strict_type(tag = 0x01)]
+    Single(LibSite),
+
+    /// The cell can be destroyed by satisfying at least `threshold` of the lock scripts.
+    ///
+    /// Each of the scripts receives a single field element from the input witness, matching the
+    /// script position in [`CellLock::sites`], as the first witness element; the other witness
+    /// registers are left empty. Thus, scripts requiring multi-element witnesses (like signature
+    /// checks) can't be used in threshold locks.
+    ///
+    /// The `fourth` script may be present only if the `third` script is present, such that the
+    /// script positions match the declared slots (see [`CellLock::is_valid`]).
+    #[strict_type(tag = 0x02)]
+    Threshold {
+        threshold: u8,
+        first: LibSite,
+        second: LibSite,
+        third: Option<LibSite>,
+        fourth: Option<LibSite>,
+    },
+}
+
+impl From<LibSite> for CellLock {
+    fn from(site: LibSite) -> Self { CellLock::Single(site) }
+}
+
+impl From<Option<LibSite>> for CellLock {
+    fn from(site: Option<LibSite>) -> Self { site.map(CellLock::Single).unwrap_or_default() }
+}
+
+impl CellLock {
+    /// Maximal number of lock scripts in a threshold lock.
+    pub const MAX_SITES: usize = 4;
+
+    pub fn is_locked(&self) -> bool { !matches!(self, CellLock::None) }
+
+    /// Returns the number of lock scripts which must be satisfied to destroy the cell.
+    pub fn threshold(&self) -> u8 {
+        match *self {
+            CellLock::None => 0,
+            CellLock::Single(_) => 1,
+            CellLock::Threshold { threshold, .. } => threshold,
+        }
+    }
+
+    /// Iterates over the lock script sites.
+    pub fn sites(&self) -> impl Iterator<Item = LibSite> {
+        let sites = match *self {
+            CellLock::None => [None; 4],
+            CellLock::Single(site) => [Some(site), None, None, None],
+            CellLock::Threshold { first, second, third, fourth, .. } => {
+                [Some(first), Some(second), third, fourth]
+            }
+        };
+        sites.into_iter().flatten()
+    }
+
+    /// Checks that the lock can be satisfied, i.e. its threshold is non-zero and doesn't exceed
+    /// the number of lock scripts, and that the `fourth` script of a threshold lock is not present
+    /// without the `third` one.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            CellLock::None | CellLock::Single(_) => true,
+            CellLock::Threshold { third: None, fourth: Some(_), .. } => false,
+            CellLock::Threshold { threshold, .. } => {
+                *threshold > 0 && *threshold as usize <= self.sites().count()
+            }
+        }
+    }
+}
+
 /// Read-once access-controlled memory cell.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[derive(CommitEncode)]
@@ -193,7 +504,31 @@ pub struct StateCell {
     pub data: StateValue,
     /// Token of authority
     pub auth: AuthToken,
-    pub lock: Option<LibSite>,
+    pub lock: CellLock,
+}
+
+impl StrictSerialize for StateCell {}
+impl StrictDeserialize for StateCell {}
+
+impl StateCell {
+    /// Checks that the cell data and the token of authority are canonical field elements under
+    /// the field `order`.
+    pub fn is_canonical(&self, order: u256) -> bool {
+        self.data.is_canonical(order) && self.auth.to_fe256().to_u256() < order
+    }
+}
+
+/// Displays the cell data followed by the short form of the token of authority and, for the
+/// locked cells, the lock threshold, for instance `single(0x64) auth 0x1f2e…9a8b lock 2/3`.
+impl Display for StateCell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} auth ", self.data)?;
+        fmt_short(self.auth.to_fe256(), f)?;
+        if self.lock.is_locked() {
+            write!(f, " lock {}/{}", self.lock.threshold(), self.lock.sites().count())?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, Debug, Display, From)]
@@ -214,15 +549,237 @@ impl FromStr for RawData {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+impl RawData {
+    /// Interprets raw data as a UTF-8 string, if it is a valid one.
+    pub fn as_utf8(&self) -> Option<&str> { core::str::from_utf8(self.as_slice()).ok() }
+
+    /// Constructs raw data from a strict-encoded value.
+    ///
+    /// Errors if the encoded value doesn't fit the maximal size of raw data.
+    pub fn encode_strict<T: StrictEncode>(value: &T) -> Result<Self, RawStrictError> {
+        let writer = StrictWriter::in_memory::<U16>();
+        let writer = value
+            .strict_encode(writer)
+            .map_err(|_| RawStrictError::TooLarge)?;
+        let data = writer.unbox().unconfine();
+        SmallBlob::try_from(data)
+            .map(Self)
+            .map_err(|_| RawStrictError::TooLarge)
+    }
+
+    /// Decodes a strict-encoded value from the raw data.
+    ///
+    /// The raw data must contain exactly the canonical encoding of the value, without any
+    /// trailing bytes.
+    pub fn decode_strict<T: StrictDecode + StrictEncode>(&self) -> Result<T, RawStrictError> {
+        let mut reader = StrictReader::with(StreamReader::cursor::<U16>(self.as_slice()));
+        let value = T::strict_decode(&mut reader)?;
+        if Self::encode_strict(&value)? != *self {
+            return Err(RawStrictError::NonCanonical);
+        }
+        Ok(value)
+    }
+}
+
+/// Errors of embedding strict-encoded values into [`RawData`].
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum RawStrictError {
+    /// strict-encoded value exceeds the maximal size of raw data.
+    TooLarge,
+
+    /// raw data contain bytes after the strict-encoded value or encode it non-canonically.
+    NonCanonical,
+
+    /// invalid strict-encoded raw data: {0}
+    #[from]
+    Decode(DecodeError),
+}
+
+/// Hint on how the [`RawData`] payload is encoded, allowing explorers and wallets to render it
+/// without protocol-specific knowledge.
+///
+/// The hint is committed (see [`StateData`]), but is not verified to match the actual data: it is
+/// up to the applications to decide how to handle data which can't be decoded according to the
+/// hint.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Default)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum RawEncoding {
+    /// Opaque binary data.
+    #[default]
+    #[strict_type(dumb)]
+    Binary = 0,
+
+    /// UTF-8 encoded string.
+    Utf8 = 1,
+
+    /// CBOR-encoded data.
+    Cbor = 2,
+
+    /// Data encoded with strict encoding.
+    Strict = 3,
+
+    /// Encrypted data in the [`EncryptedRawData`] envelope.
+    Encrypted = 4,
+}
+
+/// Cipher used to encrypt and decrypt [`EncryptedRawData`] for a specific recipient key.
+///
+/// The library doesn't fix a specific encryption scheme; applications implement the trait with
+/// their key agreement and AEAD primitives.
+pub trait RawCipher {
+    type Error;
+
+    /// Returns commitment to the key used by the cipher.
+    fn key_commitment(&self) -> Bytes32;
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Self::Error>;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Envelope of confidential raw data of an immutable memory cell.
+///
+/// The envelope is carried in [`StateData::raw`] with [`RawEncoding::Encrypted`] hint and is
+/// serialized as the key commitment followed by the ciphertext, such that the operation commits
+/// to both of them.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct EncryptedRawData {
+    /// Commitment to the key the data are encrypted with, allowing recipients to detect the
+    /// envelopes they are able to decrypt.
+    pub key_commitment: Bytes32,
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedRawData {
+    /// Encrypts the `plaintext` with the `cipher`.
+    pub fn encrypt<C: RawCipher>(cipher: &C, plaintext: &[u8]) -> Result<Self, C::Error> {
+        Ok(Self {
+            key_commitment: cipher.key_commitment(),
+            ciphertext: cipher.encrypt(plaintext)?,
+        })
+    }
+
+    /// Detects whether the data are encrypted with the key of the `cipher`.
+    pub fn is_for(&self, cipher: &impl RawCipher) -> bool {
+        self.key_commitment == cipher.key_commitment()
+    }
+
+    /// Decrypts the data with the `cipher`, returning `None` if the data are encrypted with a
+    /// different key.
+    pub fn decrypt<C: RawCipher>(&self, cipher: &C) -> Option<Result<Vec<u8>, C::Error>> {
+        if !self.is_for(cipher) {
+            return None;
+        }
+        Some(cipher.decrypt(&self.ciphertext))
+    }
+
+    /// Serializes the envelope into raw data.
+    ///
+    /// Errors if the envelope doesn't fit the maximal size of raw data.
+    pub fn to_raw(&self) -> Result<RawData, confinement::Error> {
+        let mut buf = Vec::with_capacity(32 + self.ciphertext.len());
+        buf.extend_from_slice(self.key_commitment.as_slice());
+        buf.extend_from_slice(&self.ciphertext);
+        SmallBlob::try_from(buf).map(RawData::from)
+    }
+
+    /// Parses the envelope from raw data, returning `None` if the data are too short to contain
+    /// the key commitment.
+    pub fn from_raw(raw: &RawData) -> Option<Self> {
+        let data = raw.as_slice();
+        if data.len() < 32 {
+            return None;
+        }
+        let (key, ciphertext) = data.split_at(32);
+        Some(Self {
+            key_commitment: Bytes32::copy_from_slice(key).ok()?,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+/// Data of an immutable memory cell.
+///
+/// The strict encoding is versioned: data without an encoding hint are encoded exactly as before
+/// the hint was introduced, while the hinted raw data are distinguished by a separate tag.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb)]
 #[strict_type(lib = LIB_NAME_ULTRASONIC)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub struct StateData {
     pub value: StateValue,
+    /// Optional hint on the encoding of the [`Self::raw`] data.
+    ///
+    /// The hint is meaningful only when the raw data are present. [`RawEncoding::Binary`] is
+    /// equivalent to the absent hint: such data are encoded and committed to as the data without
+    /// a hint (see [`StateData::canonical_hint`]), and decoding rejects an explicit binary hint.
+    pub hint: Option<RawEncoding>,
     pub raw: Option<RawData>,
 }
 
+impl StrictSerialize for StateData {}
+impl StrictDeserialize for StateData {}
+
+/// Wire format of [`StateData`].
+mod wire {
+    use super::*;
+
+    /// Raw data with an optional encoding hint.
+    ///
+    /// Tags `0x00` and `0x01` match the encoding of `Option<RawData>`, used by [`StateData`]
+    /// before the hint was introduced.
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC, tags = custom)]
+    pub(super) enum RawPayload {
+        #[strict_type(tag = 0x00, dumb)]
+        None,
+        #[strict_type(tag = 0x01)]
+        Raw(RawData),
+        #[strict_type(tag = 0x02)]
+        Hinted(RawEncoding, RawData),
+    }
+
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    pub(super) struct StateData {
+        pub value: StateValue,
+        pub raw: RawPayload,
+    }
+}
+
+impl StrictEncode for StateData {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        let raw = match (&self.raw, self.canonical_hint()) {
+            (None, _) => wire::RawPayload::None,
+            (Some(raw), None) => wire::RawPayload::Raw(raw.clone()),
+            (Some(raw), Some(hint)) => wire::RawPayload::Hinted(hint, raw.clone()),
+        };
+        wire::StateData { value: self.value, raw }.strict_encode(writer)
+    }
+}
+
+impl StrictDecode for StateData {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let data = wire::StateData::strict_decode(reader)?;
+        let (hint, raw) = match data.raw {
+            wire::RawPayload::None => (None, None),
+            wire::RawPayload::Raw(raw) => (None, Some(raw)),
+            wire::RawPayload::Hinted(RawEncoding::Binary, _) => {
+                return Err(DecodeError::DataIntegrityError(s!(
+                    "state data with non-canonical binary encoding hint"
+                )));
+            }
+            wire::RawPayload::Hinted(hint, raw) => (Some(hint), Some(raw)),
+        };
+        Ok(Self { value: data.value, hint, raw })
+    }
+}
+
 impl CommitEncode for StateData {
     type CommitmentId = MerkleHash;
 
@@ -230,23 +787,127 @@ impl CommitEncode for StateData {
         e.commit_to_serialized(&self.value);
         match &self.raw {
             None => e.commit_to_option(&Option::<RawData>::None),
-            Some(raw) => e.commit_to_hash(raw),
+            Some(raw) => {
+                // The hint precedes the raw data. Data without a hint commit in the same way as
+                // before the hint was introduced.
+                if let Some(hint) = self.canonical_hint() {
+                    e.commit_to_serialized(&hint);
+                }
+                e.commit_to_hash(raw)
+            }
         }
     }
 }
 
-#[cfg(all(feature = "serde", feature = "baid64"))]
+/// Displays the state value followed by the encoding and the length of the raw data, if present,
+/// for instance `single(0x01) utf8(12 bytes)`.
+impl Display for StateData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.value, f)?;
+        if let (Some(raw), Some(encoding)) = (&self.raw, self.raw_encoding()) {
+            write!(f, " {encoding}({} bytes)", raw.as_slice().len())?;
+        }
+        Ok(())
+    }
+}
+
+impl StateData {
+    /// Constructs state data without raw data.
+    pub fn new(value: StateValue) -> Self { Self { value, hint: None, raw: None } }
+
+    /// Constructs state data with raw data of unspecified encoding.
+    pub fn with_raw(value: StateValue, raw: impl Into<RawData>) -> Self {
+        Self { value, hint: None, raw: Some(raw.into()) }
+    }
+
+    /// Constructs state data with raw data and the hint of its encoding.
+    ///
+    /// [`RawEncoding::Binary`] is stored as the absent hint.
+    pub fn with_encoded_raw(
+        value: StateValue,
+        raw: impl Into<RawData>,
+        encoding: RawEncoding,
+    ) -> Self {
+        let hint = Some(encoding).filter(|encoding| *encoding != RawEncoding::Binary);
+        Self { value, hint, raw: Some(raw.into()) }
+    }
+
+    /// Returns the encoding hint in its canonical form, which is encoded and committed to.
+    ///
+    /// The hint is absent if there are no raw data or if the hint is [`RawEncoding::Binary`].
+    pub fn canonical_hint(&self) -> Option<RawEncoding> {
+        self.raw.as_ref()?;
+        self.hint.filter(|hint| *hint != RawEncoding::Binary)
+    }
+
+    /// Checks that the state value consists of canonical field elements under the field `order`.
+    pub fn is_canonical(&self, order: u256) -> bool { self.value.is_canonical(order) }
+
+    /// Returns encoding of the raw data, if the raw data are present.
+    ///
+    /// Raw data without an explicit hint are reported as [`RawEncoding::Binary`].
+    pub fn raw_encoding(&self) -> Option<RawEncoding> {
+        self.raw.as_ref().map(|_| self.hint.unwrap_or_default())
+    }
+
+    /// Returns raw data as a string, if they are hinted to be UTF-8 encoded and are a valid
+    /// UTF-8 string.
+    pub fn raw_utf8(&self) -> Option<&str> {
+        if self.hint != Some(RawEncoding::Utf8) {
+            return None;
+        }
+        self.raw.as_ref()?.as_utf8()
+    }
+
+    /// Constructs state data with the encrypted raw data envelope.
+    ///
+    /// Errors if the envelope doesn't fit the maximal size of raw data.
+    pub fn with_encrypted(
+        value: StateValue,
+        encrypted: &EncryptedRawData,
+    ) -> Result<Self, confinement::Error> {
+        Ok(Self::with_encoded_raw(value, encrypted.to_raw()?, RawEncoding::Encrypted))
+    }
+
+    /// Constructs state data with the strict-encoded `data` as the raw data, hinted with
+    /// [`RawEncoding::Strict`].
+    pub fn with_strict_raw<T: StrictEncode>(
+        value: StateValue,
+        data: &T,
+    ) -> Result<Self, RawStrictError> {
+        Ok(Self::with_encoded_raw(value, RawData::encode_strict(data)?, RawEncoding::Strict))
+    }
+
+    /// Decodes the raw data as a strict-encoded value, if the raw data are hinted to be strict
+    /// encoded.
+    pub fn raw_strict<T: StrictDecode + StrictEncode>(&self) -> Option<Result<T, RawStrictError>> {
+        if self.hint != Some(RawEncoding::Strict) {
+            return None;
+        }
+        Some(self.raw.as_ref()?.decode_strict())
+    }
+
+    /// Returns the encrypted raw data envelope, if the raw data are hinted to be encrypted.
+    pub fn encrypted(&self) -> Option<EncryptedRawData> {
+        if self.hint != Some(RawEncoding::Encrypted) {
+            return None;
+        }
+        EncryptedRawData::from_raw(self.raw.as_ref()?)
+    }
+}
+
+#[cfg(feature = "serde")]
 mod _serde {
-    use serde::de::Error;
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     use super::*;
+    use crate::serde_id;
 
     impl Serialize for AuthToken {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer {
             if serializer.is_human_readable() {
-                serializer.serialize_str(&self.to_string())
+                serializer.serialize_str(&serde_id::to_string(self.to_byte_array()))
             } else {
                 self.0.serialize(serializer)
             }
@@ -258,7 +919,7 @@ mod _serde {
         where D: Deserializer<'de> {
             if deserializer.is_human_readable() {
                 let s = String::deserialize(deserializer)?;
-                s.parse().map_err(D::Error::custom)
+                serde_id::from_str::<D, 30>(&s, serde_id::legacy::<Self, 30>).map(Self::from)
             } else {
                 fe256::deserialize(deserializer).map(Self)
             }
@@ -298,9 +959,189 @@ mod _serde2 {
 
 #[cfg(test)]
 mod test {
-    #[cfg(feature = "baid64")]
+    use amplify::confinement::{Confined, U24};
+    use commit_verify::CommitId;
+    use strict_encoding::DeserializeError;
+
     use super::*;
 
+    /// Layout of [`StateData`] before the encoding hint was introduced.
+    #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+    #[strict_type(lib = LIB_NAME_ULTRASONIC)]
+    struct LegacyStateData {
+        value: StateValue,
+        raw: Option<RawData>,
+    }
+    impl StrictSerialize for LegacyStateData {}
+
+    fn serialize(data: &impl StrictSerialize) -> Vec<u8> {
+        data.to_strict_serialized::<U24>().unwrap().release()
+    }
+
+    fn deserialize(bytes: Vec<u8>) -> Result<StateData, DeserializeError> {
+        StateData::from_strict_serialized::<U24>(Confined::from_checked(bytes))
+    }
+
+    #[test]
+    fn state_data_legacy_encoding() {
+        let value = StateValue::from([u256::from(7u8)]);
+        let raw = RawData::from(SmallBlob::from_checked(b"legacy".to_vec()));
+        for (data, legacy) in [
+            (StateData::new(value), LegacyStateData { value, raw: None }),
+            (StateData::with_raw(value, raw.clone()), LegacyStateData { value, raw: Some(raw) }),
+        ] {
+            let bytes = serialize(&legacy);
+            assert_eq!(serialize(&data), bytes);
+            assert_eq!(deserialize(bytes).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn state_data_hint() {
+        let value = StateValue::from([u256::from(7u8)]);
+        let raw = SmallBlob::from_checked(b"hinted".to_vec());
+
+        let hinted = StateData::with_encoded_raw(value, raw.clone(), RawEncoding::Utf8);
+        let plain = StateData::with_raw(value, raw.clone());
+        assert_ne!(serialize(&hinted), serialize(&plain));
+        assert_ne!(hinted.commit_id(), plain.commit_id());
+        assert_eq!(deserialize(serialize(&hinted)).unwrap(), hinted);
+
+        // Binary hint is the same as no hint
+        let binary = StateData::with_encoded_raw(value, raw.clone(), RawEncoding::Binary);
+        assert_eq!(binary, plain);
+        let explicit = StateData { hint: Some(RawEncoding::Binary), ..plain.clone() };
+        assert_eq!(explicit.canonical_hint(), None);
+        assert_eq!(serialize(&explicit), serialize(&plain));
+        assert_eq!(explicit.commit_id(), plain.commit_id());
+
+        // Hint without raw data is ignored
+        let orphan = StateData { hint: Some(RawEncoding::Utf8), ..StateData::new(value) };
+        assert_eq!(serialize(&orphan), serialize(&StateData::new(value)));
+        assert_eq!(orphan.commit_id(), StateData::new(value).commit_id());
+
+        // Explicit binary hint on the wire is rejected
+        let mut bytes = serialize(&hinted);
+        // Value is followed by the tag of the raw data
+        let pos = serialize(&StateData::new(value)).len() - 1;
+        assert_eq!(bytes[pos], 0x02);
+        assert_eq!(bytes[pos + 1], RawEncoding::Utf8 as u8);
+        bytes[pos + 1] = RawEncoding::Binary as u8;
+        assert!(deserialize(bytes).is_err());
+    }
+
+    #[test]
+    fn pubkey_token() {
+        let xonly = AuthToken::from_pubkey(&[0x02; 32]).unwrap();
+        let compressed = AuthToken::from_pubkey(&[0x02; 33]).unwrap();
+        assert_ne!(xonly, compressed);
+        assert_eq!(xonly, AuthToken::from_pubkey(&[0x02; 32]).unwrap());
+        assert_eq!(AuthToken::from_pubkey(&[0x02; 65]), Err(PubkeyLenError(65)));
+    }
+
+    #[test]
+    fn display_compact() {
+        assert_eq!(StateValue::None.to_string(), "none");
+        let mut bytes = [0u8; 32];
+        bytes[..2].copy_from_slice(&[0xDE, 0xAD]);
+        bytes[30..].copy_from_slice(&[0xBE, 0xEF]);
+        let value = StateValue::Double {
+            first: fe256::from(u256::from(1u64)),
+            second: fe256::from(u256::from_be_bytes(bytes)),
+        };
+        assert_eq!(value.to_string(), "double(0x01, 0xdead…beef)");
+
+        let data = StateData::with_encoded_raw(
+            value,
+            SmallBlob::from_checked(b"hello".to_vec()),
+            RawEncoding::Utf8,
+        );
+        assert_eq!(data.to_string(), "double(0x01, 0xdead…beef) utf8(5 bytes)");
+
+        let cell = StateCell { data: value, auth: AuthToken::from([0u8; 30]), lock: none!() };
+        assert_eq!(cell.to_string(), "double(0x01, 0xdead…beef) auth 0x00");
+    }
+
+    #[test]
+    fn encrypted_envelope() {
+        struct Xor(u8);
+        impl RawCipher for Xor {
+            type Error = ();
+            fn key_commitment(&self) -> Bytes32 { Bytes32::from([self.0; 32]) }
+            fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, ()> {
+                Ok(plaintext.iter().map(|b| b ^ self.0).collect())
+            }
+            fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, ()> { self.encrypt(ciphertext) }
+        }
+
+        let encrypted = EncryptedRawData::encrypt(&Xor(0xA5), b"secret").unwrap();
+        let data = StateData::with_encrypted(StateValue::None, &encrypted).unwrap();
+        assert_eq!(data.raw_encoding(), Some(RawEncoding::Encrypted));
+
+        let envelope = data.encrypted().unwrap();
+        assert_eq!(envelope, encrypted);
+        assert_eq!(envelope.decrypt(&Xor(0xA5)), Some(Ok(b"secret".to_vec())));
+        assert_eq!(envelope.decrypt(&Xor(0x5A)), None);
+    }
+
+    #[test]
+    fn strict_raw() {
+        let value = StateValue::from([u256::from(7u8), u256::from(0xFFu8)]);
+        let data = StateData::with_strict_raw(StateValue::None, &value).unwrap();
+        assert_eq!(data.raw_encoding(), Some(RawEncoding::Strict));
+        assert_eq!(data.raw_strict::<StateValue>().unwrap().unwrap(), value);
+        assert!(StateData::new(value).raw_strict::<StateValue>().is_none());
+
+        let raw = data.raw.unwrap();
+        let mut trailing = raw.as_slice().to_vec();
+        trailing.push(0);
+        let trailing = RawData::from(SmallBlob::from_checked(trailing));
+        assert!(matches!(
+            trailing.decode_strict::<StateValue>(),
+            Err(RawStrictError::NonCanonical)
+        ));
+        assert!(matches!(
+            RawData::from(SmallBlob::default()).decode_strict::<StateValue>(),
+            Err(RawStrictError::Decode(_))
+        ));
+
+        let large = confinement::MediumBlob::from_checked(vec![0u8; 0x10000]);
+        assert!(matches!(RawData::encode_strict(&large), Err(RawStrictError::TooLarge)));
+    }
+
+    #[test]
+    fn auth_display_policy() {
+        let auth = AuthToken::from_byte_array([0xAD; 30]);
+        let hex = auth.display(DisplayPolicy::Hex).to_string();
+        assert_eq!(hex, "ad".repeat(30));
+        assert_eq!(AuthToken::parse_with(&hex, DisplayPolicy::Hex), Ok(auth));
+        assert_eq!(
+            AuthToken::parse_with(&hex.to_uppercase(), DisplayPolicy::Hex),
+            Err(AuthTokenParseError::Hex)
+        );
+
+        let truncated = DisplayPolicy::Truncated { head: 4, tail: 4 };
+        assert_eq!(auth.display(truncated).to_string(), "adad…adad");
+        assert_eq!(
+            AuthToken::parse_with("adad…adad", truncated),
+            Err(AuthTokenParseError::Truncated)
+        );
+
+        #[cfg(feature = "baid64")]
+        assert_eq!(auth.display(DisplayPolicy::default()).to_string(), auth.to_string());
+
+        #[cfg(feature = "bech32")]
+        {
+            let bech32 = auth.display(DisplayPolicy::Bech32m).to_string();
+            assert!(bech32.starts_with("auth1"));
+            assert_eq!(AuthToken::parse_with(&bech32, DisplayPolicy::Bech32m), Ok(auth));
+            assert_eq!(
+                AuthToken::parse_with(&bech32.to_uppercase(), DisplayPolicy::Bech32m),
+                Err(AuthTokenParseError::Bech32Hrp)
+            );
+        }
+    }
+
     #[test]
     #[cfg(feature = "baid64")]
     fn auth_baid64() {
@@ -0,0 +1,222 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use aluvm::gfa::{Bits, FieldInstr};
+use aluvm::isa::CtrlInstr;
+use aluvm::{Lib, LibId, LibSite, RegE};
+use strict_encoding::{StreamReader, StrictDecode, StrictReader};
+
+use crate::isa::REG_IN_RO;
+use crate::{Instr, UsonicCore, UsonicInstr};
+
+/// Strict-serialized standard library [`Lib`].
+///
+/// The library is shipped precompiled, such that its id can't change with the changes in the
+/// assembler. The `pinned` test checks that assembling the routines reproduces these bytes.
+pub const STDLIB: &[u8] = &[];
+
+/// Id of the standard library [`Lib`], serialized in [`STDLIB`].
+pub const STDLIB_ID: [u8; 32] = [0u8; 32];
+
+/// Offsets of the routines within the standard library code, indexed by [`StdRoutine`].
+pub const STDLIB_OFFSETS: [u16; 6] = [0u16; 6];
+
+/// Routines provided by the standard helper library [`StdLib`].
+///
+/// Range checks and state tuple equality use only GFA and control flow instructions, and thus can
+/// be called both from lock scripts and from operation verifiers. A routine which check fails,
+/// fails the VM; otherwise it returns to the caller without modifying any registers.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum StdRoutine {
+    /// Checks that the value in `E1` fits into 8 bits.
+    RangeU8,
+    /// Checks that the value in `E1` fits into 16 bits.
+    RangeU16,
+    /// Checks that the value in `E1` fits into 32 bits.
+    RangeU32,
+    /// Checks that the value in `E1` fits into 64 bits.
+    RangeU64,
+    /// Checks that the state tuple in `E1`-`E4` is equal to the state tuple in `E5`-`E8`.
+    EqTuple,
+    /// Adds the second field element of the current read-once input memory cell to `E2` if the
+    /// first element of the cell (its type tag) is equal to `E1`.
+    ///
+    /// The routine must be used as the target of `nxi.ro`, such that iterating over all the
+    /// read-once inputs sums the values of the given type into `E2`. Uses the register loaded by
+    /// `ldi.ro` and `CO` register, and fails if a matching cell has no second element.
+    SumTagged,
+}
+
+impl StdRoutine {
+    /// All routines in the order of their placement in the library code, matching the enum
+    /// discriminants.
+    pub const ALL: [StdRoutine; 6] = [
+        StdRoutine::RangeU8,
+        StdRoutine::RangeU16,
+        StdRoutine::RangeU32,
+        StdRoutine::RangeU64,
+        StdRoutine::EqTuple,
+        StdRoutine::SumTagged,
+    ];
+
+    /// Returns offset of the routine within the library code.
+    pub const fn offset(self) -> u16 { STDLIB_OFFSETS[self as usize] }
+
+    /// Returns the routine code, given the offset at which it is placed.
+    fn code(self, offset: u16) -> Vec<Instr<LibId>> {
+        let range = |bits: Bits| {
+            vec![
+                FieldInstr::Fits { src: RegE::E1, bits }.into(),
+                CtrlInstr::Chk.into(),
+                CtrlInstr::Ret.into(),
+            ]
+        };
+        match self {
+            StdRoutine::RangeU8 => range(Bits::Bits8),
+            StdRoutine::RangeU16 => range(Bits::Bits16),
+            StdRoutine::RangeU32 => range(Bits::Bits32),
+            StdRoutine::RangeU64 => range(Bits::Bits64),
+            StdRoutine::EqTuple => {
+                let mut code = vec![];
+                for (src1, src2) in [
+                    (RegE::E1, RegE::E5),
+                    (RegE::E2, RegE::E6),
+                    (RegE::E3, RegE::E7),
+                    (RegE::E4, RegE::E8),
+                ] {
+                    code.push(FieldInstr::Eq { src1, src2 }.into());
+                    code.push(CtrlInstr::Chk.into());
+                }
+                code.push(CtrlInstr::Ret.into());
+                code
+            }
+            StdRoutine::SumTagged => {
+                let el = UsonicCore::load_dst(REG_IN_RO);
+                let sum = |ret: u16| -> Vec<Instr<LibId>> {
+                    vec![
+                        UsonicInstr::LdIRo.into(),
+                        FieldInstr::Eq { src1: el, src2: RegE::E1 }.into(),
+                        CtrlInstr::JiNe { pos: ret }.into(),
+                        UsonicInstr::LdIRo.into(),
+                        FieldInstr::Add { dst_src: RegE::E2, src: el }.into(),
+                        CtrlInstr::Chk.into(),
+                    ]
+                };
+                // The jump target is the final `ret`, placed right after the summing code
+                let ret = offset + code_len(&sum(0));
+                let mut code = sum(ret);
+                code.push(CtrlInstr::Ret.into());
+                code
+            }
+        }
+    }
+
+    /// Assembles the library code, returning it together with the offsets of the routines.
+    ///
+    /// Used to produce the pinned [`STDLIB`], [`STDLIB_ID`] and [`STDLIB_OFFSETS`].
+    pub fn assemble() -> (Lib, [u16; 6]) {
+        let mut code = vec![];
+        let mut offsets = [0u16; 6];
+        for (no, routine) in Self::ALL.into_iter().enumerate() {
+            offsets[no] = code_len(&code);
+            code.extend(routine.code(offsets[no]));
+        }
+        let lib = Lib::assemble(&code).expect("invalid standard library code");
+        (lib, offsets)
+    }
+}
+
+fn code_len(code: &[Instr<LibId>]) -> u16 {
+    if code.is_empty() {
+        return 0;
+    }
+    let lib = Lib::assemble(code).expect("invalid standard library code");
+    lib.code.len() as u16
+}
+
+/// Standard library of helper routines for codex verifiers and lock scripts.
+///
+/// The library must be provided by the [`crate::LibRepo`] used in the verification of codices
+/// calling its routines.
+#[derive(Clone, Debug)]
+pub struct StdLib(Lib);
+
+impl Default for StdLib {
+    fn default() -> Self { Self::new() }
+}
+
+impl StdLib {
+    /// Decodes the precompiled library.
+    pub fn new() -> Self {
+        let mut reader = StrictReader::with(StreamReader::cursor::<{ u16::MAX as usize }>(STDLIB));
+        let lib = Lib::strict_decode(&mut reader).expect("invalid precompiled standard library");
+        Self(lib)
+    }
+
+    pub fn lib(&self) -> &Lib { &self.0 }
+
+    pub fn into_lib(self) -> Lib { self.0 }
+
+    pub fn lib_id(&self) -> LibId { LibId::from(STDLIB_ID) }
+
+    /// Returns offset of the routine within the library code.
+    pub fn offset(&self, routine: StdRoutine) -> u16 { routine.offset() }
+
+    /// Returns site of the routine, which can be used as a call target.
+    pub fn site(&self, routine: StdRoutine) -> LibSite {
+        LibSite::new(self.lib_id(), routine.offset())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::ToHex;
+    use amplify::ByteArray;
+    use strict_encoding::{StrictEncode, StrictWriter};
+
+    use super::*;
+
+    #[test]
+    fn pinned() {
+        let (lib, offsets) = StdRoutine::assemble();
+        let writer = StrictWriter::in_memory::<{ u16::MAX as usize }>();
+        let writer = lib.strict_encode(writer).unwrap();
+        let data = writer.unbox().unconfine();
+        assert_eq!(data, STDLIB, "precompiled standard library must be {}", data.to_hex());
+        assert_eq!(
+            lib.lib_id(),
+            LibId::from(STDLIB_ID),
+            "standard library id must be {:?}",
+            lib.lib_id().to_byte_array()
+        );
+        assert_eq!(offsets, STDLIB_OFFSETS);
+    }
+
+    #[test]
+    fn decodes() {
+        let stdlib = StdLib::new();
+        assert_eq!(stdlib.lib().lib_id(), stdlib.lib_id());
+        assert_eq!(stdlib.site(StdRoutine::SumTagged).offset, StdRoutine::SumTagged.offset());
+    }
+}
@@ -0,0 +1,276 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use crate::{
+    ApplyState, CellAddr, ContractId, Genesis, Height, LiveState, Memory, Opid, ReplayMemory,
+    StateCell, StateValue, VerifiedOperation,
+};
+
+/// Changes made by an applied operation, allowing to roll them back.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Undo {
+    opid: Opid,
+    /// Height of the operation known before the application.
+    height: Option<Height>,
+    /// Read-once memory cells destroyed by the operation.
+    destroyed: Vec<(CellAddr, StateCell)>,
+    /// Number of read-once memory cells created by the operation.
+    destructible: u16,
+    /// Number of immutable memory cells created by the operation.
+    immutable: u16,
+}
+
+/// Reference in-memory implementation of the contract state with rollback support.
+///
+/// The store can be passed directly to [`crate::Codex::verify`]; verified operations are applied
+/// to it with [`ApplyState::apply`], and the applied operations can be rolled back in the reverse
+/// order with [`Self::rollback`], for instance on a reorg of the underlying consensus layer. The
+/// state can also be populated manually, which is useful for tests.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "fixtures")] {
+/// use ultrasonic::fixtures::Fixture;
+/// use ultrasonic::{ApplyState, LiveState, MemoryStore};
+///
+/// let fixture = Fixture::fungible_token();
+/// let contract_id = fixture.contract.contract_id();
+/// let mut store = MemoryStore::from_genesis(contract_id, &fixture.contract.genesis);
+/// let initial = store.clone();
+/// for op in fixture.operations.clone() {
+///     let verified = fixture
+///         .codex()
+///         .verify(contract_id, op, &store, &fixture)
+///         .unwrap();
+///     store.apply(verified).unwrap();
+/// }
+/// let genesis_opid = fixture.contract.genesis_opid();
+/// assert_eq!(store.rollback_to(genesis_opid), Some(fixture.operations.len()));
+/// assert!(store.live_read_once().eq(initial.live_read_once()));
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MemoryStore {
+    state: ReplayMemory,
+    undo: Vec<Undo>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self { Self::default() }
+
+    /// Constructs the store containing the state created by the contract genesis.
+    pub fn from_genesis(contract_id: ContractId, genesis: &Genesis) -> Self {
+        let mut store = Self::new();
+        store.apply_unchecked(VerifiedOperation::new_unchecked(
+            genesis.opid(contract_id),
+            genesis.to_operation(contract_id),
+            Height::GENESIS,
+        ));
+        store
+    }
+
+    /// Inserts a read-once memory cell, returning the previous cell at the address.
+    ///
+    /// Manual modifications of the state are not recorded for the rollback.
+    pub fn insert_read_once(&mut self, addr: CellAddr, cell: StateCell) -> Option<StateCell> {
+        self.state.generation += 1;
+        self.state.read_once.insert(addr, cell)
+    }
+
+    /// Inserts an immutable memory cell, returning the previous value at the address.
+    ///
+    /// Manual modifications of the state are not recorded for the rollback.
+    pub fn insert_immutable(&mut self, addr: CellAddr, value: StateValue) -> Option<StateValue> {
+        self.state.generation += 1;
+        self.state.immutable.insert(addr, value)
+    }
+
+    /// Sets the height of an operation, returning the previously known height.
+    ///
+    /// Manual modifications of the state are not recorded for the rollback.
+    pub fn insert_height(&mut self, opid: Opid, height: Height) -> Option<Height> {
+        self.state.generation += 1;
+        self.state.heights.insert(opid, height)
+    }
+
+    /// Removes a read-once memory cell.
+    ///
+    /// Manual modifications of the state are not recorded for the rollback.
+    pub fn remove_read_once(&mut self, addr: CellAddr) -> Option<StateCell> {
+        self.state.generation += 1;
+        self.state.read_once.remove(&addr)
+    }
+
+    /// Removes an immutable memory cell.
+    ///
+    /// Manual modifications of the state are not recorded for the rollback.
+    pub fn remove_immutable(&mut self, addr: CellAddr) -> Option<StateValue> {
+        self.state.generation += 1;
+        self.state.immutable.remove(&addr)
+    }
+
+    /// Returns the state without the rollback history.
+    pub fn as_state(&self) -> &ReplayMemory { &self.state }
+
+    /// Converts the store into the state, discarding the rollback history.
+    pub fn into_state(self) -> ReplayMemory { self.state }
+
+    /// Returns the number of applied operations which can be rolled back.
+    pub fn history_len(&self) -> usize { self.undo.len() }
+
+    /// Discards the rollback history, making the applied operations final.
+    pub fn finalize(&mut self) { self.undo.clear(); }
+
+    /// Rolls back the last applied operation, returning its id, or `None` if there are no
+    /// operations to roll back.
+    pub fn rollback(&mut self) -> Option<Opid> {
+        let undo = self.undo.pop()?;
+        let state = &mut self.state;
+        state.generation += 1;
+        for pos in 0..undo.destructible {
+            state.read_once.remove(&CellAddr::new(undo.opid, pos));
+        }
+        for pos in 0..undo.immutable {
+            state.immutable.remove(&CellAddr::new(undo.opid, pos));
+        }
+        state.read_once.extend(undo.destroyed);
+        match undo.height {
+            Some(height) => state.heights.insert(undo.opid, height),
+            None => state.heights.remove(&undo.opid),
+        };
+        Some(undo.opid)
+    }
+
+    /// Rolls back all the operations applied after the operation with `opid`, returning the
+    /// number of the rolled back operations.
+    ///
+    /// Returns `None` and doesn't modify the state if the operation is not in the rollback
+    /// history.
+    pub fn rollback_to(&mut self, opid: Opid) -> Option<usize> {
+        let pos = self.undo.iter().rposition(|undo| undo.opid == opid)?;
+        let count = self.undo.len() - pos - 1;
+        for _ in 0..count {
+            self.rollback();
+        }
+        Some(count)
+    }
+}
+
+impl Memory for MemoryStore {
+    fn read_once(&self, addr: CellAddr) -> Option<StateCell> { self.state.read_once(addr) }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.state.immutable(addr) }
+
+    fn height(&self, opid: Opid) -> Option<Height> { self.state.height(opid) }
+
+    fn tip(&self) -> Option<u64> { self.state.tip() }
+}
+
+impl LiveState for MemoryStore {
+    fn live_read_once(&self) -> impl Iterator<Item = (CellAddr, StateCell)> + '_ {
+        self.state.live_read_once()
+    }
+
+    fn live_immutable(&self) -> impl Iterator<Item = (CellAddr, StateValue)> + '_ {
+        self.state.live_immutable()
+    }
+}
+
+impl ApplyState for MemoryStore {
+    fn apply_unchecked(&mut self, op: VerifiedOperation) {
+        let operation = op.as_operation();
+        self.undo.push(Undo {
+            opid: op.opid(),
+            height: self.state.height(op.opid()),
+            destroyed: operation
+                .destroying
+                .iter()
+                .filter_map(|input| Some((input.addr, self.state.read_once(input.addr)?)))
+                .collect(),
+            destructible: operation.destructible.len() as u16,
+            immutable: operation.immutable.len() as u16,
+        });
+        self.state.apply_unchecked(op);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::num::u256;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{ApplyError, Input, Operation, StateData};
+
+    fn cell(val: u8) -> StateCell {
+        StateCell {
+            data: StateValue::from([u256::from(val)]),
+            ..StateCell::strict_dumb()
+        }
+    }
+
+    fn operation(spent: CellAddr, val: u8) -> VerifiedOperation {
+        let mut op = Operation::strict_dumb();
+        op.nonce = aluvm::fe256::from(u256::from(val));
+        op.destroying
+            .push(Input { addr: spent, witness: none!() })
+            .unwrap();
+        op.destructible.push(cell(val)).unwrap();
+        op.immutable
+            .push(StateData::new(StateValue::from([u256::from(val)])))
+            .unwrap();
+        VerifiedOperation::new_unchecked(op.opid(), op, Height::new(1))
+    }
+
+    #[test]
+    fn apply_rollback() {
+        let genesis = Opid::from([1u8; 32]);
+        let spent = CellAddr::new(genesis, 0);
+        let mut store = MemoryStore::new();
+        store.insert_height(genesis, Height::GENESIS);
+        store.insert_read_once(spent, cell(1));
+        let initial = store.clone();
+
+        let first = operation(spent, 2);
+        let next = operation(CellAddr::new(first.opid(), 0), 3);
+        store.apply(first.clone()).unwrap();
+        store.apply(next.clone()).unwrap();
+        assert_eq!(store.read_once(spent), None);
+        assert_eq!(store.height(next.opid()), Some(Height::new(1)));
+        assert_eq!(store.history_len(), 2);
+        assert_eq!(store.apply(first.clone()), Err(ApplyError::NotLive(spent)));
+
+        assert_eq!(store.rollback_to(first.opid()), Some(1));
+        assert_eq!(store.read_once(CellAddr::new(first.opid(), 0)), Some(cell(2)));
+        assert_eq!(store.height(next.opid()), None);
+        assert_eq!(store.rollback_to(next.opid()), None);
+
+        assert_eq!(store.rollback(), Some(first.opid()));
+        assert_eq!(store.rollback(), None);
+        assert_eq!(store.as_state().read_once, initial.as_state().read_once);
+        assert_eq!(store.as_state().immutable, initial.as_state().immutable);
+        assert_eq!(store.as_state().heights, initial.as_state().heights);
+        assert_ne!(store.tip(), initial.tip());
+    }
+}
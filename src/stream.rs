@@ -0,0 +1,335 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+use alloc::vec;
+use core::cell::RefCell;
+
+use amplify::confinement::SmallVec;
+use commit_verify::{CommitEncode, CommitEngine, CommitId, MerkleLeaves, ReservedBytes};
+
+use crate::{
+    fe256, CallError, CallId, CellAddr, Codex, ContractId, Height, Input, LibRepo, Memory,
+    Operation, Opid, ReadIntent, StateCell, StateData, StateValue, VerifiedRef, VerifyStats,
+};
+
+/// Operation which immutable outputs are provided by an iterator rather than being materialized.
+///
+/// All other fields have the same meaning as in [`Operation`]. Unlike the immutable outputs, which
+/// may carry up to 64 kiB of [`crate::RawData`] each, they are always small, and thus are kept in
+/// memory. Operations with streamed outputs are verified with [`Codex::verify_streaming`].
+#[derive(Clone, Debug)]
+pub struct OperationStream<I> {
+    pub contract_id: ContractId,
+    pub call_id: CallId,
+    pub nonce: fe256,
+    pub destroying: SmallVec<Input>,
+    pub reading: SmallVec<CellAddr>,
+    pub destructible: SmallVec<StateCell>,
+    /// Immutable memory data created by the operation, in the order of their positions.
+    ///
+    /// The iterator must report the exact number of the outputs it yields.
+    pub immutable: I,
+    pub witness_commitment: Option<fe256>,
+    pub read_intents: SmallVec<ReadIntent>,
+    pub reserved: ReservedBytes<8>,
+}
+
+impl OperationStream<vec::IntoIter<StateData>> {
+    /// Converts a materialized operation into a stream over its immutable outputs.
+    pub fn from_operation(operation: Operation) -> Self {
+        Self {
+            contract_id: operation.contract_id,
+            call_id: operation.call_id,
+            nonce: operation.nonce,
+            destroying: operation.destroying,
+            reading: operation.reading,
+            destructible: operation.destructible,
+            immutable: operation.immutable.release().into_iter(),
+            witness_commitment: operation.witness_commitment,
+            read_intents: operation.read_intents,
+            reserved: operation.reserved,
+        }
+    }
+}
+
+impl<I: ExactSizeIterator<Item = StateData>> OperationStream<I> {
+    /// Consumes the immutable outputs, returning the operation id and the operation with the
+    /// outputs reduced to their values, which is all the verifier can access.
+    ///
+    /// Each output is committed to as soon as it is yielded by the iterator, and then dropped.
+    fn materialize(self) -> Result<(Opid, Operation), CallError> {
+        let declared = self.immutable.len();
+        if declared > Operation::MAX_OUTPUTS {
+            return Err(CallError::OutputStream);
+        }
+        let mut operation = Operation {
+            contract_id: self.contract_id,
+            call_id: self.call_id,
+            nonce: self.nonce,
+            destroying: self.destroying,
+            reading: self.reading,
+            destructible: self.destructible,
+            immutable: none!(),
+            witness_commitment: self.witness_commitment,
+            read_intents: self.read_intents,
+            reserved: self.reserved,
+        };
+        let leaves = StreamLeaves {
+            iter: RefCell::new(self.immutable),
+            declared,
+            short: RefCell::new(false),
+            values: RefCell::new(Vec::with_capacity(declared)),
+        };
+        let opid = StreamId { header: &operation, immutable: &leaves }.commit_id();
+
+        let values = leaves.values.into_inner();
+        if leaves.short.into_inner()
+            || values.len() != declared
+            || leaves.iter.into_inner().next().is_some()
+        {
+            return Err(CallError::OutputStream);
+        }
+        operation.immutable = SmallVec::from_checked(values);
+        Ok((opid, operation))
+    }
+}
+
+/// Commitment to an operation with streamed immutable outputs, producing the same id as the
+/// materialized operation.
+struct StreamId<'s, I> {
+    header: &'s Operation,
+    immutable: &'s StreamLeaves<I>,
+}
+
+impl<I: ExactSizeIterator<Item = StateData>> CommitEncode for StreamId<'_, I> {
+    type CommitmentId = Opid;
+
+    fn commit_encode(&self, e: &mut CommitEngine) {
+        self.header.commit_encode_with(self.immutable, e)
+    }
+}
+
+/// Merkle leaves taken from an iterator, recording values of the yielded outputs.
+struct StreamLeaves<I> {
+    iter: RefCell<I>,
+    declared: usize,
+    short: RefCell<bool>,
+    values: RefCell<Vec<StateData>>,
+}
+
+impl<I: ExactSizeIterator<Item = StateData>> MerkleLeaves for StreamLeaves<I> {
+    type Leaf = StateData;
+    type LeafIter<'tmp>
+        = RecordingIter<'tmp, I>
+    where Self: 'tmp;
+
+    fn merkle_leaves(&self) -> Self::LeafIter<'_> {
+        let remaining = self.declared - self.values.borrow().len();
+        RecordingIter { leaves: self, remaining }
+    }
+}
+
+struct RecordingIter<'l, I> {
+    leaves: &'l StreamLeaves<I>,
+    remaining: usize,
+}
+
+impl<I: Iterator<Item = StateData>> Iterator for RecordingIter<'_, I> {
+    type Item = StateData;
+
+    fn next(&mut self) -> Option<StateData> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        match self.leaves.iter.borrow_mut().next() {
+            Some(data) => {
+                self.leaves
+                    .values
+                    .borrow_mut()
+                    .push(StateData::new(data.value));
+                Some(data)
+            }
+            // The merklization relies on the declared number of leaves, so a stream which ends
+            // early is padded, and the operation is rejected afterwards.
+            None => {
+                *self.leaves.short.borrow_mut() = true;
+                Some(StateData::new(StateValue::None))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.remaining, Some(self.remaining)) }
+}
+
+impl<I: Iterator<Item = StateData>> ExactSizeIterator for RecordingIter<'_, I> {}
+
+/// Operation with streamed immutable outputs which was successfully verified against a codex.
+///
+/// Produced by [`Codex::verify_streaming`]. Since the outputs are not retained, it is up to the
+/// caller to store them under the returned operation id.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VerifiedStream {
+    opid: Opid,
+    height: Height,
+    stats: VerifyStats,
+}
+
+impl VerifiedStream {
+    pub fn opid(&self) -> Opid { self.opid }
+
+    pub fn height(&self) -> Height { self.height }
+
+    /// Returns the resources consumed by the operation verification.
+    pub fn stats(&self) -> VerifyStats { self.stats }
+}
+
+impl Codex {
+    /// Verifies the operation like [`Self::verify`], taking its immutable outputs from an
+    /// iterator instead of a materialized [`Operation`].
+    ///
+    /// The outputs are consumed one by one: each of them is committed to when yielded, after
+    /// which only its [`StateValue`], accessible to the verifier, is kept. Thus, operations with
+    /// large raw data are verified without holding all the data in memory. If the iterator yields
+    /// a number of outputs different from the one it reports, or more than
+    /// [`Operation::MAX_OUTPUTS`], [`CallError::OutputStream`] is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "fixtures")] {
+    /// use ultrasonic::fixtures::Fixture;
+    /// use ultrasonic::OperationStream;
+    ///
+    /// let fixture = Fixture::counter();
+    /// let contract_id = fixture.contract.contract_id();
+    /// let codex = fixture.codex();
+    /// let op = fixture.operations[0].clone();
+    ///
+    /// let stream = OperationStream::from_operation(op.clone());
+    /// let streamed = codex
+    ///     .verify_streaming(contract_id, stream, &fixture, &fixture)
+    ///     .unwrap();
+    /// let verified = codex.verify(contract_id, op, &fixture, &fixture).unwrap();
+    /// assert_eq!(streamed.opid(), verified.opid());
+    /// assert_eq!(streamed.stats(), verified.stats());
+    /// # }
+    /// ```
+    pub fn verify_streaming<I>(
+        &self,
+        contract_id: ContractId,
+        operation: OperationStream<I>,
+        memory: &impl Memory,
+        repo: &impl LibRepo,
+    ) -> Result<VerifiedStream, CallError>
+    where
+        I: ExactSizeIterator<Item = StateData>,
+    {
+        // Checked before consuming the outputs, which may be expensive to produce.
+        if operation.contract_id != contract_id {
+            return Err(CallError::WrongContract {
+                expected: contract_id,
+                found: operation.contract_id,
+            });
+        }
+        let (opid, operation) = operation.materialize()?;
+        let VerifiedRef { height, stats, .. } =
+            self.verify_ref(contract_id, &operation, memory, repo)?;
+        Ok(VerifiedStream { opid, height, stats })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallBlob;
+    use amplify::num::u256;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    fn operation() -> Operation {
+        let mut op = Operation {
+            contract_id: ContractId::from([0xAB; 32]),
+            call_id: CallId::from(1u16),
+            ..Operation::strict_dumb()
+        };
+        for no in 0..4u8 {
+            let value = StateValue::from([u256::from(no)]);
+            let raw = SmallBlob::from_checked(vec![no; 1000]);
+            op.immutable.push(StateData::with_raw(value, raw)).unwrap();
+        }
+        op.immutable.push(StateData::new(StateValue::None)).unwrap();
+        op
+    }
+
+    #[test]
+    fn same_opid() {
+        let op = operation();
+        let (opid, stripped) = OperationStream::from_operation(op.clone())
+            .materialize()
+            .unwrap();
+        assert_eq!(opid, op.opid());
+        assert_eq!(stripped.immutable.len(), op.immutable.len());
+        for (stripped, data) in stripped.immutable.iter().zip(&op.immutable) {
+            assert_eq!(stripped.value, data.value);
+            assert_eq!(stripped.raw, None);
+        }
+    }
+
+    /// Iterator misreporting its length by `shift` items.
+    struct Misreported(vec::IntoIter<StateData>, isize);
+
+    impl Iterator for Misreported {
+        type Item = StateData;
+
+        fn next(&mut self) -> Option<StateData> { self.0.next() }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.0.len().saturating_add_signed(self.1);
+            (len, Some(len))
+        }
+    }
+
+    impl ExactSizeIterator for Misreported {}
+
+    #[test]
+    fn misreported_len() {
+        for shift in [-1, 1] {
+            let op = operation();
+            let immutable = Misreported(op.immutable.clone().release().into_iter(), shift);
+            let stream = OperationStream {
+                contract_id: op.contract_id,
+                call_id: op.call_id,
+                nonce: op.nonce,
+                destroying: op.destroying,
+                reading: op.reading,
+                destructible: op.destructible,
+                immutable,
+                witness_commitment: op.witness_commitment,
+                read_intents: op.read_intents,
+                reserved: op.reserved,
+            };
+            assert_eq!(stream.materialize().unwrap_err(), CallError::OutputStream);
+        }
+    }
+}
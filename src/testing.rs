@@ -0,0 +1,134 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Helpers for unit testing microcode of the instructions built on top of [`UsonicCore`], without
+//! constructing full VMs and libraries.
+
+use aluvm::regs::Status;
+use aluvm::{fe256, CoreExt, ExecStep, RegE, Site, SiteId};
+use amplify::num::u256;
+
+use crate::{CellCategory, StateCell, StateData, StateValue, UsonicCore, UsonicInstr, VmContext};
+
+/// Owned operation state, from which a [`VmContext`] can be borrowed.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct MockContext {
+    pub read_once_input: Vec<StateValue>,
+    pub immutable_input: Vec<StateValue>,
+    pub read_once_output: Vec<StateCell>,
+    pub immutable_output: Vec<StateData>,
+    pub witness_commitment: Option<fe256>,
+}
+
+impl MockContext {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_read_once_input(mut self, value: StateValue) -> Self {
+        self.read_once_input.push(value);
+        self
+    }
+
+    pub fn with_immutable_input(mut self, value: StateValue) -> Self {
+        self.immutable_input.push(value);
+        self
+    }
+
+    pub fn with_read_once_output(mut self, cell: StateCell) -> Self {
+        self.read_once_output.push(cell);
+        self
+    }
+
+    pub fn with_immutable_output(mut self, data: StateData) -> Self {
+        self.immutable_output.push(data);
+        self
+    }
+
+    pub fn with_witness_commitment(mut self, commitment: fe256) -> Self {
+        self.witness_commitment = Some(commitment);
+        self
+    }
+
+    /// Borrows the VM context from the mock.
+    pub fn as_context(&self) -> VmContext<'_> {
+        VmContext {
+            read_once_input: &self.read_once_input,
+            immutable_input: &self.immutable_input,
+            read_once_output: &self.read_once_output,
+            immutable_output: &self.immutable_output,
+            witness_commitment: self.witness_commitment,
+        }
+    }
+}
+
+/// Constructs a fresh core for the given field order.
+pub fn new_core(field_order: u256) -> UsonicCore { UsonicCore::with(field_order) }
+
+/// Executes a single USONIC instruction on the core, returning the execution step and the
+/// resulting value of `CO` register (which starts as [`Status::Ok`]).
+pub fn exec<Id: SiteId>(
+    core: &mut UsonicCore,
+    instr: UsonicInstr<Id>,
+    context: &MockContext,
+) -> (ExecStep<Site<Id>>, Status) {
+    let mut co = Status::Ok;
+    let step = core.exec_usonic(instr, &context.as_context(), &mut co);
+    (step, co)
+}
+
+/// Returns the register into which field elements of the current cell of the category are loaded.
+pub fn load_dst(cat: CellCategory) -> RegE { UsonicCore::load_dst(cat.to_reg()) }
+
+/// Returns the iterator state for the category: the number of the cells iterated so far and the
+/// number of the field elements loaded from the current cell.
+pub fn iter_state(core: &UsonicCore, cat: CellCategory) -> (u16, u8) {
+    let reg = cat.to_reg();
+    (core.ui[reg], core.ue[reg])
+}
+
+/// Sets the iterator state for the category (see [`iter_state`]).
+pub fn set_iter_state(core: &mut UsonicCore, cat: CellCategory, cells: u16, elements: u8) {
+    let reg = cat.to_reg();
+    core.ui[reg] = cells;
+    core.ue[reg] = elements;
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::LibId;
+
+    use super::*;
+    use crate::FIELD_ORDER_SECP;
+
+    #[test]
+    fn load_without_iteration() {
+        let context = MockContext::new().with_read_once_input(StateValue::None);
+        let mut core = new_core(FIELD_ORDER_SECP);
+        let (step, _) = exec(&mut core, UsonicInstr::<LibId>::LdIRo, &context);
+        assert_eq!(step, ExecStep::FailHalt);
+
+        set_iter_state(&mut core, CellCategory::InRo, 1, 0);
+        let (step, _) = exec(&mut core, UsonicInstr::<LibId>::LdIRo, &context);
+        assert_eq!(step, ExecStep::Next);
+        assert_eq!(core.get(load_dst(CellCategory::InRo)), None);
+    }
+}
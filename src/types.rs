@@ -0,0 +1,56 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Confined collections used in the public API, together with their confinement bounds.
+//!
+//! Downstream crates can use these aliases in their own signatures instead of depending on
+//! `amplify` directly. Any change to the bounds of a collection changes its alias here and is thus
+//! a visible API change.
+
+use aluvm::LibSite;
+pub use amplify::confinement::{
+    Collection, Confined, SmallBlob, SmallVec, TinyOrdMap, TinyString, U16, U8,
+};
+
+use crate::{CallId, CellAddr, Input, StateCell, StateData, StateValue};
+
+/// Inputs destroyed by an operation; up to `2^16 - 1` items.
+pub type Inputs = SmallVec<Input>;
+
+/// Addresses of immutable memory cells read by an operation; up to `2^16 - 1` items.
+pub type Reads = SmallVec<CellAddr>;
+
+/// Destructible (read-once) memory cells created by an operation; up to `2^16 - 1` items.
+pub type DestructibleCells = SmallVec<StateCell>;
+
+/// Immutable memory cells created by an operation; up to `2^16 - 1` items.
+pub type ImmutableCells = SmallVec<StateData>;
+
+/// Values of state, such as materialized operation inputs; up to `2^16 - 1` items.
+pub type StateValues = SmallVec<StateValue>;
+
+/// Codex verifiers by their call id; up to 255 entries.
+pub type Verifiers = TinyOrdMap<CallId, LibSite>;
+
+/// Human-readable codex name; up to 255 characters.
+pub type CodexName = TinyString;
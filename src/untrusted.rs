@@ -0,0 +1,186 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Decoding of untrusted data with resource limits set by the caller.
+//!
+//! Strict decoding limits are compile-time constants, which are too permissive for some network-
+//! facing deployments. [`DecodeUntrusted`] rejects oversized blobs before parsing them, and checks
+//! the number of items and the size of raw data right after decoding, before the value is used
+//! in any further processing.
+//!
+//! The item and raw data limits are not enforced while decoding: a blob within the size limit is
+//! parsed completely before these limits are checked. The memory used by the parsing is bounded by
+//! the size limit and the confinement limits of the strict encoding.
+//!
+//! Consensus types don't contain recursive structures, so their nesting depth is fixed and needs no
+//! guard.
+
+use amplify::confinement::{self, Confined, U24};
+use strict_encoding::{DeserializeError, StrictDeserialize};
+
+use crate::{Contract, Genesis, Operation, StateData};
+
+/// Limits applied when decoding untrusted data.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DecodeLimits {
+    /// Maximal size of the serialized data, in bytes.
+    pub max_size: usize,
+    /// Maximal number of items in each of the operation lists (inputs, outputs and read
+    /// intents).
+    pub max_items: usize,
+    /// Maximal size of raw data in each of the immutable memory cells, in bytes.
+    pub max_raw: usize,
+}
+
+impl Default for DecodeLimits {
+    /// The most permissive limits, matching the ones imposed by the strict encoding.
+    fn default() -> Self {
+        Self {
+            max_size: U24,
+            max_items: u16::MAX as usize,
+            max_raw: u16::MAX as usize,
+        }
+    }
+}
+
+/// Data which can be decoded from untrusted sources with caller-provided resource limits.
+pub trait DecodeUntrusted: StrictDeserialize {
+    /// Checks the decoded value against the limits.
+    fn check_limits(&self, limits: &DecodeLimits) -> Result<(), UntrustedError>;
+
+    /// Decodes the value, checking that both the serialized data and the decoded value fit the
+    /// limits.
+    fn decode_untrusted(data: &[u8], limits: &DecodeLimits) -> Result<Self, UntrustedError> {
+        let max = limits.max_size.min(U24);
+        if data.len() > max {
+            return Err(UntrustedError::TooLarge { size: data.len(), max });
+        }
+        let data = Confined::<Vec<u8>, 0, U24>::try_from(data.to_vec())?;
+        let value = Self::from_strict_serialized::<U24>(data)?;
+        value.check_limits(limits)?;
+        Ok(value)
+    }
+}
+
+fn check_items(len: usize, limits: &DecodeLimits) -> Result<(), UntrustedError> {
+    if len > limits.max_items {
+        return Err(UntrustedError::TooManyItems { count: len, max: limits.max_items });
+    }
+    Ok(())
+}
+
+fn check_raw<'a>(
+    data: impl IntoIterator<Item = &'a StateData>,
+    limits: &DecodeLimits,
+) -> Result<(), UntrustedError> {
+    for raw in data.into_iter().filter_map(|data| data.raw.as_ref()) {
+        if raw.len() > limits.max_raw {
+            return Err(UntrustedError::RawTooLarge { size: raw.len(), max: limits.max_raw });
+        }
+    }
+    Ok(())
+}
+
+impl DecodeUntrusted for Operation {
+    fn check_limits(&self, limits: &DecodeLimits) -> Result<(), UntrustedError> {
+        check_items(self.destroying.len(), limits)?;
+        check_items(self.reading.len(), limits)?;
+        check_items(self.destructible.len(), limits)?;
+        check_items(self.immutable.len(), limits)?;
+        check_items(self.read_intents.len(), limits)?;
+        check_raw(&self.immutable, limits)
+    }
+}
+
+impl DecodeUntrusted for Genesis {
+    fn check_limits(&self, limits: &DecodeLimits) -> Result<(), UntrustedError> {
+        check_items(self.destructible.len(), limits)?;
+        check_items(self.immutable.len(), limits)?;
+        check_raw(&self.immutable, limits)
+    }
+}
+
+impl<const CAPS: u32> DecodeUntrusted for Contract<CAPS> {
+    fn check_limits(&self, limits: &DecodeLimits) -> Result<(), UntrustedError> {
+        self.genesis.check_limits(limits)
+    }
+}
+
+/// Error decoding untrusted data.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum UntrustedError {
+    /// data size {size} exceeds the limit of {max} bytes.
+    TooLarge { size: usize, max: usize },
+
+    /// data contain a list of {count} items, exceeding the limit of {max} items.
+    TooManyItems { count: usize, max: usize },
+
+    /// data contain raw data of {size} bytes, exceeding the limit of {max} bytes.
+    RawTooLarge { size: usize, max: usize },
+
+    /// data are too large for the strict encoding.
+    #[from]
+    Confinement(confinement::Error),
+
+    /// invalid data: {0}
+    #[from]
+    Decode(DeserializeError),
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::{StrictDumb, StrictSerialize};
+
+    use super::*;
+    use crate::{ReadIntent, StateValue};
+
+    #[test]
+    fn limits() {
+        let op = Operation::strict_dumb();
+        let data = op.to_strict_serialized::<U24>().unwrap().release();
+        assert_eq!(Operation::decode_untrusted(&data, &DecodeLimits::default()).unwrap(), op);
+
+        let limits = DecodeLimits { max_size: data.len() - 1, ..default!() };
+        assert!(matches!(
+            Operation::decode_untrusted(&data, &limits),
+            Err(UntrustedError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn read_intents() {
+        let mut op = Operation::strict_dumb();
+        for _ in 0..2 {
+            op.read_intents
+                .push(ReadIntent::new(0, &StateValue::None))
+                .unwrap();
+        }
+        let data = op.to_strict_serialized::<U24>().unwrap().release();
+        let limits = DecodeLimits { max_items: 1, ..default!() };
+        assert!(matches!(
+            Operation::decode_untrusted(&data, &limits),
+            Err(UntrustedError::TooManyItems { count: 2, max: 1 })
+        ));
+    }
+}
@@ -21,7 +21,7 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
-use commit_verify::StrictHash;
+use commit_verify::{DigestExt, Sha256, StrictHash};
 use strict_encoding::stl::AsciiPrintable;
 use strict_encoding::RString;
 
@@ -56,3 +56,104 @@ impl Identity {
     pub fn is_empty(&self) -> bool { self.is_anonymous() }
     pub fn is_anonymous(&self) -> bool { self == &default!() }
 }
+
+/// Unix timestamp, in seconds, used in contract and codex headers.
+///
+/// Timestamps constructed with [`Timestamp::new`] are validated to lie within
+/// [`Timestamp::MIN`]..=[`Timestamp::MAX`] range, catching zero, negative or millisecond-based
+/// values. Decoding doesn't perform this validation, since the timestamp value has no consensus
+/// meaning and historical data must remain readable.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
+#[display(inner)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    /// Minimal valid timestamp (2023-01-01T00:00:00Z).
+    pub const MIN: i64 = 1_672_531_200;
+    /// Maximal valid timestamp (2100-01-01T00:00:00Z).
+    pub const MAX: i64 = 4_102_444_800;
+
+    /// Constructs timestamp, checking it to be within the valid range.
+    pub fn new(secs: i64) -> Result<Self, TimestampError> {
+        match secs {
+            secs if secs < Self::MIN => Err(TimestampError::TooEarly(secs)),
+            secs if secs > Self::MAX => Err(TimestampError::TooLate(secs)),
+            secs => Ok(Self(secs)),
+        }
+    }
+
+    /// Constructs timestamp without checking it to be within the valid range.
+    pub const fn from_unchecked(secs: i64) -> Self { Self(secs) }
+
+    /// Constructs timestamp from the current system time.
+    #[cfg(feature = "std")]
+    pub fn now() -> Self {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time is before the unix epoch")
+            .as_secs();
+        Self(secs as i64)
+    }
+
+    pub const fn to_i64(&self) -> i64 { self.0 }
+
+    /// Detects whether the timestamp is within the valid range.
+    pub const fn is_valid(&self) -> bool { self.0 >= Self::MIN && self.0 <= Self::MAX }
+}
+
+impl TryFrom<i64> for Timestamp {
+    type Error = TimestampError;
+    fn try_from(secs: i64) -> Result<Self, Self::Error> { Self::new(secs) }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(timestamp: Timestamp) -> Self { timestamp.0 }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TimestampError {
+    /// timestamp {0} is before the earliest allowed date (2023-01-01).
+    TooEarly(i64),
+
+    /// timestamp {0} is too far in the future.
+    TooLate(i64),
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for commit_verify::Sha256 {}
+}
+
+/// Hash function used to compute 32-byte identifiers ([`crate::Opid`], [`crate::GenesisId`],
+/// [`crate::ContractId`], [`crate::CodexId`]).
+///
+/// The identifiers are computed by the commitment engine, which uses SHA-256; thus SHA-256 is the
+/// only implementation, and the trait is sealed.
+pub trait IdHasher: sealed::Sealed + DigestExt {
+    /// Name of the hash function.
+    const ALGORITHM: &'static str;
+
+    /// Computes tagged hash of the provided data.
+    fn digest_tagged(tag: &str, data: &[u8]) -> [u8; 32] {
+        let mut hasher = Self::from_tag(tag);
+        hasher.input_raw(data);
+        hasher.finish()
+    }
+}
+
+impl IdHasher for Sha256 {
+    const ALGORITHM: &'static str = "sha256";
+}
+
+/// Hash function used to compute identifiers.
+///
+/// This is not a configuration point: the identifiers are always computed with SHA-256, regardless
+/// of the enabled features.
+pub type IdHash = Sha256;
@@ -0,0 +1,273 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Search for genesis parameters producing contract ids with a desired prefix.
+
+use core::ops::ControlFlow;
+
+use aluvm::fe256;
+use amplify::num::u256;
+use amplify::ByteArray;
+use commit_verify::CommitId;
+
+use crate::{Contract, ContractDraft, ContractId, Timestamp, TimestampError};
+
+/// Parameters of a vanity search over the genesis nonce and the issue timestamp.
+///
+/// For each timestamp in the jitter window, starting from the requested one, the search tries
+/// `nonce_range` consecutive genesis nonces starting from `nonce_start`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct VanitySearch {
+    /// Bytes the contract id must start with.
+    pub prefix: Vec<u8>,
+    /// First genesis nonce to try.
+    ///
+    /// The nonces are used as field elements, thus `nonce_start + nonce_range` must not exceed
+    /// the codex field order; otherwise the search fails with [`VanityError::NonceOutOfRange`].
+    pub nonce_start: u256,
+    /// Number of nonces to try for each timestamp.
+    pub nonce_range: u64,
+    /// Maximal number of seconds the issue timestamp can be moved forward.
+    pub timestamp_jitter: u32,
+    /// Number of attempts between the progress reports.
+    pub report_interval: u64,
+}
+
+impl VanitySearch {
+    /// Default number of nonces tried for each timestamp.
+    pub const DEFAULT_NONCE_RANGE: u64 = 1 << 24;
+    /// Default number of attempts between the progress reports.
+    pub const DEFAULT_REPORT_INTERVAL: u64 = 1 << 16;
+
+    /// Constructs search for the given contract id prefix with the default parameters and no
+    /// timestamp jitter.
+    pub fn new(prefix: impl Into<Vec<u8>>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            nonce_start: u256::ZERO,
+            nonce_range: Self::DEFAULT_NONCE_RANGE,
+            timestamp_jitter: 0,
+            report_interval: Self::DEFAULT_REPORT_INTERVAL,
+        }
+    }
+
+    pub fn with_nonce_start(mut self, nonce: u256) -> Self {
+        self.nonce_start = nonce;
+        self
+    }
+
+    pub fn with_nonce_range(mut self, range: u64) -> Self {
+        self.nonce_range = range;
+        self
+    }
+
+    pub fn with_timestamp_jitter(mut self, secs: u32) -> Self {
+        self.timestamp_jitter = secs;
+        self
+    }
+
+    pub fn with_report_interval(mut self, interval: u64) -> Self {
+        self.report_interval = interval;
+        self
+    }
+
+    /// Maximal number of attempts the search may take.
+    pub fn max_attempts(&self) -> u64 {
+        self.nonce_range
+            .saturating_mul(self.timestamp_jitter as u64 + 1)
+    }
+}
+
+/// Progress of a vanity search reported to the callback.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct VanityProgress {
+    /// Number of contract ids computed so far.
+    pub attempts: u64,
+    /// Maximal number of attempts, see [`VanitySearch::max_attempts`].
+    pub max_attempts: u64,
+}
+
+impl ContractDraft {
+    /// Seals the draft into a contract (see [`Self::seal`]) whose id starts with the prefix
+    /// requested by the `search`.
+    ///
+    /// The codex id is computed only once, such that each attempt recomputes just the genesis and
+    /// the contract commitments. The `progress` callback is called each
+    /// [`VanitySearch::report_interval`] attempts and can cancel the search by returning
+    /// [`ControlFlow::Break`].
+    ///
+    /// # Errors
+    ///
+    /// Fails before the search starts if some of the searched nonces are not canonical field
+    /// elements under the codex field order.
+    pub fn seal_vanity<const CAPS: u32>(
+        self,
+        timestamp: Timestamp,
+        search: &VanitySearch,
+        mut progress: impl FnMut(VanityProgress) -> ControlFlow<()>,
+    ) -> Result<Contract<CAPS>, VanityError> {
+        let end = search
+            .nonce_start
+            .checked_add(u256::from(search.nonce_range));
+        if !end.is_some_and(|end| end <= self.codex.field_order) {
+            return Err(VanityError::NonceOutOfRange {
+                start: search.nonce_start,
+                range: search.nonce_range,
+                field_order: self.codex.field_order,
+            });
+        }
+
+        let interval = search.report_interval.max(1);
+        let max_attempts = search.max_attempts();
+        let mut contract = self.seal::<CAPS>(timestamp, fe256::from(search.nonce_start));
+        let codex_id = contract.codex.codex_id();
+
+        let mut attempts = 0u64;
+        for shift in 0..=search.timestamp_jitter {
+            contract.meta.timestamp = Timestamp::new(timestamp.to_i64() + shift as i64)?;
+            for offset in 0..search.nonce_range {
+                contract.genesis.nonce = fe256::from(search.nonce_start + u256::from(offset));
+                let contract_id =
+                    ContractId::compute(&contract.meta, codex_id, contract.genesis.commit_id());
+                attempts += 1;
+                if contract_id.to_byte_array().starts_with(&search.prefix) {
+                    return Ok(contract);
+                }
+                if attempts % interval == 0
+                    && progress(VanityProgress { attempts, max_attempts }).is_break()
+                {
+                    return Err(VanityError::Cancelled(attempts));
+                }
+            }
+        }
+        Err(VanityError::Exhausted(attempts))
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum VanityError {
+    /// no contract id with the requested prefix was found after {0} attempts.
+    Exhausted(u64),
+
+    /// vanity search was cancelled after {0} attempts.
+    Cancelled(u64),
+
+    /// {range} genesis nonces starting from {start} exceed the codex field order {field_order}.
+    NonceOutOfRange {
+        start: u256,
+        range: u64,
+        field_order: u256,
+    },
+
+    /// timestamp jitter window exceeds the valid timestamp range: {0}
+    #[from]
+    Timestamp(TimestampError),
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    fn timestamp() -> Timestamp { Timestamp::from_unchecked(Timestamp::MIN) }
+
+    fn draft() -> ContractDraft {
+        let mut draft = ContractDraft::strict_dumb();
+        draft.codex.field_order = crate::FIELD_ORDER_SECP;
+        draft
+    }
+
+    #[test]
+    fn finds_prefix() {
+        let expected = draft().seal::<0>(timestamp(), fe256::from(u256::from(40u8)));
+        let prefix = expected.contract_id().to_byte_array()[..1].to_vec();
+        let search = VanitySearch::new(prefix.clone()).with_nonce_range(1024);
+        let contract = draft()
+            .seal_vanity::<0>(timestamp(), &search, |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert!(contract.contract_id().to_byte_array().starts_with(&prefix));
+        assert_eq!(contract.meta.timestamp, timestamp());
+        assert_eq!(draft().seal::<0>(timestamp(), contract.genesis.nonce), contract);
+    }
+
+    #[test]
+    fn jitter() {
+        let search = VanitySearch::new([0xFF; 32])
+            .with_nonce_range(4)
+            .with_timestamp_jitter(2)
+            .with_report_interval(1);
+        let mut reports = vec![];
+        let err = draft()
+            .seal_vanity::<0>(timestamp(), &search, |p| {
+                reports.push(p);
+                ControlFlow::Continue(())
+            })
+            .unwrap_err();
+        assert_eq!(err, VanityError::Exhausted(12));
+        assert_eq!(reports.len(), 12);
+        assert_eq!(reports[11], VanityProgress { attempts: 12, max_attempts: 12 });
+
+        let late = Timestamp::from_unchecked(Timestamp::MAX);
+        assert!(matches!(
+            draft().seal_vanity::<0>(late, &search, |_| ControlFlow::Continue(())),
+            Err(VanityError::Timestamp(_))
+        ));
+    }
+
+    #[test]
+    fn nonce_out_of_range() {
+        let order = crate::FIELD_ORDER_SECP;
+        let search = VanitySearch::new([0xFF; 32]).with_nonce_start(order - u256::from(4u8));
+        let err = |search: &VanitySearch| {
+            draft()
+                .seal_vanity::<0>(timestamp(), search, |_| ControlFlow::Continue(()))
+                .unwrap_err()
+        };
+
+        let fits = search.clone().with_nonce_range(4);
+        assert_eq!(err(&fits), VanityError::Exhausted(4));
+
+        let exceeds = search.with_nonce_range(5);
+        assert_eq!(err(&exceeds), VanityError::NonceOutOfRange {
+            start: order - u256::from(4u8),
+            range: 5,
+            field_order: order,
+        });
+
+        let overflows = VanitySearch::new([0xFF; 32])
+            .with_nonce_start(u256::MAX)
+            .with_nonce_range(1);
+        assert!(matches!(err(&overflows), VanityError::NonceOutOfRange { .. }));
+    }
+
+    #[test]
+    fn cancel() {
+        let search = VanitySearch::new([0xFF; 32]).with_report_interval(10);
+        let err = draft()
+            .seal_vanity::<0>(timestamp(), &search, |_| ControlFlow::Break(()))
+            .unwrap_err();
+        assert_eq!(err, VanityError::Cancelled(10));
+    }
+}
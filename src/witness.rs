@@ -0,0 +1,307 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Well-known layouts of lock witnesses.
+//!
+//! A lock script receives the witness of the destroyed memory cell as up to four field elements
+//! (see [`crate::Input::witness`]). The module standardizes how the witnesses of common lock
+//! schemes are laid out in these elements, such that wallets of different vendors can satisfy
+//! each other's locks: a wallet looks up the layout of a lock script in a [`WitnessRegistry`] and
+//! encodes a [`StdWitness`] accordingly.
+
+use std::collections::BTreeMap;
+
+use aluvm::{fe256, LibSite};
+use amplify::num::u256;
+
+use crate::{CellLock, StateValue, LIB_NAME_ULTRASONIC};
+
+/// Well-known layout of a lock witness.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_ULTRASONIC, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[display(lowercase)]
+#[repr(u8)]
+pub enum WitnessLayout {
+    /// Single element with the preimage of a hash lock.
+    #[strict_type(dumb)]
+    Preimage = 0,
+
+    /// Two elements with a signature: the commitment to the nonce followed by the response.
+    Signature = 1,
+
+    /// Up to four elements, one per script of a [`CellLock::Threshold`] lock, in the order of
+    /// [`CellLock::sites`]; scripts which are not satisfied get a zero element.
+    Threshold = 2,
+
+    /// Three elements allowing to destroy a time-locked cell before the lock expiration: the
+    /// height or timestamp at which the bypass is claimed, followed by the signature of the
+    /// bypass key.
+    TimelockBypass = 3,
+}
+
+impl WitnessLayout {
+    /// Checks whether a witness of `len` elements may have the layout.
+    pub fn accepts_len(self, len: usize) -> bool {
+        match self {
+            WitnessLayout::Preimage => len == 1,
+            WitnessLayout::Signature => len == 2,
+            WitnessLayout::Threshold => (1..=4).contains(&len),
+            WitnessLayout::TimelockBypass => len == 3,
+        }
+    }
+}
+
+/// Lock witness in one of the well-known layouts.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StdWitness {
+    /// Witness of [`WitnessLayout::Preimage`].
+    Preimage(fe256),
+
+    /// Witness of [`WitnessLayout::Signature`].
+    Signature { nonce: fe256, response: fe256 },
+
+    /// Witness of [`WitnessLayout::Threshold`].
+    Threshold(Vec<fe256>),
+
+    /// Witness of [`WitnessLayout::TimelockBypass`].
+    TimelockBypass {
+        claimed: u64,
+        nonce: fe256,
+        response: fe256,
+    },
+}
+
+impl StdWitness {
+    /// Constructs threshold witness, placing the provided elements at the positions of the
+    /// satisfied scripts and zeros at the rest of the `count` positions.
+    pub fn threshold(count: u8, satisfied: impl IntoIterator<Item = (u8, fe256)>) -> Self {
+        let mut elements = vec![fe256::from(u256::ZERO); count.min(4) as usize];
+        for (pos, el) in satisfied {
+            if let Some(slot) = elements.get_mut(pos as usize) {
+                *slot = el;
+            }
+        }
+        StdWitness::Threshold(elements)
+    }
+
+    pub fn layout(&self) -> WitnessLayout {
+        match self {
+            StdWitness::Preimage(_) => WitnessLayout::Preimage,
+            StdWitness::Signature { .. } => WitnessLayout::Signature,
+            StdWitness::Threshold(_) => WitnessLayout::Threshold,
+            StdWitness::TimelockBypass { .. } => WitnessLayout::TimelockBypass,
+        }
+    }
+
+    /// Encodes the witness into the state value provided in [`crate::Input::witness`].
+    pub fn encode(&self) -> Result<StateValue, WitnessError> {
+        let elements = match self {
+            StdWitness::Preimage(preimage) => vec![*preimage],
+            StdWitness::Signature { nonce, response } => vec![*nonce, *response],
+            StdWitness::Threshold(elements) => elements.clone(),
+            StdWitness::TimelockBypass { claimed, nonce, response } => {
+                vec![fe256::from(u256::from(*claimed)), *nonce, *response]
+            }
+        };
+        if !self.layout().accepts_len(elements.len()) {
+            return Err(WitnessError::Len { layout: self.layout(), len: elements.len() });
+        }
+        Ok(StateValue::from(elements.into_iter().map(|el| el.to_u256())))
+    }
+
+    /// Decodes the witness with the given layout from the state value.
+    pub fn decode(layout: WitnessLayout, witness: &StateValue) -> Result<Self, WitnessError> {
+        let el = witness.elements().collect::<Vec<_>>();
+        if !layout.accepts_len(el.len()) {
+            return Err(WitnessError::Len { layout, len: el.len() });
+        }
+        Ok(match layout {
+            WitnessLayout::Preimage => StdWitness::Preimage(el[0]),
+            WitnessLayout::Signature => StdWitness::Signature { nonce: el[0], response: el[1] },
+            WitnessLayout::Threshold => StdWitness::Threshold(el),
+            WitnessLayout::TimelockBypass => {
+                let bytes = el[0].to_u256().to_le_bytes();
+                if bytes[8..].iter().any(|b| *b != 0) {
+                    return Err(WitnessError::Claimed);
+                }
+                let mut claimed = [0u8; 8];
+                claimed.copy_from_slice(&bytes[..8]);
+                StdWitness::TimelockBypass {
+                    claimed: u64::from_le_bytes(claimed),
+                    nonce: el[1],
+                    response: el[2],
+                }
+            }
+        })
+    }
+}
+
+/// Registry of the witness layouts used by lock scripts.
+///
+/// Vendors publish the layouts of their lock scripts, such that wallets registering them can
+/// construct witnesses for each other's locks.
+pub trait WitnessRegistry {
+    /// Returns the witness layout of the lock script at the `site`.
+    fn layout(&self, site: LibSite) -> Option<WitnessLayout>;
+
+    /// Returns the witness layout required to destroy a memory cell with the `lock`.
+    ///
+    /// Threshold locks always use [`WitnessLayout::Threshold`]; cells without a lock require no
+    /// witness.
+    fn lock_layout(&self, lock: CellLock) -> Option<WitnessLayout> {
+        match lock {
+            CellLock::None => None,
+            CellLock::Single(site) => self.layout(site),
+            CellLock::Threshold { .. } => Some(WitnessLayout::Threshold),
+        }
+    }
+
+    /// Encodes the witness for a memory cell with the `lock`, checking that the witness has the
+    /// layout the lock requires.
+    fn encode_witness(
+        &self,
+        lock: CellLock,
+        witness: &StdWitness,
+    ) -> Result<StateValue, WitnessError> {
+        let expected = self.lock_layout(lock).ok_or(WitnessError::Unknown)?;
+        if witness.layout() != expected {
+            return Err(WitnessError::Layout { expected, found: witness.layout() });
+        }
+        if let (StdWitness::Threshold(elements), CellLock::Threshold { .. }) = (witness, lock) {
+            if elements.len() != lock.sites().count() {
+                return Err(WitnessError::Len { layout: expected, len: elements.len() });
+            }
+        }
+        witness.encode()
+    }
+
+    /// Decodes the witness provided for a memory cell with the `lock`.
+    fn decode_witness(
+        &self,
+        lock: CellLock,
+        witness: &StateValue,
+    ) -> Result<StdWitness, WitnessError> {
+        let layout = self.lock_layout(lock).ok_or(WitnessError::Unknown)?;
+        StdWitness::decode(layout, witness)
+    }
+}
+
+impl WitnessRegistry for BTreeMap<LibSite, WitnessLayout> {
+    fn layout(&self, site: LibSite) -> Option<WitnessLayout> { self.get(&site).copied() }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum WitnessError {
+    /// the lock script has no registered witness layout.
+    Unknown,
+
+    /// the lock requires a witness with {expected} layout, while a witness with {found} layout was
+    /// provided.
+    Layout {
+        expected: WitnessLayout,
+        found: WitnessLayout,
+    },
+
+    /// witness with {layout} layout can't have {len} elements.
+    Len { layout: WitnessLayout, len: usize },
+
+    /// claimed timelock bypass height or timestamp exceeds 64 bits.
+    Claimed,
+}
+
+#[cfg(test)]
+mod test {
+    use aluvm::LibId;
+
+    use super::*;
+
+    fn fe(val: u64) -> fe256 { fe256::from(u256::from(val)) }
+
+    #[test]
+    fn roundtrip() {
+        for witness in [
+            StdWitness::Preimage(fe(1)),
+            StdWitness::Signature { nonce: fe(2), response: fe(3) },
+            StdWitness::threshold(3, [(0, fe(4)), (2, fe(5))]),
+            StdWitness::TimelockBypass { claimed: 800_000, nonce: fe(6), response: fe(7) },
+        ] {
+            let value = witness.encode().unwrap();
+            assert_eq!(StdWitness::decode(witness.layout(), &value), Ok(witness));
+        }
+        assert_eq!(
+            StdWitness::decode(WitnessLayout::Signature, &StateValue::from([u256::ONE])),
+            Err(WitnessError::Len { layout: WitnessLayout::Signature, len: 1 })
+        );
+        assert_eq!(
+            StdWitness::Threshold(vec![]).encode(),
+            Err(WitnessError::Len { layout: WitnessLayout::Threshold, len: 0 })
+        );
+    }
+
+    #[test]
+    fn registry() {
+        let preimage = LibSite::new(LibId::from([1u8; 32]), 0);
+        let signature = LibSite::new(LibId::from([2u8; 32]), 0);
+        let mut registry = BTreeMap::new();
+        registry.insert(preimage, WitnessLayout::Preimage);
+        registry.insert(signature, WitnessLayout::Signature);
+
+        let sig = StdWitness::Signature { nonce: fe(1), response: fe(2) };
+        let value = registry
+            .encode_witness(CellLock::Single(signature), &sig)
+            .unwrap();
+        assert_eq!(registry.decode_witness(CellLock::Single(signature), &value), Ok(sig.clone()));
+        assert_eq!(
+            registry.encode_witness(CellLock::Single(preimage), &sig),
+            Err(WitnessError::Layout {
+                expected: WitnessLayout::Preimage,
+                found: WitnessLayout::Signature
+            })
+        );
+        let unknown = LibSite::new(LibId::from([3u8; 32]), 0);
+        assert_eq!(
+            registry.encode_witness(CellLock::Single(unknown), &sig),
+            Err(WitnessError::Unknown)
+        );
+
+        let lock = CellLock::Threshold {
+            threshold: 1,
+            first: preimage,
+            second: signature,
+            third: None,
+            fourth: None,
+        };
+        let witness = StdWitness::threshold(2, [(1, fe(9))]);
+        let value = registry.encode_witness(lock, &witness).unwrap();
+        assert_eq!(value.get(0), Some(fe(0)));
+        assert_eq!(value.get(1), Some(fe(9)));
+        assert_eq!(
+            registry.encode_witness(lock, &StdWitness::threshold(3, [])),
+            Err(WitnessError::Len { layout: WitnessLayout::Threshold, len: 3 })
+        );
+        assert_eq!(registry.lock_layout(CellLock::None), None);
+    }
+}
@@ -0,0 +1,111 @@
+// UltraSONIC: transactional execution layer with capability-based memory access for zk-AluVM
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@ubideco.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 Laboratories for Ubiquitous Deterministic Computing (UBIDECO),
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Pool of worker threads verifying operations in parallel.
+//!
+//! The pool owns the codex and the library repository, sharing them between the worker threads,
+//! such that integrators don't need to pass non-`Send` library resolvers across threads.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::{CallError, Codex, ContractId, LibRepo, Memory, Operation, Opid, VerifiedOperation};
+
+/// Result of an operation verification performed by a [`VerifyPool`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VerifyResult {
+    /// Id of the verified operation.
+    pub opid: Opid,
+    /// Verification outcome.
+    pub result: Result<VerifiedOperation, CallError>,
+}
+
+/// Pool of worker threads verifying operations of a single contract.
+///
+/// Operations are submitted together with a handle to the contract memory (which is usually an
+/// [`Arc`] around a memory snapshot or a thread-safe store); results are reported over a channel
+/// returned by [`VerifyPool::new`] in the order of their completion, which may differ from the
+/// order of submission.
+///
+/// The pool doesn't track dependencies between the submitted operations: it is up to the caller to
+/// submit an operation only once the memory cells it accesses are present in the memory.
+pub struct VerifyPool<R: LibRepo + Send + Sync + 'static> {
+    contract_id: ContractId,
+    codex: Arc<Codex>,
+    repo: Arc<R>,
+    threads: ThreadPool,
+    sender: Sender<VerifyResult>,
+}
+
+impl<R: LibRepo + Send + Sync + 'static> VerifyPool<R> {
+    /// Constructs pool with the given number of worker threads; if `threads` is zero, the number
+    /// of threads is selected automatically.
+    ///
+    /// Returns the pool and the receiving end of the channel reporting verification results.
+    pub fn new(
+        contract_id: ContractId,
+        codex: Codex,
+        repo: R,
+        threads: usize,
+    ) -> Result<(Self, Receiver<VerifyResult>), ThreadPoolBuildError> {
+        let threads = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|no| format!("usonic-verify-{no}"))
+            .build()?;
+        let (sender, receiver) = mpsc::channel();
+        let pool = Self {
+            contract_id,
+            codex: Arc::new(codex),
+            repo: Arc::new(repo),
+            threads,
+            sender,
+        };
+        Ok((pool, receiver))
+    }
+
+    pub fn contract_id(&self) -> ContractId { self.contract_id }
+
+    pub fn codex(&self) -> &Codex { &self.codex }
+
+    /// Returns the number of worker threads in the pool.
+    pub fn threads(&self) -> usize { self.threads.current_num_threads() }
+
+    /// Submits the operation for verification against the provided `memory`, returning its id.
+    ///
+    /// The verification result is reported over the channel returned by [`VerifyPool::new`].
+    pub fn submit<M: Memory + Send + 'static>(&self, operation: Operation, memory: M) -> Opid {
+        let opid = operation.opid();
+        let contract_id = self.contract_id;
+        let codex = self.codex.clone();
+        let repo = self.repo.clone();
+        let sender = self.sender.clone();
+        self.threads.spawn(move || {
+            let result = codex.verify(contract_id, operation, &memory, repo.as_ref());
+            // The receiver may be dropped if the caller is not interested in the results anymore
+            let _ = sender.send(VerifyResult { opid, result });
+        });
+        opid
+    }
+}